@@ -1,13 +1,18 @@
+use crate::tracking::TrackingPolicy;
 use crate::uniswap::tick::Origin;
 use crate::{
-    keyer, utils, BurnEvent, Erc20Token, Events, Flashes, MintEvent, PoolSqrtPrice, Pools,
-    Positions, SnapshotPositions, SwapEvent, Tick, Transactions,
+    keyer, tracking, utils, Accounts, Arbitrages, BundlePrice, BurnEvent, DerivedEthPrices,
+    Erc20Token, Events, Flashes, JitLiquidityEvents, MintEvent, PoolCandles, PoolSqrtPrice,
+    PoolTwaps, Pools, PositionPerformances, PositionRangeEvents, PositionUncollectedFees,
+    Positions, RawPositionEvent, Sandwiches, SnapshotPositions, SwapEvent, Tick,
+    TokenTransferBehavior, Transactions,
 };
+use std::ops::{Div, Mul};
 use std::str::FromStr;
 use substreams::scalar::{BigDecimal, BigInt};
 use substreams::store::{
-    DeltaArray, DeltaBigDecimal, DeltaBigInt, DeltaProto, Deltas, StoreGet, StoreGetBigDecimal,
-    StoreGetBigInt,
+    DeltaArray, DeltaBigDecimal, DeltaBigInt, DeltaProto, DeltaString, Deltas, StoreGet,
+    StoreGetBigDecimal, StoreGetBigInt,
 };
 use substreams::Hex;
 use substreams_entity_change::pb::entity::{entity_change::Operation, EntityChange, EntityChanges};
@@ -37,6 +42,18 @@ pub fn bundle_store_eth_price_usd_bundle_entity_change(
     }
 }
 
+pub fn bundle_price_source_bundle_entity_change(
+    entity_changes: &mut EntityChanges,
+    deltas: Deltas<DeltaProto<BundlePrice>>,
+) {
+    for delta in deltas.deltas {
+        entity_changes
+            .push_change("Bundle", "1", delta.ordinal, Operation::Update)
+            .change("ethPriceSource", delta.new_value.source)
+            .change("ethPriceSourcePools", delta.new_value.pools);
+    }
+}
+
 // -------------------
 //  Map Factory Entities
 // -------------------
@@ -137,6 +154,8 @@ pub fn total_value_locked_factory_entity_change(
         let name = match delta.key.as_str().split(":").last().unwrap() {
             "totalValueLockedUSD" => "totalValueLockedUSD",
             "totalValueLockedETH" => "totalValueLockedETH",
+            "totalValueLockedUSDUntracked" => "totalValueLockedUSDUntracked",
+            "totalValueLockedETHUntracked" => "totalValueLockedETHUntracked",
             _ => continue,
         };
 
@@ -183,6 +202,9 @@ pub fn pools_created_pool_entity_change(pools: Pools, entity_changes: &mut Entit
             .change("token1Price", BigDecimal::zero())
             .change("tick", BigInt::zero())
             .change("observationIndex", BigInt::zero())
+            .change("observationCardinality", BigInt::zero())
+            .change("observationCardinalityNext", BigInt::zero())
+            .change("tickCumulative", BigInt::zero())
             .change("volumeToken0", BigDecimal::zero())
             .change("volumeToken1", BigDecimal::zero())
             .change("volumeUSD", BigDecimal::zero())
@@ -194,10 +216,15 @@ pub fn pools_created_pool_entity_change(pools: Pools, entity_changes: &mut Entit
             .change("collectedFeesUSD", BigDecimal::zero())
             .change("totalValueLockedToken0", BigDecimal::zero())
             .change("totalValueLockedToken1", BigDecimal::zero())
+            .change("balanceToken0", BigDecimal::zero())
+            .change("balanceToken1", BigDecimal::zero())
+            .change("balanceReconciliationToken0", BigDecimal::zero())
+            .change("balanceReconciliationToken1", BigDecimal::zero())
             .change("totalValueLockedETH", BigDecimal::zero())
             .change("totalValueLockedUSD", BigDecimal::zero())
             .change("totalValueLockedUSDUntracked", BigDecimal::zero())
-            .change("liquidityProviderCount", BigInt::zero());
+            .change("liquidityProviderCount", BigInt::zero())
+            .change("arbitrageVolumeUSD", BigDecimal::zero());
     }
 }
 
@@ -263,11 +290,17 @@ pub fn total_value_locked_pool_entity_change(
         if !delta.key.starts_with("pool:") {
             continue;
         }
-        let pool_address = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        let chunks: Vec<&str> = delta.key.split(":").collect();
+        // the tracked share of each token is only summed up into the token TVL
+        if chunks.len() != 3 {
+            continue;
+        }
+        let pool_address = chunks[1].to_string();
 
-        let name = match delta.key.as_str().split(":").last().unwrap() {
+        let name = match chunks[2] {
             "usd" => "totalValueLockedUSD",
             "eth" => "totalValueLockedETH",
+            "usdUntracked" => "totalValueLockedUSDUntracked",
             _ => continue,
         };
 
@@ -305,6 +338,52 @@ pub fn total_value_locked_by_token_pool_entity_change(
     }
 }
 
+pub fn pool_balances_pool_entity_change(
+    entity_changes: &mut EntityChanges,
+    deltas: Deltas<DeltaBigDecimal>,
+) {
+    for delta in deltas.deltas {
+        let pool_address = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        let name = match delta.key.as_str().split(":").last().unwrap() {
+            "token0" => "balanceToken0",
+            "token1" => "balanceToken1",
+            _ => continue,
+        };
+
+        entity_changes
+            .push_change(
+                "Pool",
+                pool_address.as_str(),
+                delta.ordinal,
+                Operation::Update,
+            )
+            .change(name, delta);
+    }
+}
+
+pub fn pool_balance_reconciliations_pool_entity_change(
+    entity_changes: &mut EntityChanges,
+    deltas: Deltas<DeltaBigDecimal>,
+) {
+    for delta in deltas.deltas {
+        let pool_address = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        let name = match delta.key.as_str().split(":").last().unwrap() {
+            "token0" => "balanceReconciliationToken0",
+            "token1" => "balanceReconciliationToken1",
+            _ => continue,
+        };
+
+        entity_changes
+            .push_change(
+                "Pool",
+                pool_address.as_str(),
+                delta.ordinal,
+                Operation::Update,
+            )
+            .change(name, delta);
+    }
+}
+
 pub fn pool_fee_growth_global_x128_entity_change(
     entity_changes: &mut EntityChanges,
     deltas: Deltas<DeltaBigInt>,
@@ -356,6 +435,58 @@ pub fn price_pool_entity_change(
     }
 }
 
+pub fn arbitrage_volume_pool_entity_change(
+    entity_changes: &mut EntityChanges,
+    deltas: Deltas<DeltaBigDecimal>,
+) {
+    for delta in deltas.deltas {
+        let pool_address = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        entity_changes
+            .push_change(
+                "Pool",
+                pool_address.as_str(),
+                delta.ordinal,
+                Operation::Update,
+            )
+            .change("arbitrageVolumeUSD", delta);
+    }
+}
+
+pub fn tick_cumulative_pool_entity_change(
+    entity_changes: &mut EntityChanges,
+    deltas: Deltas<DeltaBigInt>,
+) {
+    for delta in deltas.deltas {
+        let pool_address = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        entity_changes
+            .push_change(
+                "Pool",
+                pool_address.as_str(),
+                delta.ordinal,
+                Operation::Update,
+            )
+            .change("tickCumulative", delta);
+    }
+}
+
+pub fn observation_state_pool_entity_change(
+    entity_changes: &mut EntityChanges,
+    deltas: Deltas<DeltaBigInt>,
+) {
+    for delta in deltas.deltas {
+        let pool_address = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        let name = delta.key.as_str().split(":").last().unwrap().to_string();
+        entity_changes
+            .push_change(
+                "Pool",
+                pool_address.as_str(),
+                delta.ordinal,
+                Operation::Update,
+            )
+            .change(name.as_str(), delta);
+    }
+}
+
 pub fn tx_count_pool_entity_change(
     entity_changes: &mut EntityChanges,
     deltas: Deltas<DeltaBigInt>,
@@ -411,13 +542,12 @@ pub fn swap_volume_pool_entity_change(
 // --------------------
 //  Map Token Entities
 // --------------------
-pub fn tokens_created_token_entity_change(entity_changes: &mut EntityChanges, pools: Pools) {
-    for pool in pools.pools {
-        let token0: &Erc20Token = pool.token0.as_ref().unwrap();
-        let token1: &Erc20Token = pool.token1.as_ref().unwrap();
-
-        add_token_entity_change(entity_changes, token0, pool.log_ordinal);
-        add_token_entity_change(entity_changes, token1, pool.log_ordinal);
+pub fn tokens_created_token_entity_change(
+    entity_changes: &mut EntityChanges,
+    deltas: Deltas<DeltaProto<Erc20Token>>,
+) {
+    for delta in deltas.deltas {
+        add_token_entity_change(entity_changes, &delta.new_value, delta.ordinal);
     }
 }
 
@@ -502,6 +632,7 @@ pub fn total_value_locked_usd_token_entity_change(
 
         let name: &str = match delta.key.as_str().split(":").last().unwrap() {
             "usd" => "totalValueLockedUSD",
+            "usdUntracked" => "totalValueLockedUSDUntracked",
             _ => continue,
         };
 
@@ -542,12 +673,83 @@ pub fn derived_eth_prices_token_entity_change(
     }
 }
 
-pub fn whitelist_token_entity_change(
+pub fn token_usd_price_token_entity_change(
+    entity_changes: &mut EntityChanges,
+    deltas: Deltas<DeltaBigDecimal>,
+) {
+    for delta in deltas.deltas {
+        let token_address = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+
+        entity_changes
+            .push_change(
+                "Token",
+                token_address.as_str(),
+                delta.ordinal,
+                Operation::Update,
+            )
+            .change("priceUSD", delta);
+    }
+}
+
+pub fn derived_eth_path_token_entity_change(
+    entity_changes: &mut EntityChanges,
+    derived_eth_prices: DerivedEthPrices,
+) {
+    for derived_eth_price in derived_eth_prices.derived_eth_prices {
+        entity_changes
+            .push_change(
+                "Token",
+                derived_eth_price.token_address.as_str(),
+                derived_eth_price.ordinal,
+                Operation::Update,
+            )
+            .change("derivedETHPath", derived_eth_price.pools)
+            .change(
+                "derivedETHConfidence",
+                BigDecimal::from(derived_eth_price.confidence.unwrap()),
+            );
+    }
+}
+
+pub fn token_pools_token_entity_change(
     entity_changes: &mut EntityChanges,
     deltas: Deltas<DeltaArray<String>>,
 ) {
     for delta in deltas.deltas {
         let token_address = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+
+        match delta.key.as_str().split(":").last().unwrap() {
+            "pools" => {
+                entity_changes
+                    .push_change(
+                        "Token",
+                        token_address.as_str(),
+                        delta.ordinal,
+                        Operation::Update,
+                    )
+                    .change("poolCount", BigInt::from(delta.new_value.len() as u64));
+            }
+            "whitelistPools" => {
+                entity_changes
+                    .push_change(
+                        "Token",
+                        token_address.as_str(),
+                        delta.ordinal,
+                        Operation::Update,
+                    )
+                    .change("whitelistPools", delta);
+            }
+            _ => continue,
+        }
+    }
+}
+
+pub fn total_supply_token_entity_change(
+    entity_changes: &mut EntityChanges,
+    deltas: Deltas<DeltaBigInt>,
+) {
+    for delta in deltas.deltas {
+        let token_address = delta.key.as_str().split(":").nth(1).unwrap().to_string();
         entity_changes
             .push_change(
                 "Token",
@@ -555,7 +757,24 @@ pub fn whitelist_token_entity_change(
                 delta.ordinal,
                 Operation::Update,
             )
-            .change("whitelistPools", delta);
+            .change("totalSupply", delta);
+    }
+}
+
+pub fn transfer_behavior_token_entity_change(
+    entity_changes: &mut EntityChanges,
+    deltas: Deltas<DeltaProto<TokenTransferBehavior>>,
+) {
+    for delta in deltas.deltas {
+        let token_address = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        entity_changes
+            .push_change(
+                "Token",
+                token_address.as_str(),
+                delta.ordinal,
+                Operation::Update,
+            )
+            .change("transferBehavior", delta.new_value.type_name());
     }
 }
 
@@ -589,7 +808,9 @@ fn add_token_entity_change(
         .change("totalValueLockedUSD", BigDecimal::zero())
         .change("totalValueLockedUSDUntracked", BigDecimal::zero())
         .change("derivedETH", BigDecimal::zero())
-        .change("whitelistPools", token.whitelist_pools.clone());
+        .change("priceUSD", BigDecimal::zero())
+        .change("whitelistPools", token.whitelist_pools.clone())
+        .change("transferBehavior", "STANDARD".to_string());
 }
 
 // --------------------
@@ -706,6 +927,24 @@ fn update_tick_entity_change(entity_changes: &mut EntityChanges, old_tick: Tick,
         );
 }
 
+pub fn liquidity_provider_count_entity_change(
+    entity_changes: &mut EntityChanges,
+    deltas: Deltas<DeltaBigInt>,
+    key_prefix: &str,
+    entity_name: &str,
+) {
+    for delta in deltas.deltas {
+        if delta.key.as_str().split(":").nth(0).unwrap() != key_prefix {
+            continue;
+        }
+
+        let id = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        entity_changes
+            .push_change(entity_name, id.as_str(), delta.ordinal, Operation::Update)
+            .change("liquidityProviderCount", delta);
+    }
+}
+
 // --------------------
 //  Map Position Entities
 // --------------------
@@ -732,6 +971,9 @@ pub fn position_create_entity_change(positions: Positions, entity_changes: &mut
             .change("withdrawnToken1", BigDecimal::zero())
             .change("collectedFeesToken0", BigDecimal::zero())
             .change("collectedFeesToken1", BigDecimal::zero())
+            .change("uncollectedFeesToken0", BigDecimal::zero())
+            .change("uncollectedFeesToken1", BigDecimal::zero())
+            .change("uncollectedFeesUSD", BigDecimal::zero())
             .change("transaction", position.transaction)
             .change(
                 "feeGrowthInside0LastX128",
@@ -775,6 +1017,218 @@ pub fn positions_changes_entity_change(
     }
 }
 
+pub fn uncollected_fees_position_entity_change(
+    position_uncollected_fees: PositionUncollectedFees,
+    entity_changes: &mut EntityChanges,
+) {
+    for uncollected_fee in position_uncollected_fees.position_uncollected_fees {
+        entity_changes
+            .push_change(
+                "Position",
+                uncollected_fee.position_id.as_str(),
+                uncollected_fee.log_ordinal,
+                Operation::Update,
+            )
+            .change(
+                "uncollectedFeesToken0",
+                BigDecimal::from(uncollected_fee.uncollected_fees_token0.unwrap()),
+            )
+            .change(
+                "uncollectedFeesToken1",
+                BigDecimal::from(uncollected_fee.uncollected_fees_token1.unwrap()),
+            )
+            .change(
+                "uncollectedFeesUSD",
+                BigDecimal::from(uncollected_fee.uncollected_fees_usd.unwrap()),
+            );
+    }
+}
+
+pub fn range_events_position_entity_change(
+    position_range_events: PositionRangeEvents,
+    entity_changes: &mut EntityChanges,
+) {
+    for range_event in position_range_events.position_range_events {
+        entity_changes
+            .push_change(
+                "PositionRangeEvent",
+                range_event.id.as_str(),
+                range_event.log_ordinal,
+                Operation::Create,
+            )
+            .change("id", range_event.id.clone())
+            .change("position", range_event.position_id.clone())
+            .change("pool", range_event.pool.clone())
+            .change("type", range_event.type_name())
+            .change("tick", BigInt::from(range_event.tick.clone().unwrap()))
+            .change("blockNumber", BigInt::from(range_event.block_number))
+            .change("timestamp", BigInt::from(range_event.timestamp));
+
+        entity_changes
+            .push_change(
+                "Position",
+                range_event.position_id.as_str(),
+                range_event.log_ordinal,
+                Operation::Update,
+            )
+            .change("inRange", range_event.is_entered());
+    }
+}
+
+pub fn time_in_range_position_entity_change(
+    entity_changes: &mut EntityChanges,
+    deltas: Deltas<DeltaBigInt>,
+) {
+    for delta in deltas.deltas {
+        let position_id = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        entity_changes
+            .push_change(
+                "Position",
+                position_id.as_str(),
+                delta.ordinal,
+                Operation::Update,
+            )
+            .change("timeInRange", delta);
+    }
+}
+
+// --------------------
+//  Map Raw Position Entities
+// --------------------
+pub fn raw_position_create_entity_change(
+    deltas: Deltas<DeltaProto<RawPositionEvent>>,
+    entity_changes: &mut EntityChanges,
+) {
+    for delta in deltas.deltas {
+        let raw_position = delta.new_value;
+        let tick_lower = BigInt::from(raw_position.tick_lower.unwrap());
+        let tick_upper = BigInt::from(raw_position.tick_upper.unwrap());
+        entity_changes
+            .push_change(
+                "RawPosition",
+                raw_position.id.clone().as_str(),
+                delta.ordinal,
+                Operation::Create,
+            )
+            .change("id", raw_position.id)
+            .change("owner", raw_position.owner.into_bytes())
+            .change("pool", raw_position.pool.clone())
+            .change("token0", raw_position.token0)
+            .change("token1", raw_position.token1)
+            .change("tickLower", format!("{}#{}", raw_position.pool, tick_lower))
+            .change("tickUpper", format!("{}#{}", raw_position.pool, tick_upper))
+            .change("liquidity", BigDecimal::zero())
+            .change("depositedToken0", BigDecimal::zero())
+            .change("depositedToken1", BigDecimal::zero())
+            .change("withdrawnToken0", BigDecimal::zero())
+            .change("withdrawnToken1", BigDecimal::zero())
+            .change("collectedFeesToken0", BigDecimal::zero())
+            .change("collectedFeesToken1", BigDecimal::zero())
+            .change("transaction", raw_position.transaction);
+    }
+}
+
+pub fn raw_position_changes_entity_change(
+    entity_changes: &mut EntityChanges,
+    deltas: Deltas<DeltaBigDecimal>,
+) {
+    for delta in deltas.deltas {
+        let raw_position_id = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+
+        let name = match delta.key.as_str().split(":").last().unwrap() {
+            "liquidity" => "liquidity",
+            "depositedToken0" => "depositedToken0",
+            "depositedToken1" => "depositedToken1",
+            "withdrawnToken0" => "withdrawnToken0",
+            "withdrawnToken1" => "withdrawnToken1",
+            "collectedFeesToken0" => "collectedFeesToken0",
+            "collectedFeesToken1" => "collectedFeesToken1",
+            _ => continue,
+        };
+
+        entity_changes
+            .push_change(
+                "RawPosition",
+                raw_position_id.as_str(),
+                delta.ordinal,
+                Operation::Update,
+            )
+            .change(name, delta);
+    }
+}
+
+// --------------------
+//  Map Account Entities
+// --------------------
+pub fn account_entity_change(accounts: Accounts, entity_changes: &mut EntityChanges) {
+    for account in accounts.accounts {
+        entity_changes
+            .push_change(
+                "Account",
+                account.id.clone().as_str(),
+                account.log_ordinal,
+                Operation::Create,
+            )
+            .change("id", account.id)
+            .change("positionCount", BigInt::from(account.position_count))
+            .change(
+                "activePositionCount",
+                BigInt::from(account.active_position_count),
+            )
+            .change(
+                "netDepositedUSD",
+                BigDecimal::from(account.net_deposited_usd.unwrap()),
+            )
+            .change(
+                "depositedUSD",
+                BigDecimal::from(account.deposited_usd.unwrap()),
+            )
+            .change(
+                "withdrawnUSD",
+                BigDecimal::from(account.withdrawn_usd.unwrap()),
+            )
+            .change(
+                "collectedFeesUSD",
+                BigDecimal::from(account.collected_fees_usd.unwrap()),
+            );
+    }
+}
+
+pub fn account_position_entity_change(
+    account_active_positions_deltas: Deltas<DeltaString>,
+    entity_changes: &mut EntityChanges,
+) {
+    for delta in account_active_positions_deltas.deltas {
+        let owner = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        let position_id = delta.key.as_str().split(":").nth(3).unwrap().to_string();
+        let id = format!("{}#{}", owner, position_id);
+
+        match delta.operation {
+            substreams::pb::substreams::store_delta::Operation::Create => {
+                entity_changes
+                    .push_change(
+                        "AccountPosition",
+                        id.as_str(),
+                        delta.ordinal,
+                        Operation::Create,
+                    )
+                    .change("id", id.clone())
+                    .change("account", owner)
+                    .change("position", position_id);
+            }
+            substreams::pb::substreams::store_delta::Operation::Delete => {
+                entity_changes.push_change(
+                    "AccountPosition",
+                    id.as_str(),
+                    delta.ordinal,
+                    Operation::Delete,
+                );
+            }
+            _ => continue,
+        }
+    }
+}
+
 // --------------------
 //  Map Snapshot Position Entities
 // --------------------
@@ -832,7 +1286,63 @@ pub fn snapshot_position_entity_change(
             .change(
                 "feeGrowthInside1LastX128",
                 BigInt::from(snapshot_position.fee_growth_inside_1_last_x_128.unwrap()),
-            );
+            )
+            .change("currentValueUSD", BigDecimal::zero())
+            .change("hodlValueUSD", BigDecimal::zero())
+            .change("impermanentLossUSD", BigDecimal::zero())
+            .change("realizedFeesUSD", BigDecimal::zero())
+            .change("pnlUSD", BigDecimal::zero())
+            .change("timeInPosition", BigInt::zero())
+            .change("feeAPR", BigDecimal::zero());
+    }
+}
+
+pub fn position_performance_entity_change(
+    position_performances: PositionPerformances,
+    entity_changes: &mut EntityChanges,
+) {
+    for performance in position_performances.position_performances {
+        let current_value_usd = BigDecimal::from(performance.current_value_usd.unwrap());
+        let hodl_value_usd = BigDecimal::from(performance.hodl_value_usd.unwrap());
+        let impermanent_loss_usd = BigDecimal::from(performance.impermanent_loss_usd.unwrap());
+        let realized_fees_usd = BigDecimal::from(performance.realized_fees_usd.unwrap());
+        let pnl_usd = BigDecimal::from(performance.pnl_usd.unwrap());
+        let fee_apr = BigDecimal::from(performance.fee_apr.unwrap());
+
+        entity_changes
+            .push_change(
+                "PositionSnapshot",
+                performance.snapshot.as_str(),
+                performance.log_ordinal,
+                Operation::Update,
+            )
+            .change("currentValueUSD", current_value_usd.clone())
+            .change("hodlValueUSD", hodl_value_usd.clone())
+            .change("impermanentLossUSD", impermanent_loss_usd.clone())
+            .change("realizedFeesUSD", realized_fees_usd.clone())
+            .change("pnlUSD", pnl_usd.clone())
+            .change("timeInPosition", BigInt::from(performance.time_in_position))
+            .change("feeAPR", fee_apr.clone());
+
+        entity_changes
+            .push_change(
+                "PositionPerformance",
+                performance.id.as_str(),
+                performance.log_ordinal,
+                Operation::Create,
+            )
+            .change("id", performance.id.clone())
+            .change("position", performance.id)
+            .change("lastSnapshot", performance.snapshot)
+            .change("blockNumber", BigInt::from(performance.block_number))
+            .change("timestamp", BigInt::from(performance.timestamp))
+            .change("currentValueUSD", current_value_usd)
+            .change("hodlValueUSD", hodl_value_usd)
+            .change("impermanentLossUSD", impermanent_loss_usd)
+            .change("realizedFeesUSD", realized_fees_usd)
+            .change("pnlUSD", pnl_usd)
+            .change("timeInPosition", BigInt::from(performance.time_in_position))
+            .change("feeAPR", fee_apr);
     }
 }
 
@@ -866,8 +1376,10 @@ pub fn transaction_entity_change(transactions: Transactions, entity_changes: &mu
 // --------------------
 pub fn swaps_mints_burns_created_entity_change(
     events: Events,
+    tracking_policy: TrackingPolicy,
     tx_count_store: StoreGetBigInt,
     store_eth_prices: StoreGetBigDecimal,
+    native_total_value_locked_store: StoreGetBigDecimal,
     entity_changes: &mut EntityChanges,
 ) {
     for event in events.events {
@@ -908,18 +1420,45 @@ pub fn swaps_mints_burns_created_entity_change(
                     Some(price) => price,
                 };
 
+            let pool_eth_locked = tracking::pool_eth_locked(
+                event.log_ordinal,
+                &event.pool_address,
+                &event.token0,
+                &event.token1,
+                &native_total_value_locked_store,
+                &store_eth_prices,
+            );
+            let (token0_id, token1_id) = (event.token0.clone(), event.token1.clone());
+            let tracked_amount_usd = |amount0: &BigDecimal, amount1: &BigDecimal| -> BigDecimal {
+                let absolute = |amount: &BigDecimal| -> BigDecimal {
+                    match amount.lt(&BigDecimal::zero()) {
+                        true => amount.clone().mul(BigDecimal::from(-1 as i64)),
+                        false => amount.clone(),
+                    }
+                };
+                tracking_policy.tracked_amount_usd(
+                    &token0_id,
+                    &token1_id,
+                    &absolute(amount0),
+                    &absolute(amount1),
+                    &token0_derived_eth_price
+                        .clone()
+                        .mul(bundle_eth_price.clone()),
+                    &token1_derived_eth_price
+                        .clone()
+                        .mul(bundle_eth_price.clone()),
+                    &pool_eth_locked,
+                )
+            };
+
             return match event.r#type.unwrap() {
                 SwapEvent(swap) => {
                     let amount0: BigDecimal = BigDecimal::from(swap.amount_0.unwrap());
                     let amount1: BigDecimal = BigDecimal::from(swap.amount_1.unwrap());
 
-                    let amount_usd: BigDecimal = utils::calculate_amount_usd(
-                        &amount0,
-                        &amount1,
-                        &token0_derived_eth_price,
-                        &token1_derived_eth_price,
-                        &bundle_eth_price,
-                    );
+                    // one side goes in and the other out, so the traded value is half of both
+                    let amount_usd: BigDecimal =
+                        tracked_amount_usd(&amount0, &amount1).div(BigDecimal::from(2 as i32));
 
                     entity_changes
                         .push_change(
@@ -949,13 +1488,7 @@ pub fn swaps_mints_burns_created_entity_change(
                     let amount0: BigDecimal = BigDecimal::from(mint.amount_0.unwrap());
                     let amount1: BigDecimal = BigDecimal::from(mint.amount_1.unwrap());
 
-                    let amount_usd: BigDecimal = utils::calculate_amount_usd(
-                        &amount0,
-                        &amount1,
-                        &token0_derived_eth_price,
-                        &token1_derived_eth_price,
-                        &bundle_eth_price,
-                    );
+                    let amount_usd: BigDecimal = tracked_amount_usd(&amount0, &amount1);
 
                     entity_changes
                         .push_change(
@@ -986,13 +1519,7 @@ pub fn swaps_mints_burns_created_entity_change(
                     let amount0: BigDecimal = BigDecimal::from(burn.amount_0.unwrap());
                     let amount1: BigDecimal = BigDecimal::from(burn.amount_1.unwrap());
 
-                    let amount_usd: BigDecimal = utils::calculate_amount_usd(
-                        &amount0,
-                        &amount1,
-                        &token0_derived_eth_price,
-                        &token1_derived_eth_price,
-                        &bundle_eth_price,
-                    );
+                    let amount_usd: BigDecimal = tracked_amount_usd(&amount0, &amount1);
 
                     entity_changes
                         .push_change(
@@ -1134,3 +1661,312 @@ pub fn uniswap_day_data_volumes_entity_change(
             .change(name, delta);
     }
 }
+
+// --------------------
+//  Map Interface Day Data Entities
+// --------------------
+pub fn interface_day_data_swap_count_entity_change(
+    entity_changes: &mut EntityChanges,
+    deltas: Deltas<DeltaBigInt>,
+) {
+    for delta in deltas.deltas {
+        if !delta.key.starts_with("interface_day_data") {
+            continue;
+        }
+
+        let day_id: i64 = delta
+            .key
+            .as_str()
+            .split(":")
+            .nth(1)
+            .unwrap()
+            .parse::<i64>()
+            .unwrap();
+        let interface = delta.key.as_str().split(":").nth(2).unwrap().to_string();
+        let day_start_timestamp = (day_id * 86400) as i32;
+        let id = format!("{}-{}", interface, day_id);
+
+        entity_changes
+            .push_change(
+                "InterfaceDayData",
+                id.as_str(),
+                delta.ordinal,
+                Operation::Update,
+            )
+            .change("id", id.clone())
+            .change("interface", interface)
+            .change("date", day_start_timestamp)
+            .change("swapCount", delta);
+    }
+}
+
+pub fn interface_day_data_volume_entity_change(
+    entity_changes: &mut EntityChanges,
+    deltas: Deltas<DeltaBigDecimal>,
+) {
+    for delta in deltas.deltas {
+        if !delta.key.starts_with("interface_day_data") {
+            continue;
+        }
+
+        let day_id = delta.key.as_str().split(":").nth(1).unwrap();
+        let interface = delta.key.as_str().split(":").nth(2).unwrap();
+        let id = format!("{}-{}", interface, day_id);
+
+        entity_changes
+            .push_change(
+                "InterfaceDayData",
+                id.as_str(),
+                delta.ordinal,
+                Operation::Update,
+            )
+            .change("volumeUSD", delta);
+    }
+}
+
+// --------------------
+//  Map Sandwich Entities
+// --------------------
+pub fn sandwich_entity_change(sandwiches: Sandwiches, entity_changes: &mut EntityChanges) {
+    for sandwich in sandwiches.sandwiches {
+        entity_changes
+            .push_change(
+                "Sandwich",
+                sandwich.id.clone().as_str(),
+                sandwich.log_ordinal,
+                Operation::Create,
+            )
+            .change("id", sandwich.id)
+            .change("pool", sandwich.pool)
+            .change("attacker", sandwich.attacker.into_bytes())
+            .change("frontRunTransaction", sandwich.front_run_transaction)
+            .change("backRunTransaction", sandwich.back_run_transaction)
+            .change("victimTransactions", sandwich.victim_transactions)
+            .change("tokenIn", sandwich.token_in)
+            .change("tokenOut", sandwich.token_out)
+            .change(
+                "attackerProfit",
+                BigDecimal::from(sandwich.attacker_profit.unwrap()),
+            )
+            .change(
+                "attackerProfitUSD",
+                BigDecimal::from(sandwich.attacker_profit_usd.unwrap()),
+            )
+            .change(
+                "victimLoss",
+                BigDecimal::from(sandwich.victim_loss.unwrap()),
+            )
+            .change(
+                "victimLossUSD",
+                BigDecimal::from(sandwich.victim_loss_usd.unwrap()),
+            )
+            .change("blockNumber", BigInt::from(sandwich.block_number))
+            .change("timestamp", BigInt::from(sandwich.timestamp));
+    }
+}
+
+// --------------------
+//  Map Jit Liquidity Entities
+// --------------------
+pub fn jit_liquidity_event_entity_change(
+    jit_liquidity_events: JitLiquidityEvents,
+    entity_changes: &mut EntityChanges,
+) {
+    for jit in jit_liquidity_events.jit_liquidity_events {
+        let tick_lower = BigInt::from(jit.tick_lower.unwrap());
+        let tick_upper = BigInt::from(jit.tick_upper.unwrap());
+        entity_changes
+            .push_change(
+                "JitLiquidityEvent",
+                jit.id.clone().as_str(),
+                jit.log_ordinal,
+                Operation::Create,
+            )
+            .change("id", jit.id)
+            .change("pool", jit.pool.clone())
+            .change("owner", jit.owner.into_bytes())
+            .change("positionId", jit.position_id)
+            .change("tickLower", format!("{}#{}", jit.pool, tick_lower))
+            .change("tickUpper", format!("{}#{}", jit.pool, tick_upper))
+            .change("liquidity", BigInt::from(jit.liquidity.unwrap()))
+            .change("mintTransaction", jit.mint_transaction)
+            .change("burnTransaction", jit.burn_transaction)
+            .change("swapCount", BigInt::from(jit.swap_count))
+            .change(
+                "swapVolumeUSD",
+                BigDecimal::from(jit.swap_volume_usd.unwrap()),
+            )
+            .change("feesToken0", BigDecimal::from(jit.fees_token0.unwrap()))
+            .change("feesToken1", BigDecimal::from(jit.fees_token1.unwrap()))
+            .change("feesUSD", BigDecimal::from(jit.fees_usd.unwrap()))
+            .change(
+                "passiveFeesUSD",
+                BigDecimal::from(jit.passive_fees_usd.unwrap()),
+            )
+            .change("blockNumber", BigInt::from(jit.block_number))
+            .change("timestamp", BigInt::from(jit.timestamp));
+    }
+}
+
+// --------------------
+//  Map Arbitrage Entities
+// --------------------
+pub fn arbitrage_entity_change(arbitrages: Arbitrages, entity_changes: &mut EntityChanges) {
+    for arbitrage in arbitrages.arbitrages {
+        entity_changes
+            .push_change(
+                "Arbitrage",
+                arbitrage.id.clone().as_str(),
+                arbitrage.log_ordinal,
+                Operation::Create,
+            )
+            .change("id", arbitrage.id)
+            .change("transaction", arbitrage.transaction_id)
+            .change("arbitrageur", arbitrage.arbitrageur.into_bytes())
+            .change("pools", arbitrage.pools)
+            .change("path", arbitrage.path)
+            .change("token", arbitrage.token)
+            .change("amountIn", BigDecimal::from(arbitrage.amount_in.unwrap()))
+            .change("amountOut", BigDecimal::from(arbitrage.amount_out.unwrap()))
+            .change("profit", BigDecimal::from(arbitrage.profit.unwrap()))
+            .change("profitUSD", BigDecimal::from(arbitrage.profit_usd.unwrap()))
+            .change("volumeUSD", BigDecimal::from(arbitrage.volume_usd.unwrap()))
+            .change("gasUsed", BigInt::from(arbitrage.gas_used))
+            .change("gasPrice", BigInt::from(arbitrage.gas_price.unwrap()))
+            .change(
+                "gasCostETH",
+                BigDecimal::from(arbitrage.gas_cost_eth.unwrap()),
+            )
+            .change(
+                "gasCostUSD",
+                BigDecimal::from(arbitrage.gas_cost_usd.unwrap()),
+            )
+            .change(
+                "netProfitUSD",
+                BigDecimal::from(arbitrage.net_profit_usd.unwrap()),
+            )
+            .change("blockNumber", BigInt::from(arbitrage.block_number))
+            .change("timestamp", BigInt::from(arbitrage.timestamp));
+    }
+}
+
+pub fn pool_candle_entity_change(pool_candles: PoolCandles, entity_changes: &mut EntityChanges) {
+    for pool_candle in pool_candles.pool_candles {
+        entity_changes
+            .push_change(
+                "PoolCandle",
+                pool_candle.id.clone().as_str(),
+                0,
+                Operation::Create,
+            )
+            .change("id", pool_candle.id)
+            .change("pool", pool_candle.pool)
+            .change("interval", pool_candle.interval)
+            .change("periodStart", BigInt::from(pool_candle.period_start))
+            .change(
+                "token0PriceOpen",
+                BigDecimal::from(pool_candle.token0_price_open.unwrap()),
+            )
+            .change(
+                "token0PriceHigh",
+                BigDecimal::from(pool_candle.token0_price_high.unwrap()),
+            )
+            .change(
+                "token0PriceLow",
+                BigDecimal::from(pool_candle.token0_price_low.unwrap()),
+            )
+            .change(
+                "token0PriceClose",
+                BigDecimal::from(pool_candle.token0_price_close.unwrap()),
+            )
+            .change(
+                "token1PriceOpen",
+                BigDecimal::from(pool_candle.token1_price_open.unwrap()),
+            )
+            .change(
+                "token1PriceHigh",
+                BigDecimal::from(pool_candle.token1_price_high.unwrap()),
+            )
+            .change(
+                "token1PriceLow",
+                BigDecimal::from(pool_candle.token1_price_low.unwrap()),
+            )
+            .change(
+                "token1PriceClose",
+                BigDecimal::from(pool_candle.token1_price_close.unwrap()),
+            )
+            .change(
+                "volumeToken0",
+                BigDecimal::from(pool_candle.volume_token0.unwrap()),
+            )
+            .change(
+                "volumeToken1",
+                BigDecimal::from(pool_candle.volume_token1.unwrap()),
+            )
+            .change(
+                "volumeUSD",
+                BigDecimal::from(pool_candle.volume_usd.unwrap()),
+            )
+            .change("tradeCount", BigInt::from(pool_candle.trade_count));
+    }
+}
+
+pub fn pool_twap_entity_change(pool_twaps: PoolTwaps, entity_changes: &mut EntityChanges) {
+    for pool_twap in pool_twaps.pool_twaps {
+        let id = format!("{}-{}", pool_twap.pool_address, pool_twap.window);
+        entity_changes
+            .push_change(
+                "PoolTwap",
+                id.as_str(),
+                pool_twap.ordinal,
+                Operation::Create,
+            )
+            .change("id", id.clone())
+            .change("pool", pool_twap.pool_address)
+            .change("window", pool_twap.window)
+            .change("meanTick", BigInt::from(pool_twap.mean_tick))
+            .change(
+                "token0Price",
+                BigDecimal::from(pool_twap.token0_price.unwrap()),
+            )
+            .change(
+                "token1Price",
+                BigDecimal::from(pool_twap.token1_price.unwrap()),
+            )
+            .change("updatedAtTimestamp", BigInt::from(pool_twap.timestamp));
+    }
+}
+
+pub fn price_anomaly_entity_change(
+    price_anomalies: PriceAnomalies,
+    entity_changes: &mut EntityChanges,
+) {
+    for price_anomaly in price_anomalies.price_anomalies {
+        entity_changes
+            .push_change(
+                "PriceAnomaly",
+                price_anomaly.id.clone().as_str(),
+                price_anomaly.ordinal,
+                Operation::Create,
+            )
+            .change("id", price_anomaly.id.clone())
+            .change("token", price_anomaly.token_address.clone())
+            .change("reason", price_anomaly.reason_name())
+            .change(
+                "rejectedETHPrice",
+                BigDecimal::from(price_anomaly.rejected_eth_price.unwrap()),
+            )
+            .change(
+                "referenceETHPrice",
+                BigDecimal::from(price_anomaly.reference_eth_price.unwrap()),
+            )
+            .change(
+                "deviation",
+                BigDecimal::from(price_anomaly.deviation.unwrap()),
+            )
+            .change("confirmations", BigInt::from(price_anomaly.confirmations))
+            .change("pools", price_anomaly.pools)
+            .change("blockNumber", BigInt::from(price_anomaly.block_number))
+            .change("timestamp", BigInt::from(price_anomaly.timestamp));
+    }
+}
@@ -8,6 +8,27 @@ pub fn factory_pool_count_key() -> String {
     format!("factory:poolCount")
 }
 
+// ------------------------------------------------
+//      store_known_tokens / store_tokens
+// ------------------------------------------------
+pub fn token_key(token_address: &String) -> String {
+    format!("token:{}", token_address)
+}
+
+// ------------------------------------------------
+//      store_token_total_supply
+// ------------------------------------------------
+pub fn token_total_supply(token_address: &String) -> String {
+    format!("token:{}:totalSupply", token_address)
+}
+
+// ------------------------------------------------
+//      store_token_transfer_behaviors
+// ------------------------------------------------
+pub fn token_transfer_behavior(token_address: &String) -> String {
+    format!("token:{}:transferBehavior", token_address)
+}
+
 // ------------------------------------------------
 //      store_pools
 // ------------------------------------------------
@@ -34,10 +55,19 @@ pub fn generate_tokens_key(token0: &str, token1: &str) -> String {
 }
 
 // ------------------------------------------------
-//      store_tokens_whitelist_pools
+//      store_token_pools
 // ------------------------------------------------
-pub fn token_pool_whitelist(token_address: &String) -> String {
-    format!("token:{}", token_address)
+pub fn token_pools(token_address: &String) -> String {
+    format!("token:{}:pools", token_address)
+}
+
+// <pool address>:<counter token address> of every pool of the token
+pub fn token_pairs(token_address: &String) -> String {
+    format!("token:{}:pairs", token_address)
+}
+
+pub fn token_whitelist_pools(token_address: &String) -> String {
+    format!("token:{}:whitelistPools", token_address)
 }
 
 // ------------------------------------------------
@@ -80,6 +110,19 @@ pub fn factory_total_value_locked_usd() -> String {
     format!("factory:totalValueLockedUSD")
 }
 
+pub fn factory_total_value_locked_eth_untracked() -> String {
+    format!("factory:totalValueLockedETHUntracked")
+}
+
+pub fn factory_total_value_locked_usd_untracked() -> String {
+    format!("factory:totalValueLockedUSDUntracked")
+}
+
+// tracked, summed over the pools holding the token
+pub fn token_usd_total_value_locked(token_address: &String) -> String {
+    format!("token:{}:usd", token_address)
+}
+
 pub fn uniswap_total_value_locked_usd(day_id: String) -> String {
     format!("uniswap_day_data:{}:totalValueLockedUSD", day_id)
 }
@@ -94,8 +137,8 @@ pub fn pool_fee_growth_global_x128(pool_address: &String, token: String) -> Stri
 // ------------------------------------------------
 //      store_total_value_locked
 // ------------------------------------------------
-pub fn token_usd_total_value_locked(token_address: &String) -> String {
-    format!("token:{}:usd", token_address)
+pub fn token_usd_untracked_total_value_locked(token_address: &String) -> String {
+    format!("token:{}:usdUntracked", token_address)
 }
 
 pub fn pool_eth_total_value_locked(pool_address: &String) -> String {
@@ -106,6 +149,19 @@ pub fn pool_usd_total_value_locked(pool_address: &String) -> String {
     format!("pool:{}:usd", pool_address)
 }
 
+pub fn pool_eth_untracked_total_value_locked(pool_address: &String) -> String {
+    format!("pool:{}:ethUntracked", pool_address)
+}
+
+pub fn pool_usd_untracked_total_value_locked(pool_address: &String) -> String {
+    format!("pool:{}:usdUntracked", pool_address)
+}
+
+// the tracked USD share of a token in the pool
+pub fn pool_token_usd_total_value_locked(pool_address: &String, token_address: &String) -> String {
+    format!("pool:{}:{}:trackedUsd", pool_address, token_address)
+}
+
 pub fn native_token_from_key(key: &String) -> Option<String> {
     let chunks: Vec<&str> = key.split(":").collect();
     if chunks.len() != 3 {
@@ -160,6 +216,24 @@ pub fn total_value_locked_by_tokens(
     format!("pool:{}:{}:{}", pool_address, token_address, token)
 }
 
+// ------------------------------------------------
+//      store_pool_balances
+// ------------------------------------------------
+pub fn pool_balance(pool_address: &String, token_address: &String, token: String) -> String {
+    format!("pool:{}:{}:{}", pool_address, token_address, token)
+}
+
+// ------------------------------------------------
+//      store_pool_balance_reconciliations
+// ------------------------------------------------
+pub fn pool_balance_reconciliation(
+    pool_address: &String,
+    token_address: &String,
+    token: String,
+) -> String {
+    format!("pool:{}:{}:{}", pool_address, token_address, token)
+}
+
 // ------------------------------------------------
 //      store_derived_eth_prices
 // ------------------------------------------------
@@ -261,6 +335,68 @@ pub fn swap_uniswap_day_data_fees_usd(day_id: String) -> String {
     format!("uniswap_day_data:{}:feesUSD", day_id)
 }
 
+// ------------------------------------------------
+//      store_interface_day_data
+// ------------------------------------------------
+pub fn interface_day_data_volume_usd(day_id: String, interface: &String) -> String {
+    format!("interface_day_data:{}:{}:volumeUSD", day_id, interface)
+}
+
+pub fn interface_day_data_swap_count(day_id: String, interface: &String) -> String {
+    format!("interface_day_data:{}:{}:swapCount", day_id, interface)
+}
+
+// ------------------------------------------------
+//      store_candle_*
+// ------------------------------------------------
+pub fn candles_timestamp() -> String {
+    "candles:timestamp".to_string()
+}
+
+pub fn candle_prefix(interval: &String, period_start: u64) -> String {
+    format!("candle:{}:{}:", interval, period_start)
+}
+
+pub fn candle(interval: &String, period_start: u64, pool_address: &String) -> String {
+    format!("candle:{}:{}:{}", interval, period_start, pool_address)
+}
+
+pub fn candle_pools(interval: &String, period_start: u64) -> String {
+    format!("candle:{}:{}:pools", interval, period_start)
+}
+
+pub fn candle_volume(candle_key: &String, name: &str) -> String {
+    format!("{}:{}", candle_key, name)
+}
+
+// ------------------------------------------------
+//      store_pool_tick_* / store_pool_observations / store_pool_twaps
+// ------------------------------------------------
+pub fn pool_tick_timestamp(pool_address: &String) -> String {
+    format!("pool:{}:tickTimestamp", pool_address)
+}
+
+pub fn pool_tick_cumulative(pool_address: &String) -> String {
+    format!("pool:{}:tickCumulative", pool_address)
+}
+
+pub fn pool_observations_prefix(day_id: i64) -> String {
+    format!("observations:{}:", day_id)
+}
+
+pub fn pool_observations(day_id: i64, pool_address: &String) -> String {
+    format!("observations:{}:{}", day_id, pool_address)
+}
+
+// observationIndex, observationCardinality or observationCardinalityNext
+pub fn pool_observation_state(pool_address: &String, name: &str) -> String {
+    format!("pool:{}:{}", pool_address, name)
+}
+
+pub fn pool_twap(pool_address: &String, window: &str, name: &str) -> String {
+    format!("pool:{}:twap:{}:{}", pool_address, window, name)
+}
+
 // ------------------------------------------------
 //      store_ticks
 // ------------------------------------------------
@@ -286,6 +422,13 @@ pub fn all_position(id: &String, event_type: &String) -> String {
     format!("position:{}:{}", id, event_type)
 }
 
+// ------------------------------------------------
+//      store_position_static
+// ------------------------------------------------
+pub fn position_static(id: &String) -> String {
+    format!("position:{}:static", id)
+}
+
 // ------------------------------------------------
 //      store_positions_misc
 // ------------------------------------------------
@@ -305,6 +448,164 @@ pub fn position_collected_fees_token(id: &String, token: &str) -> String {
     format!("position:{}:collectedFees{}", id, token)
 }
 
+// ------------------------------------------------
+//      store_position_fee_growth_inside_last
+// ------------------------------------------------
+pub fn position_fee_growth_inside_last_x128(id: &String, token: &str) -> String {
+    format!("position:{}:feeGrowthInsideLastX128{}", id, token)
+}
+
+// ------------------------------------------------
+//      store_position_fees_accrued
+// ------------------------------------------------
+pub fn position_fees_accrued_token(id: &String, token: &str) -> String {
+    format!("position:{}:feesAccrued{}", id, token)
+}
+
+// ------------------------------------------------
+//      store_tick_positions
+// ------------------------------------------------
+pub fn tick_positions(tick_id: &String) -> String {
+    format!("tick:{}:positions", tick_id)
+}
+
+// ------------------------------------------------
+//      store_tick_fee_growth_outside_slots
+// ------------------------------------------------
+pub fn tick_fee_growth_outside_slot(pool_address: &String, slot: &String) -> String {
+    format!("pool:{}:tickSlot:{}", pool_address, slot)
+}
+
+// ------------------------------------------------
+//      store_tick_fee_growth_outside_x128
+// ------------------------------------------------
+pub fn tick_fee_growth_outside_x128(tick_id: &String, token: &str) -> String {
+    format!("tick:{}:feeGrowthOutsideX128:{}", tick_id, token)
+}
+
+// ------------------------------------------------
+//      store_pool_ticks
+// ------------------------------------------------
+pub fn pool_ticks(pool_address: &String) -> String {
+    format!("pool:{}:ticks", pool_address)
+}
+
+// ------------------------------------------------
+//      store_pool_active_positions
+// ------------------------------------------------
+// deleted once the position has no liquidity left, the suffix keeping the prefix
+// delete from matching longer position ids
+pub fn pool_active_position(pool_address: &String, id: &String) -> String {
+    format!("pool:{}:activePosition:{}:range", pool_address, id)
+}
+
+// ------------------------------------------------
+//      store_position_range_status and store_position_time_in_range
+// ------------------------------------------------
+pub fn position_range_status(id: &String) -> String {
+    format!("position:{}:rangeStatus", id)
+}
+
+pub fn position_time_in_range(id: &String) -> String {
+    format!("position:{}:timeInRange", id)
+}
+
+// ------------------------------------------------
+//      store_raw_positions
+// ------------------------------------------------
+pub fn raw_position(id: &String) -> String {
+    format!("rawPosition:{}", id)
+}
+
+// ------------------------------------------------
+//      store_raw_position_changes
+// ------------------------------------------------
+pub fn raw_position_liquidity(id: &String) -> String {
+    format!("rawPosition:{}:liquidity", id)
+}
+
+pub fn raw_position_deposited_token(id: &String, token: &str) -> String {
+    format!("rawPosition:{}:deposited{}", id, token)
+}
+
+pub fn raw_position_withdrawn_token(id: &String, token: &str) -> String {
+    format!("rawPosition:{}:withdrawn{}", id, token)
+}
+
+pub fn raw_position_collected_fees_token(id: &String, token: &str) -> String {
+    format!("rawPosition:{}:collectedFees{}", id, token)
+}
+
+// ------------------------------------------------
+//      store_liquidity_provider_positions
+// ------------------------------------------------
+pub fn pool_owner_active_positions(pool_address: &String, owner: &String) -> String {
+    format!("pool:{}:owner:{}:activePositions", pool_address, owner)
+}
+
+pub fn tick_owner_active_positions(tick_id: &String, owner: &String) -> String {
+    format!("tick:{}:owner:{}:activePositions", tick_id, owner)
+}
+
+// ------------------------------------------------
+//      store_liquidity_provider_counts
+// ------------------------------------------------
+pub fn liquidity_provider_count(entity: &str, id: &str) -> String {
+    format!("{}:{}:liquidityProviderCount", entity, id)
+}
+
+// ------------------------------------------------
+//      store_pool_arbitrage_volumes
+// ------------------------------------------------
+pub fn pool_arbitrage_volume_usd(pool_address: &String) -> String {
+    format!("pool:{}:arbitrageVolumeUSD", pool_address)
+}
+
+// ------------------------------------------------
+//      store_position_owners
+// ------------------------------------------------
+pub fn position_owner(id: &String) -> String {
+    format!("position:{}:owner", id)
+}
+
+// deleted once the position is transferred away from the owner, the suffix
+// keeping the prefix delete from matching longer position ids
+pub fn owner_position(owner: &String, id: &String) -> String {
+    format!("owner:{}:position:{}:owned", owner, id)
+}
+
+// ------------------------------------------------
+//      store_account_position_counts
+// ------------------------------------------------
+pub fn account_position_count(owner: &String, name: &str) -> String {
+    format!("owner:{}:{}", owner, name)
+}
+
+// ------------------------------------------------
+//      store_account_active_positions
+// ------------------------------------------------
+pub fn account_active_position(owner: &String, id: &String) -> String {
+    format!("owner:{}:activePosition:{}:active", owner, id)
+}
+
+// ------------------------------------------------
+//      store_account_totals
+// ------------------------------------------------
+pub fn account_total(owner: &String, name: &str) -> String {
+    format!("owner:{}:{}", owner, name)
+}
+
+// ------------------------------------------------
+//      store_priced_tokens / store_token_usd_prices
+// ------------------------------------------------
+pub fn priced_tokens() -> String {
+    "tokens:priced".to_string()
+}
+
+pub fn token_usd_price(token_address: &String) -> String {
+    format!("token:{}:usd", token_address)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -6,6 +6,7 @@ mod math;
 mod pb;
 mod price;
 mod rpc;
+mod tracking;
 mod utils;
 
 use crate::abi::pool::events::Swap;
@@ -16,20 +17,32 @@ use crate::pb::uniswap::tick::Origin::{Burn, Mint};
 use crate::pb::uniswap::tick::Type::{Lower, Upper};
 use crate::pb::uniswap::{
     Erc20Token, Erc20Tokens, Event, EventAmount, Events, Pool, PoolLiquidities, PoolLiquidity,
-    PoolSqrtPrice, PoolSqrtPrices, Pools, Tick, Ticks,
+    PoolSqrtPrice, PoolSqrtPrices, PoolTokenTransfer, PoolTokenTransfers, Pools, Tick, Ticks,
+    TokenSupplyChange, TokenSupplyChanges, TokenTransferBehavior, TokenTransferBehaviors,
 };
-use crate::pb::{uniswap, PositionEvent};
+use crate::pb::{uniswap, PositionEvent, SwapLeg};
 use crate::price::WHITELIST_TOKENS;
 use crate::uniswap::position::PositionType;
 use crate::uniswap::position::PositionType::{
     Collect, DecreaseLiquidity, IncreaseLiquidity, Transfer,
 };
+use crate::uniswap::position_range_event::Type as RangeEventType;
+use crate::uniswap::raw_position_event::Type as RawPositionEventType;
+use crate::uniswap::token_price_change::Cause as TokenPriceChangeCause;
+use crate::uniswap::token_transfer_behavior::Type as TransferBehaviorType;
 use crate::uniswap::{
-    Flash, Flashes, Position, Positions, SnapshotPosition, SnapshotPositions, Transactions,
+    Account, Accounts, Arbitrage, Arbitrages, BundlePrice, BundlePrices, CandleSwap, CandleSwaps,
+    DerivedEthPrice, DerivedEthPrices, Flash, Flashes, JitLiquidityEvent, JitLiquidityEvents,
+    PoolCandle, PoolCandles, PoolObservation, PoolObservations, PoolTwap, PoolTwaps, Position,
+    PositionPerformance, PositionPerformances, PositionRangeEvent, PositionRangeEvents,
+    PositionStatic, PositionUncollectedFee, PositionUncollectedFees, Positions, RawPositionEvent,
+    RawPositionEvents, Sandwich, Sandwiches, SnapshotPosition, SnapshotPositions, SwapInterface,
+    SwapInterfaces, TokenPriceChange, TokenPriceChanges, Trade, Trades, Transactions,
 };
-use crate::utils::{NON_FUNGIBLE_POSITION_MANAGER, UNISWAP_V3_FACTORY};
+use crate::utils::{NON_FUNGIBLE_POSITION_MANAGER, UNISWAP_V3_FACTORY, ZERO_ADDRESS};
 use std::collections::HashMap;
 use std::ops::{Add, Div, Mul, Sub};
+use std::str::FromStr;
 use substreams::errors::Error;
 use substreams::hex;
 use substreams::pb::substreams::Clock;
@@ -37,16 +50,80 @@ use substreams::prelude::*;
 use substreams::scalar::{BigDecimal, BigInt};
 use substreams::store;
 use substreams::store::{
-    DeltaArray, DeltaBigDecimal, DeltaBigInt, DeltaProto, StoreAddBigDecimal, StoreAddBigInt,
-    StoreAppend, StoreGetBigDecimal, StoreGetBigInt, StoreGetProto, StoreGetRaw,
-    StoreSetBigDecimal, StoreSetBigInt, StoreSetProto,
+    DeltaArray, DeltaBigDecimal, DeltaBigInt, DeltaProto, DeltaString, StoreAddBigDecimal,
+    StoreAddBigInt, StoreAppend, StoreGetArray, StoreGetBigDecimal, StoreGetBigInt, StoreGetProto,
+    StoreGetRaw, StoreGetString, StoreMaxBigDecimal, StoreMinBigDecimal, StoreSetBigDecimal,
+    StoreSetBigInt, StoreSetIfNotExistsBigDecimal, StoreSetIfNotExistsBigInt,
+    StoreSetIfNotExistsProto, StoreSetIfNotExistsString, StoreSetProto, StoreSetString,
 };
 use substreams::{log, Hex};
 use substreams_entity_change::pb::entity::EntityChanges;
 use substreams_ethereum::{pb::eth as ethpb, Event as EventTrait};
 
+#[substreams::handlers::store]
+pub fn store_known_tokens(block: Block, store: StoreSetIfNotExistsBigInt) {
+    use abi::factory::events::PoolCreated;
+
+    for (event, log) in block.events::<PoolCreated>(&[&UNISWAP_V3_FACTORY]) {
+        store.set_if_not_exists(
+            log.ordinal(),
+            keyer::token_key(&Hex(&event.token0).to_string()),
+            &BigInt::from(block.number),
+        );
+        store.set_if_not_exists(
+            log.ordinal(),
+            keyer::token_key(&Hex(&event.token1).to_string()),
+            &BigInt::from(block.number),
+        );
+    }
+}
+
+// tokens are fetched through `eth_call` only the first time they are seen, a token
+// whose metadata could not be fetched then is left out of `store_tokens`
+#[substreams::handlers::map]
+pub fn map_tokens_created(
+    block: Block,
+    known_tokens_store: StoreGetBigInt,
+) -> Result<Erc20Tokens, Error> {
+    use abi::factory::events::PoolCreated;
+
+    let mut tokens: Vec<Erc20Token> = vec![];
+    let mut fetched_tokens: Vec<String> = vec![];
+    for (event, _) in block.events::<PoolCreated>(&[&UNISWAP_V3_FACTORY]) {
+        for token_address in [
+            Hex(&event.token0).to_string(),
+            Hex(&event.token1).to_string(),
+        ] {
+            // a token appearing in multiple pools created in the same block is only fetched once
+            if fetched_tokens.contains(&token_address)
+                || !utils::is_first_seen_token(&token_address, block.number, &known_tokens_store)
+            {
+                continue;
+            }
+            fetched_tokens.push(token_address.clone());
+
+            match rpc::create_uniswap_token(&token_address) {
+                None => log::info!("unable to fetch token {} metadata", token_address),
+                Some(token) => tokens.push(token),
+            }
+        }
+    }
+
+    Ok(Erc20Tokens { tokens })
+}
+
+#[substreams::handlers::store]
+pub fn store_tokens(tokens: Erc20Tokens, store: StoreSetIfNotExistsProto<Erc20Token>) {
+    for token in tokens.tokens {
+        store.set_if_not_exists(0, keyer::token_key(&token.address), &token);
+    }
+}
+
 #[substreams::handlers::map]
-pub fn map_pools_created(block: Block) -> Result<Pools, Error> {
+pub fn map_pools_created(
+    block: Block,
+    tokens_store: StoreGetProto<Erc20Token>,
+) -> Result<Pools, Error> {
     use abi::factory::events::PoolCreated;
 
     Ok(Pools {
@@ -70,30 +147,10 @@ pub fn map_pools_created(block: Block) -> Result<Pools, Error> {
                     tick_spacing: event.tick_spacing.into(),
                     log_ordinal: log.ordinal(),
                     ignore_pool: event.pool == hex!("8fe8d9bb8eeba3ed688069c3d6b556c9ca258248"),
-                    token0: Some(match rpc::create_uniswap_token(&token0_address) {
-                        Some(mut token) => {
-                            token.total_supply = rpc::token_total_supply_call(&token0_address)
-                                .expect("failed to get token0 total supply")
-                                .to_string();
-                            token
-                        }
-                        None => {
-                            // We were unable to create the uniswap token, so we discard this event entirely
-                            return None;
-                        }
-                    }),
-                    token1: Some(match rpc::create_uniswap_token(&token1_address) {
-                        Some(mut token) => {
-                            token.total_supply = rpc::token_total_supply_call(&token1_address)
-                                .expect("failed to get token1 total supply")
-                                .to_string();
-                            token
-                        }
-                        None => {
-                            // We were unable to create the uniswap token, so we discard this event entirely
-                            return None;
-                        }
-                    }),
+                    // if we were unable to create the uniswap token when it was first seen,
+                    // we discard this event entirely
+                    token0: Some(tokens_store.get_last(keyer::token_key(&token0_address))?),
+                    token1: Some(tokens_store.get_last(keyer::token_key(&token1_address))?),
                     ..Default::default()
                 })
             })
@@ -118,6 +175,87 @@ pub fn store_pools(pools: Pools, store: StoreSetProto<Pool>) {
     }
 }
 
+#[substreams::handlers::map]
+pub fn map_token_supply_changes(
+    block: Block,
+    known_tokens_store: StoreGetBigInt,
+    tokens_store: StoreGetProto<Erc20Token>,
+) -> Result<TokenSupplyChanges, Error> {
+    let mut token_supply_changes = vec![];
+    for log in block.logs() {
+        let transfer = match abi::erc20::events::Transfer::match_and_decode(log) {
+            None => continue,
+            Some(transfer) => transfer,
+        };
+
+        let amount: BigInt = if transfer.from == ZERO_ADDRESS && transfer.to != ZERO_ADDRESS {
+            transfer.value
+        } else if transfer.to == ZERO_ADDRESS && transfer.from != ZERO_ADDRESS {
+            transfer.value.neg()
+        } else {
+            continue;
+        };
+
+        let token_address = Hex(log.address()).to_string();
+        if tokens_store
+            .get_last(keyer::token_key(&token_address))
+            .is_none()
+        {
+            continue;
+        }
+
+        // the total supply of a token seen for the first time in this block
+        // was just fetched when creating its pool
+        if utils::is_first_seen_token(&token_address, block.number, &known_tokens_store) {
+            continue;
+        }
+
+        log::debug!(
+            "token {} supply change {} trx_id: {}",
+            token_address,
+            amount,
+            Hex(&log.receipt.transaction.hash).to_string()
+        );
+        token_supply_changes.push(TokenSupplyChange {
+            token_address,
+            amount: Some(amount.into()),
+            log_ordinal: log.ordinal(),
+        });
+    }
+
+    Ok(TokenSupplyChanges {
+        token_supply_changes,
+    })
+}
+
+#[substreams::handlers::store]
+pub fn store_token_total_supply(
+    tokens_deltas: store::Deltas<DeltaProto<Erc20Token>>,
+    token_supply_changes: TokenSupplyChanges,
+    store: StoreAddBigInt,
+) {
+    for delta in tokens_deltas.deltas {
+        let token = delta.new_value;
+        let total_supply = match BigInt::from_str(token.total_supply.as_str()) {
+            Ok(total_supply) => total_supply,
+            Err(_) => BigInt::zero(),
+        };
+        store.add(
+            delta.ordinal,
+            keyer::token_total_supply(&token.address),
+            &total_supply,
+        );
+    }
+
+    for token_supply_change in token_supply_changes.token_supply_changes {
+        store.add(
+            token_supply_change.log_ordinal,
+            keyer::token_total_supply(&token_supply_change.token_address),
+            &BigInt::from(token_supply_change.amount.unwrap()),
+        );
+    }
+}
+
 #[substreams::handlers::store]
 pub fn store_pool_count(pools: Pools, store: StoreAddBigInt) {
     for pool in pools.pools {
@@ -129,42 +267,36 @@ pub fn store_pool_count(pools: Pools, store: StoreAddBigInt) {
     }
 }
 
-#[substreams::handlers::map]
-pub fn map_tokens_whitelist_pools(pools: Pools) -> Result<Erc20Tokens, Error> {
-    let mut tokens = vec![];
-
+#[substreams::handlers::store]
+pub fn store_token_pools(pools: Pools, output_append: StoreAppend<String>) {
+    // every pool is indexed on both of its tokens, along with the counter token for the pricing
+    // graph, a pool is whitelisted for a token when the other token is a whitelisted one
     for pool in pools.pools {
-        let mut token0 = pool.token0();
-        let mut token1 = pool.token1();
-
-        let token0_whitelisted = WHITELIST_TOKENS.contains(&token0.address.as_str());
-        let token1_whitelisted = WHITELIST_TOKENS.contains(&token1.address.as_str());
-
-        if token0_whitelisted {
-            log::info!("adding pool: {} to token: {}", pool.address, token1.address);
-            token1.whitelist_pools.push(pool.address.to_string());
-            tokens.push(token1);
-        }
+        let token0 = pool.token0_ref();
+        let token1 = pool.token1_ref();
+
+        for (token, other_token) in [(token0, token1), (token1, token0)] {
+            output_append.append(
+                pool.log_ordinal,
+                keyer::token_pools(&token.address),
+                pool.address.clone(),
+            );
+            output_append.append(
+                pool.log_ordinal,
+                keyer::token_pairs(&token.address),
+                format!("{}:{}", pool.address, other_token.address),
+            );
 
-        if token1_whitelisted {
-            log::info!("adding pool: {} to token: {}", pool.address, token0.address);
-            token0.whitelist_pools.push(pool.address.to_string());
-            tokens.push(token0);
+            if WHITELIST_TOKENS.contains(&other_token.address.as_str()) {
+                log::info!("adding pool: {} to token: {}", pool.address, token.address);
+                output_append.append(
+                    pool.log_ordinal,
+                    keyer::token_whitelist_pools(&token.address),
+                    pool.address.clone(),
+                );
+            }
         }
     }
-
-    Ok(Erc20Tokens { tokens })
-}
-
-#[substreams::handlers::store]
-pub fn store_tokens_whitelist_pools(tokens: Erc20Tokens, output_append: StoreAppend<String>) {
-    for token in tokens.tokens {
-        output_append.append_all(
-            1,
-            keyer::token_pool_whitelist(&token.address),
-            token.whitelist_pools,
-        );
-    }
 }
 
 #[substreams::handlers::map]
@@ -228,6 +360,253 @@ pub fn store_pool_sqrt_price(sqrt_prices: PoolSqrtPrices, store: StoreSetProto<P
     }
 }
 
+#[substreams::handlers::store]
+pub fn store_pool_tick_timestamps(
+    clock: Clock,
+    pool_sqrt_price_deltas: store::Deltas<DeltaProto<PoolSqrtPrice>>,
+    store: StoreSetBigInt,
+) {
+    let timestamp_seconds = clock.timestamp.unwrap().seconds;
+    for delta in pool_sqrt_price_deltas.deltas {
+        store.set(
+            delta.ordinal,
+            keyer::pool_tick_timestamp(&delta.new_value.pool_address),
+            &BigInt::from(timestamp_seconds),
+        );
+    }
+}
+
+// tickCumulative grows by the tick times the seconds it was held, as in the pool oracle
+#[substreams::handlers::store]
+pub fn store_pool_tick_cumulatives(
+    clock: Clock,
+    pool_sqrt_price_deltas: store::Deltas<DeltaProto<PoolSqrtPrice>>,
+    tick_timestamps_store: StoreGetBigInt,
+    store: StoreAddBigInt,
+) {
+    let timestamp_seconds = clock.timestamp.unwrap().seconds;
+    for delta in pool_sqrt_price_deltas.deltas {
+        let pool_address = &delta.new_value.pool_address;
+        if delta.operation == store::Operation::Create {
+            store.add(
+                delta.ordinal,
+                keyer::pool_tick_cumulative(pool_address),
+                &BigInt::zero(),
+            );
+            continue;
+        }
+
+        let previous_timestamp = tick_timestamps_store
+            .get_at(delta.ordinal - 1, keyer::pool_tick_timestamp(pool_address))
+            .unwrap()
+            .to_u64() as i64;
+        let previous_tick: BigInt = delta.old_value.tick.unwrap().into();
+        store.add(
+            delta.ordinal,
+            keyer::pool_tick_cumulative(pool_address),
+            &previous_tick.mul(BigInt::from(timestamp_seconds - previous_timestamp)),
+        );
+    }
+}
+
+#[substreams::handlers::map]
+pub fn map_pool_observations(
+    clock: Clock,
+    pool_sqrt_price_deltas: store::Deltas<DeltaProto<PoolSqrtPrice>>,
+    tick_timestamps_store: StoreGetBigInt,
+    tick_cumulatives_store: StoreGetBigInt,
+) -> Result<PoolObservations, Error> {
+    let timestamp_seconds = clock.timestamp.unwrap().seconds as u64;
+    let mut pool_observations: Vec<PoolObservation> = vec![];
+
+    for delta in pool_sqrt_price_deltas.deltas {
+        let pool_address = delta.new_value.pool_address.clone();
+        // like the pool oracle, only the first tick update of a block is written
+        if pool_observations
+            .iter()
+            .any(|observation| observation.pool_address == pool_address)
+        {
+            continue;
+        }
+
+        let (previous_timestamp, previous_tick) = match delta.operation {
+            store::Operation::Create => (0, BigInt::zero()),
+            _ => (
+                tick_timestamps_store
+                    .get_at(delta.ordinal - 1, keyer::pool_tick_timestamp(&pool_address))
+                    .unwrap()
+                    .to_u64(),
+                delta.old_value.tick.unwrap().into(),
+            ),
+        };
+        let tick_cumulative = tick_cumulatives_store
+            .get_at(delta.ordinal, keyer::pool_tick_cumulative(&pool_address))
+            .unwrap();
+
+        pool_observations.push(PoolObservation {
+            pool_address,
+            timestamp: timestamp_seconds,
+            tick_cumulative: Some(tick_cumulative.into()),
+            tick: delta.new_value.tick,
+            previous_timestamp,
+            previous_tick: Some(previous_tick.into()),
+            ordinal: delta.ordinal,
+        });
+    }
+
+    Ok(PoolObservations { pool_observations })
+}
+
+// the longest TWAP window is a day, so two days of observations are kept
+#[substreams::handlers::store]
+pub fn store_pool_observations(
+    clock: Clock,
+    pool_observations: PoolObservations,
+    store: StoreAppend<String>,
+) {
+    let timestamp_seconds = clock.timestamp.unwrap().seconds;
+    let day_id: i64 = timestamp_seconds / 86400;
+    store.delete_prefix(0, &keyer::pool_observations_prefix(day_id - 2));
+
+    for observation in pool_observations.pool_observations {
+        store.append(
+            observation.ordinal,
+            keyer::pool_observations(day_id, &observation.pool_address),
+            utils::encode_observation(&observation),
+        );
+    }
+}
+
+#[substreams::handlers::map]
+pub fn map_pool_twaps(
+    pool_observations: PoolObservations,
+    observations_store: StoreGetArray<String>,
+    pools_store: StoreGetProto<Pool>,
+) -> Result<PoolTwaps, Error> {
+    let mut pool_twaps = vec![];
+    for observation in pool_observations.pool_observations {
+        let pool = match pools_store.get_last(keyer::pool_key(&observation.pool_address)) {
+            None => continue,
+            Some(pool) => pool,
+        };
+
+        let day_id = (observation.timestamp / 86400) as i64;
+        let observations: Vec<(u64, i64, u64, i64)> = [day_id - 1, day_id]
+            .iter()
+            .flat_map(|day_id| {
+                observations_store
+                    .get_last(keyer::pool_observations(*day_id, &observation.pool_address))
+                    .unwrap_or_default()
+            })
+            .filter_map(|encoded| utils::decode_observation(&encoded))
+            .collect();
+        let tick_cumulative = BigInt::from(observation.tick_cumulative.unwrap())
+            .to_string()
+            .parse::<i64>()
+            .unwrap();
+
+        for (window, seconds) in utils::TWAP_WINDOWS {
+            let window_tick_cumulative =
+                match utils::tick_cumulative_at(&observations, observation.timestamp - seconds) {
+                    None => continue,
+                    Some(window_tick_cumulative) => window_tick_cumulative,
+                };
+
+            let mean_tick = (tick_cumulative - window_tick_cumulative).div_euclid(seconds as i64);
+            let (token0_price, token1_price) = price::tick_to_token_prices(
+                mean_tick,
+                pool.token0.as_ref().unwrap(),
+                pool.token1.as_ref().unwrap(),
+            );
+            pool_twaps.push(PoolTwap {
+                pool_address: observation.pool_address.clone(),
+                window: window.to_string(),
+                mean_tick,
+                token0_price: Some(token0_price.into()),
+                token1_price: Some(token1_price.into()),
+                timestamp: observation.timestamp,
+                ordinal: observation.ordinal,
+            });
+        }
+    }
+
+    Ok(PoolTwaps { pool_twaps })
+}
+
+#[substreams::handlers::store]
+pub fn store_pool_twaps(pool_twaps: PoolTwaps, store: StoreSetBigDecimal) {
+    for pool_twap in pool_twaps.pool_twaps {
+        store.set(
+            pool_twap.ordinal,
+            keyer::pool_twap(&pool_twap.pool_address, &pool_twap.window, "meanTick"),
+            &BigInt::from(pool_twap.mean_tick).to_decimal(0),
+        );
+        store.set(
+            pool_twap.ordinal,
+            keyer::pool_twap(&pool_twap.pool_address, &pool_twap.window, "token0Price"),
+            &BigDecimal::from(pool_twap.token0_price.unwrap()),
+        );
+        store.set(
+            pool_twap.ordinal,
+            keyer::pool_twap(&pool_twap.pool_address, &pool_twap.window, "token1Price"),
+            &BigDecimal::from(pool_twap.token1_price.unwrap()),
+        );
+    }
+}
+
+// the oracle index and cardinalities only live in slot0, cardinality growth on a write emits no event
+#[substreams::handlers::store]
+pub fn store_pool_observation_states(
+    block: Block,
+    pools_store: StoreGetProto<Pool>,
+    store: StoreSetBigInt,
+) {
+    for trx in block.transaction_traces {
+        if trx.status != 1 {
+            continue;
+        }
+        for call in trx.calls {
+            if call.state_reverted {
+                continue;
+            }
+            for storage_change in call.storage_changes {
+                let new_state = match utils::slot0_observation_state(
+                    &storage_change.key,
+                    &storage_change.new_value,
+                ) {
+                    None => continue,
+                    Some(state) => state,
+                };
+                let old_state =
+                    utils::slot0_observation_state(&storage_change.key, &storage_change.old_value);
+                let pool_address = Hex(&storage_change.address).to_string();
+                if pools_store
+                    .get_last(keyer::pool_key(&pool_address))
+                    .is_none()
+                {
+                    continue;
+                }
+
+                let names = [
+                    "observationIndex",
+                    "observationCardinality",
+                    "observationCardinalityNext",
+                ];
+                for (i, name) in names.iter().enumerate() {
+                    if old_state.map(|state| state[i]) == Some(new_state[i]) {
+                        continue;
+                    }
+                    store.set(
+                        storage_change.ordinal,
+                        keyer::pool_observation_state(&pool_address, name),
+                        &BigInt::from(new_state[i]),
+                    );
+                }
+            }
+        }
+    }
+}
+
 #[substreams::handlers::map]
 pub fn map_pool_liquidities(
     block: Block,
@@ -544,1176 +923,4132 @@ pub fn map_swaps_mints_burns(
 }
 
 #[substreams::handlers::map]
-pub fn map_event_amounts(events: Events) -> Result<uniswap::EventAmounts, Error> {
-    let mut event_amounts = vec![];
+pub fn map_trades(events: Events, eth_prices_store: StoreGetBigDecimal) -> Result<Trades, Error> {
+    let mut trades: Vec<Trade> = vec![];
+    // trades of the transaction being walked, a hop continues the latest trade whose output
+    // it spends: the output token and amount went to the router swapping this hop or to the
+    // pool itself
+    let mut transaction_id = String::new();
+    let mut transaction_trades: Vec<usize> = vec![];
+
     for event in events.events {
-        log::debug!("transaction id: {}", event.transaction_id);
-        if event.r#type.is_none() {
-            continue;
+        let swap_leg = match event.swap_leg() {
+            None => continue,
+            Some(swap_leg) => swap_leg,
+        };
+        let swap = match event.r#type.unwrap() {
+            SwapEvent(swap) => swap,
+            _ => continue,
+        };
+
+        if event.transaction_id != transaction_id {
+            transaction_id = event.transaction_id.clone();
+            transaction_trades.clear();
         }
 
-        if event.r#type.is_some() {
-            match event.r#type.unwrap() {
-                BurnEvent(burn) => {
-                    log::debug!("handling burn for pool {}", event.pool_address);
-                    let amount0: BigDecimal = burn.amount_0.unwrap().into();
-                    let amount1: BigDecimal = burn.amount_1.unwrap().into();
-                    event_amounts.push(EventAmount {
-                        pool_address: event.pool_address,
-                        log_ordinal: event.log_ordinal,
-                        token0_addr: event.token0,
-                        amount0_value: Some(amount0.neg().into()),
-                        token1_addr: event.token1,
-                        amount1_value: Some(amount1.neg().into()),
-                        ..Default::default()
-                    });
-                }
-                MintEvent(mint) => {
-                    log::debug!("handling mint for pool {}", event.pool_address);
-                    let amount0: BigDecimal = mint.amount_0.unwrap().into();
-                    let amount1: BigDecimal = mint.amount_1.unwrap().into();
-                    event_amounts.push(EventAmount {
-                        pool_address: event.pool_address,
-                        log_ordinal: event.log_ordinal,
-                        token0_addr: event.token0,
-                        amount0_value: Some(amount0.into()),
-                        token1_addr: event.token1,
-                        amount1_value: Some(amount1.into()),
-                        ..Default::default()
-                    });
-                }
-                SwapEvent(swap) => {
-                    log::debug!("handling swap for pool {}", event.pool_address);
-                    let amount0: BigDecimal = swap.amount_0.unwrap().into();
-                    let amount1: BigDecimal = swap.amount_1.unwrap().into();
-                    event_amounts.push(EventAmount {
-                        pool_address: event.pool_address,
-                        log_ordinal: event.log_ordinal,
-                        token0_addr: event.token0,
-                        amount0_value: Some(amount0.into()),
-                        token1_addr: event.token1,
-                        amount1_value: Some(amount1.into()),
-                        ..Default::default()
-                    });
-                }
+        let previous_hop = transaction_trades
+            .iter()
+            .rev()
+            .find(|index| {
+                let trade = &trades[**index];
+                trade.token_out == swap_leg.token_in
+                    && (trade.recipient == swap.sender || trade.recipient == event.pool_address)
+                    && BigDecimal::from(trade.amount_out.clone().unwrap()).eq(&swap_leg.amount_in)
+            })
+            .cloned();
+        match previous_hop {
+            Some(index) => {
+                let trade = &mut trades[index];
+                trade.pools.push(event.pool_address);
+                trade.path.push(swap_leg.token_out.clone());
+                trade.token_out = swap_leg.token_out;
+                trade.amount_out = Some(swap_leg.amount_out.into());
+                trade.recipient = swap.recipient;
+                trade.log_ordinal = event.log_ordinal;
+            }
+            None => {
+                transaction_trades.push(trades.len());
+                trades.push(Trade {
+                    id: format!("{}#{}", event.transaction_id, event.log_ordinal),
+                    transaction_id: event.transaction_id,
+                    origin: swap.origin,
+                    sender: swap.sender,
+                    recipient: swap.recipient,
+                    pools: vec![event.pool_address],
+                    path: vec![swap_leg.token_in.clone(), swap_leg.token_out.clone()],
+                    token_in: swap_leg.token_in,
+                    token_out: swap_leg.token_out,
+                    amount_in: Some(swap_leg.amount_in.into()),
+                    amount_out: Some(swap_leg.amount_out.into()),
+                    effective_price: None,
+                    amount_usd: None,
+                    timestamp: event.timestamp,
+                    block_number: event.created_at_block_number,
+                    log_ordinal: event.log_ordinal,
+                });
             }
         }
     }
-    Ok(uniswap::EventAmounts { event_amounts })
-}
 
-#[substreams::handlers::map]
-pub fn map_transactions(
-    block: Block,
-    pools_store: StoreGetProto<Pool>,
-) -> Result<Transactions, Error> {
-    let mut transactions: Transactions = Transactions {
-        transactions: vec![],
-    };
+    for trade in trades.iter_mut() {
+        let amount_in = BigDecimal::from(trade.amount_in.clone().unwrap());
+        let amount_out = BigDecimal::from(trade.amount_out.clone().unwrap());
 
-    for log in block.logs() {
-        let mut add_transaction = false;
-        let pool_key = &format!("pool:{}", Hex(&log.address()).to_string());
+        // value the trade on its input, falling back to its output for unpriced tokens
+        let mut amount_usd = utils::token_amount_usd(
+            &amount_in,
+            &trade.token_in,
+            trade.log_ordinal,
+            &eth_prices_store,
+        );
+        if amount_usd.eq(&BigDecimal::zero()) {
+            amount_usd = utils::token_amount_usd(
+                &amount_out,
+                &trade.token_out,
+                trade.log_ordinal,
+                &eth_prices_store,
+            );
+        }
 
-        if let Some(_) = abi::pool::events::Burn::match_and_decode(log) {
-            match pools_store.get_last(pool_key) {
-                None => continue,
+        trade.effective_price = Some(math::safe_div(&amount_out, &amount_in).into());
+        trade.amount_usd = Some(amount_usd.into());
+    }
 
-                Some(_) => add_transaction = true,
-            }
-        } else if let Some(_) = abi::pool::events::Mint::match_and_decode(log) {
-            match pools_store.get_last(pool_key) {
-                None => continue,
-                Some(_) => add_transaction = true,
-            }
-        } else if let Some(_) = abi::pool::events::Swap::match_and_decode(log) {
-            match pools_store.get_last(pool_key) {
-                None => continue,
-                Some(_) => add_transaction = true,
-            }
-        } else if let Some(_) =
-            abi::positionmanager::events::IncreaseLiquidity::match_and_decode(log)
-        {
-            if log.address() == NON_FUNGIBLE_POSITION_MANAGER {
-                add_transaction = true;
-            }
-        } else if let Some(_) = abi::positionmanager::events::Collect::match_and_decode(log) {
-            if log.address() == NON_FUNGIBLE_POSITION_MANAGER {
-                add_transaction = true;
-            }
-        } else if let Some(_) =
-            abi::positionmanager::events::DecreaseLiquidity::match_and_decode(log)
-        {
-            if log.address() == NON_FUNGIBLE_POSITION_MANAGER {
-                add_transaction = true;
-            }
-        } else if let Some(_) = abi::positionmanager::events::Transfer::match_and_decode(log) {
-            if log.address() == NON_FUNGIBLE_POSITION_MANAGER {
-                add_transaction = true;
-            }
+    Ok(Trades { trades })
+}
+
+#[substreams::handlers::map]
+pub fn map_swap_interfaces(
+    params: String,
+    block: Block,
+    events: Events,
+    eth_prices_store: StoreGetBigDecimal,
+) -> Result<SwapInterfaces, Error> {
+    let registry = utils::parse_interface_registry(&params);
+    let mut interfaces: HashMap<String, (String, String)> = HashMap::new();
+    let mut swap_interfaces = vec![];
+
+    for event in events.events {
+        let swap_leg = match event.swap_leg() {
+            None => continue,
+            Some(swap_leg) => swap_leg,
+        };
+
+        if !interfaces.contains_key(&event.transaction_id) {
+            let transaction_trace = block
+                .transaction_traces
+                .iter()
+                .find(|trx| Hex(&trx.hash).to_string() == event.transaction_id)
+                .unwrap();
+            interfaces.insert(
+                event.transaction_id.clone(),
+                (
+                    utils::classify_interface(transaction_trace, &registry),
+                    Hex(&transaction_trace.to).to_string(),
+                ),
+            );
         }
+        let (interface, to) = interfaces.get(&event.transaction_id).unwrap().clone();
 
-        if add_transaction {
-            transactions.transactions.push(utils::load_transaction(
-                block.number,
-                block.timestamp_seconds(),
-                log.ordinal(),
-                log.receipt.transaction,
-            ));
+        // value the swap on its input, falling back to its output for unpriced tokens
+        let mut amount_usd = utils::token_amount_usd(
+            &swap_leg.amount_in,
+            &swap_leg.token_in,
+            event.log_ordinal,
+            &eth_prices_store,
+        );
+        if amount_usd.eq(&BigDecimal::zero()) {
+            amount_usd = utils::token_amount_usd(
+                &swap_leg.amount_out,
+                &swap_leg.token_out,
+                event.log_ordinal,
+                &eth_prices_store,
+            );
         }
+
+        swap_interfaces.push(SwapInterface {
+            transaction_id: event.transaction_id,
+            pool_address: event.pool_address,
+            interface,
+            to,
+            amount_usd: Some(amount_usd.into()),
+            timestamp: event.timestamp,
+            log_ordinal: event.log_ordinal,
+        });
     }
 
-    Ok(transactions)
+    Ok(SwapInterfaces { swap_interfaces })
 }
 
 #[substreams::handlers::store]
-pub fn store_totals(
+pub fn store_interface_day_volumes(
     clock: Clock,
-    store_eth_prices: StoreGetBigDecimal,
-    total_value_locked_deltas: store::Deltas<DeltaBigDecimal>,
+    swap_interfaces: SwapInterfaces,
     store: StoreAddBigDecimal,
 ) {
     let timestamp_seconds = clock.timestamp.unwrap().seconds;
     let day_id: i64 = timestamp_seconds / 86400;
-    store.delete_prefix(0, &format!("uniswap_day_data:{}:", day_id - 1));
-
-    let mut pool_total_value_locked_eth_new_value: BigDecimal = BigDecimal::zero();
-    for delta in total_value_locked_deltas.deltas {
-        if !delta.key.starts_with("pool:") {
-            continue;
-        }
-        match delta.key.as_str().split(":").last().unwrap() {
-            "eth" => {
-                let pool_total_value_locked_eth_old_value = delta.old_value;
-                // let pool_total_value_locked_eth_old_value: BigDecimal = math::decimal_from_bytes(&delta.old_value);
-                pool_total_value_locked_eth_new_value = delta.new_value;
-                // pool_total_value_locked_eth_new_value = math::decimal_from_bytes(&delta.new_value);
-
-                let pool_total_value_locked_eth_diff: BigDecimal =
-                    pool_total_value_locked_eth_old_value
-                        .sub(pool_total_value_locked_eth_new_value.clone());
+    store.delete_prefix(0, &format!("interface_day_data:{}:", day_id - 1));
 
-                store.add(
-                    delta.ordinal,
-                    keyer::factory_total_value_locked_eth(),
-                    &pool_total_value_locked_eth_diff,
-                )
-            }
-            "usd" => {
-                let bundle_eth_price: BigDecimal = match store_eth_prices.get_last("bundle") {
-                    None => continue,
-                    Some(price) => price,
-                };
-                log::debug!("eth_price_usd: {}", bundle_eth_price);
-
-                let total_value_locked_usd: BigDecimal = pool_total_value_locked_eth_new_value
-                    .clone()
-                    .mul(bundle_eth_price);
-
-                // here we have to do a hackish way to set the value, to not have to
-                // create a new store which would do the same but that would set the
-                // value instead of summing it, what we do is calculate the difference
-                // and simply add/sub the difference and that mimics the same as setting
-                // the value
-                let total_value_locked_usd_old_value: BigDecimal = delta.old_value;
-                // let total_value_locked_usd_old_value: BigDecimal = math::decimal_from_bytes(&delta.old_value);
-                let diff: BigDecimal = total_value_locked_usd
-                    .clone()
-                    .sub(total_value_locked_usd_old_value);
-
-                store.add(
-                    delta.ordinal,
-                    keyer::factory_total_value_locked_usd(),
-                    &diff,
-                );
-                store.add(
-                    delta.ordinal,
-                    keyer::uniswap_total_value_locked_usd(day_id.to_string()),
-                    &total_value_locked_usd,
-                )
-            }
-            _ => continue,
-        }
+    for swap_interface in swap_interfaces.swap_interfaces {
+        store.add(
+            swap_interface.log_ordinal,
+            keyer::interface_day_data_volume_usd(day_id.to_string(), &swap_interface.interface),
+            &BigDecimal::from(swap_interface.amount_usd.unwrap()),
+        );
     }
 }
 
 #[substreams::handlers::store]
-pub fn store_total_tx_counts(clock: Clock, events: Events, output: StoreAddBigInt) {
+pub fn store_interface_day_swap_counts(
+    clock: Clock,
+    swap_interfaces: SwapInterfaces,
+    store: StoreAddBigInt,
+) {
     let timestamp_seconds = clock.timestamp.unwrap().seconds;
     let day_id: i64 = timestamp_seconds / 86400;
-    output.delete_prefix(0, &format!("uniswap_day_data:{}:", day_id - 1));
+    store.delete_prefix(0, &format!("interface_day_data:{}:", day_id - 1));
 
-    for event in events.events {
-        let keys: Vec<String> = vec![
-            keyer::pool_total_tx_count(&event.pool_address),
-            keyer::token_total_tx_count(&event.token0),
-            keyer::token_total_tx_count(&event.token1),
-            keyer::factory_total_tx_count(),
-            keyer::uniswap_data_data_tx_count(day_id.to_string()),
-        ];
-        output.add_many(event.log_ordinal, &keys, &BigInt::from(1 as i32));
+    for swap_interface in swap_interfaces.swap_interfaces {
+        store.add(
+            swap_interface.log_ordinal,
+            keyer::interface_day_data_swap_count(day_id.to_string(), &swap_interface.interface),
+            &BigInt::one(),
+        );
     }
 }
 
 #[substreams::handlers::store]
-pub fn store_swaps_volume(
-    clock: Clock,
-    events: Events,
-    store_pool: StoreGetProto<Pool>,
-    store_total_tx_counts: StoreGetBigInt,
-    store_eth_prices: StoreGetBigDecimal,
-    output: StoreAddBigDecimal,
-) {
+pub fn store_candles_timestamp(clock: Clock, store: StoreSetBigInt) {
     let timestamp_seconds = clock.timestamp.unwrap().seconds;
-    let day_id: i64 = timestamp_seconds / 86400;
-    output.delete_prefix(0, &format!("uniswap_day_data:{}:", day_id - 1));
+    store.set(
+        0,
+        keyer::candles_timestamp(),
+        &BigInt::from(timestamp_seconds),
+    );
+}
+
+#[substreams::handlers::map]
+pub fn map_candle_swaps(
+    params: String,
+    timestamp_deltas: store::Deltas<DeltaBigInt>,
+    events: Events,
+    pools_store: StoreGetProto<Pool>,
+    eth_prices_store: StoreGetBigDecimal,
+) -> Result<CandleSwaps, Error> {
+    let intervals = utils::parse_candle_intervals(&params);
+    let (previous_timestamp, timestamp) = match utils::candle_timestamps(&timestamp_deltas) {
+        None => return Ok(CandleSwaps::default()),
+        Some(timestamps) => timestamps,
+    };
+
+    // the candle closed when the period changed is emitted by map_pool_candles on that block,
+    // so the one closed on the period change before is the one to drop
+    let mut expired_prefixes = vec![];
+    for (interval, seconds) in &intervals {
+        let previous_period_start = previous_timestamp - previous_timestamp % seconds;
+        let period_start = timestamp - timestamp % seconds;
+        if period_start != previous_period_start && previous_period_start >= *seconds {
+            expired_prefixes.push(keyer::candle_prefix(
+                interval,
+                previous_period_start - seconds,
+            ));
+        }
+    }
 
+    let mut candle_swaps = vec![];
     for event in events.events {
-        let pool: Pool = match store_pool.get_last(keyer::pool_key(&event.pool_address)) {
+        let swap_leg = match event.swap_leg() {
+            None => continue,
+            Some(swap_leg) => swap_leg,
+        };
+        let swap = match event.r#type.unwrap() {
+            SwapEvent(swap) => swap,
+            _ => continue,
+        };
+        let pool = match pools_store.get_last(keyer::pool_key(&event.pool_address)) {
             None => continue,
             Some(pool) => pool,
         };
-        match store_total_tx_counts.get_last(keyer::pool_total_tx_count(&event.pool_address)) {
-            None => {}
-            Some(_) => match event.r#type.unwrap() {
-                SwapEvent(swap) => {
-                    let eth_price_in_usd: BigDecimal =
-                        match store_eth_prices.get_last(&keyer::bundle_eth_price()) {
-                            None => {
-                                panic!("bundle eth price not found")
-                            }
-                            Some(price) => price,
-                        };
 
-                    let token0_derived_eth_price: BigDecimal =
-                        match store_eth_prices.get_last(keyer::token_eth_price(&event.token0)) {
-                            None => continue,
-                            Some(price) => price,
-                        };
+        let (token0_price, _) = price::sqrt_price_x96_to_token_prices(
+            BigDecimal::from(swap.sqrt_price.unwrap()),
+            pool.token0.as_ref().unwrap(),
+            pool.token1.as_ref().unwrap(),
+        );
+        let (amount0, amount1) = match swap_leg.token_in == event.token0 {
+            true => (swap_leg.amount_in.clone(), swap_leg.amount_out.clone()),
+            false => (swap_leg.amount_out.clone(), swap_leg.amount_in.clone()),
+        };
 
-                    let token1_derived_eth_price: BigDecimal =
-                        match store_eth_prices.get_last(keyer::token_eth_price(&event.token1)) {
-                            None => continue,
-                            Some(price) => price,
-                        };
+        let mut amount_usd = utils::token_amount_usd(
+            &swap_leg.amount_in,
+            &swap_leg.token_in,
+            event.log_ordinal,
+            &eth_prices_store,
+        );
+        if amount_usd.eq(&BigDecimal::zero()) {
+            amount_usd = utils::token_amount_usd(
+                &swap_leg.amount_out,
+                &swap_leg.token_out,
+                event.log_ordinal,
+                &eth_prices_store,
+            );
+        }
 
-                    let mut amount0_abs: BigDecimal = BigDecimal::from(swap.amount_0.unwrap());
-                    if amount0_abs.lt(&BigDecimal::from(0 as u64)) {
-                        amount0_abs = amount0_abs.mul(BigDecimal::from(-1 as i64))
-                    }
+        for (interval, seconds) in &intervals {
+            candle_swaps.push(CandleSwap {
+                interval: interval.clone(),
+                period_start: timestamp - timestamp % seconds,
+                pool_address: event.pool_address.clone(),
+                token0_price: Some(token0_price.clone().into()),
+                amount0: Some(amount0.clone().into()),
+                amount1: Some(amount1.clone().into()),
+                amount_usd: Some(amount_usd.clone().into()),
+                log_ordinal: event.log_ordinal,
+            });
+        }
+    }
 
-                    let mut amount1_abs: BigDecimal = BigDecimal::from(swap.amount_1.unwrap());
-                    if amount1_abs.lt(&BigDecimal::from(0 as u64)) {
-                        amount1_abs = amount1_abs.mul(BigDecimal::from(-1 as i64))
-                    }
+    Ok(CandleSwaps {
+        candle_swaps,
+        expired_prefixes,
+    })
+}
 
-                    log::debug!("trx_id: {}", event.transaction_id);
-                    log::debug!("bundle.ethPriceUSD: {}", eth_price_in_usd);
-                    log::debug!("token0_derived_eth_price: {}", token0_derived_eth_price);
-                    log::debug!("token1_derived_eth_price: {}", token1_derived_eth_price);
-                    log::debug!("amount0_abs: {}", amount0_abs);
-                    log::debug!("amount1_abs: {}", amount1_abs);
+#[substreams::handlers::store]
+pub fn store_candle_pools(candle_swaps: CandleSwaps, store: StoreAppend<String>) {
+    for prefix in &candle_swaps.expired_prefixes {
+        store.delete_prefix(0, prefix);
+    }
 
-                    let amount_total_usd_tracked: BigDecimal = utils::get_tracked_amount_usd(
-                        &event.token0,
-                        &event.token1,
-                        &token0_derived_eth_price,
-                        &token1_derived_eth_price,
-                        &amount0_abs,
-                        &amount1_abs,
-                        &eth_price_in_usd,
-                    )
-                    .div(BigDecimal::from(2 as i32));
+    for candle_swap in candle_swaps.candle_swaps {
+        store.append(
+            candle_swap.log_ordinal,
+            keyer::candle_pools(&candle_swap.interval, candle_swap.period_start),
+            candle_swap.pool_address,
+        );
+    }
+}
 
-                    let amount_total_eth_tracked =
-                        math::safe_div(&amount_total_usd_tracked, &eth_price_in_usd);
+#[substreams::handlers::store]
+pub fn store_candle_opens(candle_swaps: CandleSwaps, store: StoreSetIfNotExistsBigDecimal) {
+    for prefix in &candle_swaps.expired_prefixes {
+        store.delete_prefix(0, prefix);
+    }
 
-                    let amount_total_usd_untracked: BigDecimal = amount0_abs
-                        .clone()
-                        .add(amount1_abs.clone())
-                        .div(BigDecimal::from(2 as i32));
+    for candle_swap in candle_swaps.candle_swaps {
+        store.set_if_not_exists(
+            candle_swap.log_ordinal,
+            keyer::candle(
+                &candle_swap.interval,
+                candle_swap.period_start,
+                &candle_swap.pool_address,
+            ),
+            &BigDecimal::from(candle_swap.token0_price.unwrap()),
+        );
+    }
+}
 
-                    let fee_tier: BigDecimal = BigDecimal::from(pool.fee_tier.unwrap());
-                    let fee_usd: BigDecimal = amount_total_usd_tracked
-                        .clone()
-                        .mul(fee_tier.clone())
-                        .div(BigDecimal::from(1000000 as u64));
-                    let fee_eth: BigDecimal = amount_total_eth_tracked
-                        .clone()
-                        .mul(fee_tier)
-                        .div(BigDecimal::from(1000000 as u64));
+#[substreams::handlers::store]
+pub fn store_candle_highs(candle_swaps: CandleSwaps, store: StoreMaxBigDecimal) {
+    for prefix in &candle_swaps.expired_prefixes {
+        store.delete_prefix(0, prefix);
+    }
 
-                    output.add_many(
-                        event.log_ordinal,
-                        &vec![
-                            keyer::swap_volume_token_0(&event.pool_address),
-                            keyer::swap_token_volume(&event.token0, "token0".to_string()),
-                        ],
-                        &amount0_abs,
-                    );
-                    output.add_many(
-                        event.log_ordinal,
-                        &vec![
-                            keyer::swap_volume_token_1(&event.pool_address),
-                            keyer::swap_token_volume(&event.token1, "token1".to_string()),
-                        ],
-                        &amount1_abs,
-                    );
-                    output.add_many(
-                        event.log_ordinal,
-                        &vec![
-                            keyer::swap_volume_usd(&event.pool_address),
-                            keyer::swap_token_volume_usd(&event.token0),
-                            keyer::swap_token_volume_usd(&event.token1),
-                            keyer::swap_factory_total_volume_usd(),
-                            keyer::swap_uniswap_day_data_volume_usd(day_id.to_string()),
-                        ],
-                        &amount_total_usd_tracked,
-                    );
-                    output.add_many(
-                        event.log_ordinal,
-                        &vec![
-                            keyer::swap_untracked_volume_usd(&event.pool_address),
-                            keyer::swap_token_volume_untracked_volume_usd(&event.token0),
-                            keyer::swap_token_volume_untracked_volume_usd(&event.token1),
-                            keyer::swap_factory_untracked_volume_usd(),
-                        ],
-                        &amount_total_usd_untracked,
-                    );
-                    output.add_many(
-                        event.log_ordinal,
-                        &vec![
-                            keyer::swap_fee_usd(&event.pool_address),
-                            keyer::swap_token_fee_usd(&event.token0),
-                            keyer::swap_token_fee_usd(&event.token1),
-                            keyer::swap_factory_total_fees_usd(),
-                            keyer::swap_uniswap_day_data_fees_usd(day_id.to_string()),
-                        ],
-                        &fee_usd,
-                    );
-                    output.add(
-                        event.log_ordinal,
-                        keyer::swap_factory_total_volume_eth(),
-                        &amount_total_eth_tracked.clone(),
-                    );
-                    output.add(
-                        event.log_ordinal,
-                        keyer::swap_factory_total_fees_eth(),
-                        &fee_eth,
-                    );
-                    output.add(
-                        event.log_ordinal,
-                        keyer::swap_uniswap_day_data_volume_eth(day_id.to_string()),
-                        &amount_total_eth_tracked,
-                    );
-                }
-                _ => {}
-            },
-        }
+    for candle_swap in candle_swaps.candle_swaps {
+        store.max(
+            candle_swap.log_ordinal,
+            keyer::candle(
+                &candle_swap.interval,
+                candle_swap.period_start,
+                &candle_swap.pool_address,
+            ),
+            BigDecimal::from(candle_swap.token0_price.unwrap()),
+        );
     }
 }
 
 #[substreams::handlers::store]
-pub fn store_pool_fee_growth_global_x128(pools: Pools, store: StoreSetBigInt) {
-    for pool in pools.pools {
-        log::info!(
-            "pool address: {} trx_id:{}",
-            pool.address,
-            pool.transaction_id
-        );
-        let (big_int_1, big_int_2) = rpc::fee_growth_global_x128_call(&pool.address);
-        log::debug!("big decimal0: {}", big_int_1);
-        log::debug!("big decimal1: {}", big_int_2);
+pub fn store_candle_lows(candle_swaps: CandleSwaps, store: StoreMinBigDecimal) {
+    for prefix in &candle_swaps.expired_prefixes {
+        store.delete_prefix(0, prefix);
+    }
 
-        store.set(
-            pool.log_ordinal,
-            keyer::pool_fee_growth_global_x128(&pool.address, "token0".to_string()),
-            &big_int_1,
-        );
-        store.set(
-            pool.log_ordinal,
-            keyer::pool_fee_growth_global_x128(&pool.address, "token1".to_string()),
-            &big_int_2,
+    for candle_swap in candle_swaps.candle_swaps {
+        store.min(
+            candle_swap.log_ordinal,
+            keyer::candle(
+                &candle_swap.interval,
+                candle_swap.period_start,
+                &candle_swap.pool_address,
+            ),
+            BigDecimal::from(candle_swap.token0_price.unwrap()),
         );
     }
 }
 
 #[substreams::handlers::store]
-pub fn store_native_total_value_locked(
-    event_amounts: uniswap::EventAmounts,
-    store: StoreAddBigDecimal,
-) {
-    for event_amount in event_amounts.event_amounts {
-        let amount0: BigDecimal = event_amount.amount0_value.unwrap().into();
-        let amount1: BigDecimal = event_amount.amount1_value.unwrap().into();
-        store.add_many(
-            event_amount.log_ordinal,
-            &vec![
-                keyer::token_native_total_value_locked(&event_amount.token0_addr),
-                keyer::pool_native_total_value_locked_token(
-                    &event_amount.pool_address,
-                    &event_amount.token0_addr,
-                ),
-            ],
-            &amount0,
-        );
-        store.add_many(
-            event_amount.log_ordinal,
-            &vec![
-                keyer::token_native_total_value_locked(&event_amount.token1_addr),
-                keyer::pool_native_total_value_locked_token(
-                    &event_amount.pool_address,
-                    &event_amount.token1_addr,
-                ),
-            ],
-            &amount1,
+pub fn store_candle_closes(candle_swaps: CandleSwaps, store: StoreSetBigDecimal) {
+    for prefix in &candle_swaps.expired_prefixes {
+        store.delete_prefix(0, prefix);
+    }
+
+    for candle_swap in candle_swaps.candle_swaps {
+        store.set(
+            candle_swap.log_ordinal,
+            keyer::candle(
+                &candle_swap.interval,
+                candle_swap.period_start,
+                &candle_swap.pool_address,
+            ),
+            &BigDecimal::from(candle_swap.token0_price.unwrap()),
         );
     }
 }
 
 #[substreams::handlers::store]
-pub fn store_eth_prices(
-    pool_sqrt_prices: PoolSqrtPrices,
-    pools_store: StoreGetProto<Pool>,
-    prices_store: StoreGetBigDecimal,
-    tokens_whitelist_pools_store: StoreGetRaw,
-    total_native_value_locked_store: StoreGetBigDecimal,
-    pool_liquidities_store: StoreGetBigInt,
-    store: StoreSetBigDecimal,
-) {
-    for pool_sqrt_price in pool_sqrt_prices.pool_sqrt_prices {
-        log::debug!(
-            "handling pool price update - addr: {} price: {}",
-            pool_sqrt_price.pool_address,
-            pool_sqrt_price.sqrt_price.unwrap().value
-        );
-        let pool = pools_store.must_get_last(&keyer::pool_key(&pool_sqrt_price.pool_address));
-        let token_0 = pool.token0.as_ref().unwrap();
-        let token_1 = pool.token1.as_ref().unwrap();
-
-        token_0.log();
-        token_1.log();
-
-        let bundle_eth_price_usd =
-            price::get_eth_price_in_usd(&prices_store, pool_sqrt_price.ordinal);
-        log::info!("bundle_eth_price_usd: {}", bundle_eth_price_usd);
-
-        let token0_derived_eth_price: BigDecimal = price::find_eth_per_token(
-            pool_sqrt_price.ordinal,
-            &pool.address,
-            &token_0.address,
-            &pools_store,
-            &pool_liquidities_store,
-            &tokens_whitelist_pools_store,
-            &total_native_value_locked_store,
-            &prices_store,
-        );
-        log::info!(
-            "token 0 {} derived eth price: {}",
-            token_0.address,
-            token0_derived_eth_price
-        );
+pub fn store_candle_volumes(candle_swaps: CandleSwaps, store: StoreAddBigDecimal) {
+    for prefix in &candle_swaps.expired_prefixes {
+        store.delete_prefix(0, prefix);
+    }
 
-        let token1_derived_eth_price: BigDecimal = price::find_eth_per_token(
-            pool_sqrt_price.ordinal,
-            &pool.address,
-            &token_1.address,
-            &pools_store,
-            &pool_liquidities_store,
-            &tokens_whitelist_pools_store,
-            &total_native_value_locked_store,
-            &prices_store,
-        );
-        log::info!(
-            "token 1 {} derived eth price: {}",
-            token_1.address,
-            token1_derived_eth_price
+    for candle_swap in candle_swaps.candle_swaps {
+        let candle_key = keyer::candle(
+            &candle_swap.interval,
+            candle_swap.period_start,
+            &candle_swap.pool_address,
         );
-
-        store.set(
-            pool_sqrt_price.ordinal,
-            keyer::bundle_eth_price(),
-            &bundle_eth_price_usd,
+        store.add(
+            candle_swap.log_ordinal,
+            keyer::candle_volume(&candle_key, "volumeToken0"),
+            &BigDecimal::from(candle_swap.amount0.unwrap()),
         );
-
-        store.set(
-            pool_sqrt_price.ordinal,
-            keyer::token_eth_price(&token_0.address),
-            &token0_derived_eth_price,
+        store.add(
+            candle_swap.log_ordinal,
+            keyer::candle_volume(&candle_key, "volumeToken1"),
+            &BigDecimal::from(candle_swap.amount1.unwrap()),
         );
-
-        store.set(
-            pool_sqrt_price.ordinal,
-            keyer::token_eth_price(&token_1.address),
-            &token1_derived_eth_price,
+        store.add(
+            candle_swap.log_ordinal,
+            keyer::candle_volume(&candle_key, "volumeUSD"),
+            &BigDecimal::from(candle_swap.amount_usd.unwrap()),
         );
     }
 }
 
 #[substreams::handlers::store]
-pub fn store_total_value_locked_by_tokens(events: Events, store: StoreAddBigDecimal) {
-    for event in events.events {
-        log::debug!("trx_id: {}", event.transaction_id);
-        let mut amount0: BigDecimal;
-        let mut amount1: BigDecimal;
-
-        match event.r#type.unwrap() {
-            BurnEvent(burn) => {
-                amount0 = burn.amount_0.unwrap().into();
-                amount0 = amount0.neg();
-                amount1 = burn.amount_1.unwrap().into();
-                amount1 = amount1.neg();
-            }
-            MintEvent(mint) => {
-                amount0 = mint.amount_0.unwrap().into();
-                amount1 = mint.amount_1.unwrap().into();
-            }
-            SwapEvent(swap) => {
-                amount0 = swap.amount_0.unwrap().into();
-                amount1 = swap.amount_1.unwrap().into();
-            }
-        }
+pub fn store_candle_trade_counts(candle_swaps: CandleSwaps, store: StoreAddBigInt) {
+    for prefix in &candle_swaps.expired_prefixes {
+        store.delete_prefix(0, prefix);
+    }
 
+    for candle_swap in candle_swaps.candle_swaps {
         store.add(
-            event.log_ordinal,
-            keyer::total_value_locked_by_tokens(
-                &event.pool_address,
-                &event.token0,
-                "token0".to_string(),
-            ),
-            &amount0,
-        );
-        store.add(
-            event.log_ordinal,
-            keyer::total_value_locked_by_tokens(
-                &event.pool_address,
-                &event.token1,
-                "token1".to_string(),
+            candle_swap.log_ordinal,
+            keyer::candle(
+                &candle_swap.interval,
+                candle_swap.period_start,
+                &candle_swap.pool_address,
             ),
-            &amount1,
+            &BigInt::one(),
         );
     }
 }
 
-#[substreams::handlers::store]
-pub fn store_total_value_locked(
-    native_total_value_locked_deltas: store::Deltas<DeltaBigDecimal>,
-    pools_store: StoreGetProto<Pool>,
-    eth_prices_store: StoreGetBigDecimal,
-    store: StoreSetBigDecimal,
-) {
-    // fixme: @julien: what is the use for the pool aggregator here ?
-    let mut pool_aggregator: HashMap<String, (u64, BigDecimal)> = HashMap::from([]);
+#[substreams::handlers::map]
+pub fn map_pool_candles(
+    params: String,
+    timestamp_deltas: store::Deltas<DeltaBigInt>,
+    candle_pools_store: StoreGetArray<String>,
+    opens_store: StoreGetBigDecimal,
+    highs_store: StoreGetBigDecimal,
+    lows_store: StoreGetBigDecimal,
+    closes_store: StoreGetBigDecimal,
+    volumes_store: StoreGetBigDecimal,
+    trade_counts_store: StoreGetBigInt,
+) -> Result<PoolCandles, Error> {
+    let (previous_timestamp, timestamp) = match utils::candle_timestamps(&timestamp_deltas) {
+        None => return Ok(PoolCandles::default()),
+        Some(timestamps) => timestamps,
+    };
 
-    // fixme: are we sure we want to unwrap and fail here ? we can't even go over the first block..
-    // let eth_price_usd = helper::get_eth_price(&eth_prices_store).unwrap();
+    let mut pool_candles = vec![];
+    for (interval, seconds) in utils::parse_candle_intervals(&params) {
+        // a candle closes on the first block of a later period
+        let period_start = previous_timestamp - previous_timestamp % seconds;
+        if period_start == timestamp - timestamp % seconds {
+            continue;
+        }
 
-    for native_total_value_locked in native_total_value_locked_deltas.deltas {
-        let eth_price_usd: BigDecimal = match &eth_prices_store.get_last(&keyer::bundle_eth_price())
+        let mut pools: Vec<String> = vec![];
+        for pool_address in candle_pools_store
+            .get_last(keyer::candle_pools(&interval, period_start))
+            .unwrap_or_default()
         {
+            if !pools.contains(&pool_address) {
+                pools.push(pool_address);
+            }
+        }
+
+        for pool_address in pools {
+            let candle_key = keyer::candle(&interval, period_start, &pool_address);
+            let open = opens_store.get_last(&candle_key).unwrap();
+            let high = highs_store.get_last(&candle_key).unwrap();
+            let low = lows_store.get_last(&candle_key).unwrap();
+            let close = closes_store.get_last(&candle_key).unwrap();
+            let volume = |name: &str| -> BigDecimal {
+                volumes_store
+                    .get_last(keyer::candle_volume(&candle_key, name))
+                    .unwrap_or(BigDecimal::zero())
+            };
+
+            // token1 is priced in token0 as the inverse of the token0 price, flipping high and low
+            let one = BigDecimal::one();
+            pool_candles.push(PoolCandle {
+                id: format!("{}-{}-{}", pool_address, interval, period_start),
+                pool: pool_address.clone(),
+                interval: interval.clone(),
+                period_start,
+                token0_price_open: Some(open.clone().into()),
+                token0_price_high: Some(high.clone().into()),
+                token0_price_low: Some(low.clone().into()),
+                token0_price_close: Some(close.clone().into()),
+                token1_price_open: Some(math::safe_div(&one, &open).into()),
+                token1_price_high: Some(math::safe_div(&one, &low).into()),
+                token1_price_low: Some(math::safe_div(&one, &high).into()),
+                token1_price_close: Some(math::safe_div(&one, &close).into()),
+                volume_token0: Some(volume("volumeToken0").into()),
+                volume_token1: Some(volume("volumeToken1").into()),
+                volume_usd: Some(volume("volumeUSD").into()),
+                trade_count: trade_counts_store
+                    .get_last(&candle_key)
+                    .unwrap_or(BigInt::zero())
+                    .to_u64(),
+            });
+        }
+    }
+
+    Ok(PoolCandles { pool_candles })
+}
+
+#[substreams::handlers::map]
+pub fn map_sandwiches(
+    block: Block,
+    events: Events,
+    prices_store: StoreGetBigDecimal,
+    eth_prices_store: StoreGetBigDecimal,
+) -> Result<Sandwiches, Error> {
+    let transaction_indexes: HashMap<String, u32> = block
+        .transaction_traces
+        .iter()
+        .map(|trx| (Hex(&trx.hash).to_string(), trx.index))
+        .collect();
+
+    // swaps of each pool, in block order
+    let mut pools: Vec<String> = vec![];
+    let mut pool_swaps: HashMap<String, Vec<(u32, Event, SwapLeg, uniswap::Swap)>> = HashMap::new();
+    for event in events.events {
+        let swap_leg = match event.swap_leg() {
             None => continue,
-            Some(price) => price.with_prec(100),
+            Some(swap_leg) => swap_leg,
         };
-        log::debug!(
-            "eth_price_usd: {}, native_total_value_locked.key: {}",
-            eth_price_usd,
-            native_total_value_locked.key
-        );
-        if let Some(token_addr) = keyer::native_token_from_key(&native_total_value_locked.key) {
-            let value = &native_total_value_locked.new_value;
-            let token_derive_eth: BigDecimal =
-                match eth_prices_store.get_last(&keyer::token_eth_price(&token_addr)) {
-                    None => panic!("token eth price not found for token {}", token_addr),
-                    Some(price) => price,
-                };
+        let swap = match event.r#type.clone().unwrap() {
+            SwapEvent(swap) => swap,
+            _ => continue,
+        };
+        let transaction_index = *transaction_indexes.get(&event.transaction_id).unwrap();
 
-            let total_value_locked_usd = value
-                .clone()
-                .mul(token_derive_eth)
-                .mul(eth_price_usd.clone());
+        if !pool_swaps.contains_key(&event.pool_address) {
+            pools.push(event.pool_address.clone());
+        }
+        pool_swaps
+            .entry(event.pool_address.clone())
+            .or_insert(vec![])
+            .push((transaction_index, event, swap_leg, swap));
+    }
 
-            log::info!(
-                "token {} total value locked usd: {}",
-                token_addr,
-                total_value_locked_usd
-            );
-            store.set(
-                native_total_value_locked.ordinal,
-                keyer::token_usd_total_value_locked(&token_addr),
-                &total_value_locked_usd,
-            );
-        } else if let Some((pool_addr, token_addr)) =
-            keyer::native_pool_from_key(&native_total_value_locked.key)
-        {
-            let pool = pools_store.must_get_last(keyer::pool_key(&pool_addr));
-            // we only want to use the token0
-            if pool.token0.as_ref().unwrap().address != token_addr {
+    let mut sandwiches = vec![];
+    for pool_address in pools {
+        let swaps = pool_swaps.get(&pool_address).unwrap();
+        let mut back_runs: Vec<usize> = vec![];
+
+        for (i, (front_index, front_event, front_leg, front_swap)) in swaps.iter().enumerate() {
+            if back_runs.contains(&i) {
                 continue;
             }
-            let value: BigDecimal = native_total_value_locked.new_value;
-            let token_derive_eth: BigDecimal =
-                match eth_prices_store.get_last(&keyer::token_eth_price(&token_addr)) {
-                    None => panic!("token eth price not found for token {}", token_addr),
-                    Some(price) => price,
-                };
-            let partial_pool_total_value_locked_eth = value.mul(token_derive_eth);
-            log::info!(
-                "partial pool {} token {} partial total value locked usd: {}",
-                pool_addr,
-                token_addr,
-                partial_pool_total_value_locked_eth,
-            );
-            let aggregate_key = pool_addr.clone();
-
-            //fixme: @julien: it seems we never actually enter here... as it would only be valid if we have
-            // twice a valid event on the same pool
-            if let Some(pool_agg) = pool_aggregator.get(&aggregate_key) {
-                let count = &pool_agg.0;
-                let rolling_sum = &pool_agg.1;
-                log::info!("found another partial pool value {} token {} count {} partial total value locked usd: {}",
-                    pool_addr,
-                    token_addr,
-                    count,
-                    rolling_sum,
-                );
-                if count >= &(2 as u64) {
-                    panic!(
-                        "{}",
-                        format!("this is unexpected should only see 2 pool keys")
-                    )
-                }
 
-                log::info!(
-                    "partial_pool_total_value_locked_eth: {} and rolling_sum: {}",
-                    partial_pool_total_value_locked_eth,
-                    rolling_sum,
-                );
-                let pool_total_value_locked_eth =
-                    partial_pool_total_value_locked_eth.add(rolling_sum.clone());
-                let pool_total_value_locked_usd = pool_total_value_locked_eth
-                    .clone()
-                    .mul(eth_price_usd.clone());
-                store.set(
-                    native_total_value_locked.ordinal,
-                    keyer::pool_eth_total_value_locked(&pool_addr),
-                    &pool_total_value_locked_eth,
-                );
-                store.set(
-                    native_total_value_locked.ordinal,
-                    keyer::pool_usd_total_value_locked(&pool_addr),
-                    &pool_total_value_locked_usd,
-                );
+            // the back-run sells what the front-run bought, sent by the same account or,
+            // through a shared recipient contract, selling back the exact amount bought
+            let back_run =
+                swaps
+                    .iter()
+                    .enumerate()
+                    .skip(i + 1)
+                    .find(|(_, (index, _, leg, swap))| {
+                        index > front_index
+                            && leg.token_in == front_leg.token_out
+                            && (swap.origin == front_swap.origin
+                                || (swap.recipient == front_swap.recipient
+                                    && leg.amount_in.eq(&front_leg.amount_out)))
+                    });
+            let (k, (back_index, back_event, back_leg, _)) = match back_run {
+                None => continue,
+                Some(back_run) => back_run,
+            };
 
+            let victims: Vec<&(u32, Event, SwapLeg, uniswap::Swap)> = swaps[i + 1..k]
+                .iter()
+                .filter(|(index, _, leg, swap)| {
+                    index > front_index
+                        && index < back_index
+                        && leg.token_in == front_leg.token_in
+                        && swap.origin != front_swap.origin
+                })
+                .collect();
+            if victims.is_empty() {
                 continue;
             }
-            pool_aggregator.insert(
-                aggregate_key.clone(),
-                (1, partial_pool_total_value_locked_eth),
+            back_runs.push(k);
+
+            // price of token_out per token_in, the price key is named after the quoted token
+            let token_out_slot = if front_leg.token_out == front_event.token0 {
+                "token0"
+            } else {
+                "token1"
+            };
+            let price_key = keyer::prices_pool_token_key(
+                &pool_address,
+                &front_leg.token_out,
+                token_out_slot.to_string(),
             );
-            log::info!("partial inserted");
-        }
-    }
-}
+            let price_before_front_run = prices_store
+                .get_at(front_event.log_ordinal - 1, &price_key)
+                .unwrap_or(BigDecimal::zero());
+
+            let mut victim_loss = BigDecimal::zero();
+            for (_, victim_event, victim_leg, _) in victims.iter() {
+                let price_before_victim = prices_store
+                    .get_at(victim_event.log_ordinal - 1, &price_key)
+                    .unwrap_or(BigDecimal::zero());
+                let missed = victim_leg
+                    .amount_in
+                    .clone()
+                    .mul(price_before_front_run.clone().sub(price_before_victim));
+                if missed.gt(&BigDecimal::zero()) {
+                    victim_loss = victim_loss.add(missed);
+                }
+            }
 
-#[substreams::handlers::map]
-pub fn map_ticks(events: Events) -> Result<Ticks, Error> {
-    let mut out: Ticks = Ticks { ticks: vec![] };
-    for event in events.events {
-        match event.r#type.unwrap() {
-            BurnEvent(burn) => {
-                log::debug!("burn event transaction_id: {}", event.transaction_id);
-                let lower_tick_id: String = format!(
-                    "{}#{}",
-                    &event.pool_address,
-                    burn.tick_lower.as_ref().unwrap().value
-                );
-                let lower_tick_idx: BigInt = burn.tick_lower.unwrap().into();
-                let lower_tick_price0 = math::big_decimal_exponated(
-                    BigDecimal::try_from(1.0001).unwrap().with_prec(100),
-                    lower_tick_idx.clone(),
-                );
-                let lower_tick_price1 =
-                    math::safe_div(&BigDecimal::from(1 as i32), &lower_tick_price0);
-
-                //todo: implement the fee_growth_outside_x128_call mimicked from the smart contract
-                // to reduce the number of rpc calls to do
-                let lower_tick_result =
-                    rpc::fee_growth_outside_x128_call(&event.pool_address, &lower_tick_idx);
+            let attacker_profit = back_leg.amount_out.clone().sub(front_leg.amount_in.clone());
+            let leftover = front_leg.amount_out.clone().sub(back_leg.amount_in.clone());
+            let attacker_profit_usd = utils::token_amount_usd(
+                &attacker_profit,
+                &front_leg.token_in,
+                back_event.log_ordinal,
+                &eth_prices_store,
+            )
+            .add(utils::token_amount_usd(
+                &leftover,
+                &front_leg.token_out,
+                back_event.log_ordinal,
+                &eth_prices_store,
+            ));
+            let victim_loss_usd = utils::token_amount_usd(
+                &victim_loss,
+                &front_leg.token_out,
+                back_event.log_ordinal,
+                &eth_prices_store,
+            );
 
-                let tick_lower: Tick = Tick {
-                    id: lower_tick_id,
-                    pool_address: event.pool_address.to_string(),
-                    idx: Some(lower_tick_idx.into()),
-                    price0: Some(lower_tick_price0.into()),
-                    price1: Some(lower_tick_price1.into()),
-                    created_at_timestamp: event.timestamp,
-                    created_at_block_number: event.created_at_block_number,
-                    fee_growth_outside_0x_128: Some(lower_tick_result.0.into()),
-                    fee_growth_outside_1x_128: Some(lower_tick_result.1.into()),
-                    log_ordinal: event.log_ordinal,
-                    amount: burn.amount.clone(),
-                    r#type: Lower as i32,
-                    origin: Burn as i32,
-                };
+            let mut victim_transactions: Vec<String> = vec![];
+            for (_, victim_event, _, _) in victims.iter() {
+                if !victim_transactions.contains(&victim_event.transaction_id) {
+                    victim_transactions.push(victim_event.transaction_id.clone());
+                }
+            }
 
-                let upper_tick_id: String = format!(
+            sandwiches.push(Sandwich {
+                id: format!(
                     "{}#{}",
-                    &event.pool_address,
-                    burn.tick_upper.as_ref().unwrap().value
-                );
-                let upper_tick_idx: BigInt = burn.tick_upper.unwrap().into();
-                let upper_tick_price0 = math::big_decimal_exponated(
-                    BigDecimal::try_from(1.0001).unwrap().with_prec(100),
-                    upper_tick_idx.clone(),
-                );
-                let upper_upper_price1 =
-                    math::safe_div(&BigDecimal::from(1 as i32), &upper_tick_price0);
-
-                let upper_tick_result =
-                    rpc::fee_growth_outside_x128_call(&event.pool_address, &upper_tick_idx);
+                    front_event.transaction_id, back_event.transaction_id
+                ),
+                pool: pool_address.clone(),
+                attacker: front_swap.origin.clone(),
+                front_run_transaction: front_event.transaction_id.clone(),
+                back_run_transaction: back_event.transaction_id.clone(),
+                victim_transactions,
+                token_in: front_leg.token_in.clone(),
+                token_out: front_leg.token_out.clone(),
+                attacker_profit: Some(attacker_profit.into()),
+                attacker_profit_usd: Some(attacker_profit_usd.into()),
+                victim_loss: Some(victim_loss.into()),
+                victim_loss_usd: Some(victim_loss_usd.into()),
+                block_number: block.number,
+                timestamp: back_event.timestamp,
+                log_ordinal: back_event.log_ordinal,
+            });
+        }
+    }
 
-                let tick_upper: Tick = Tick {
-                    id: upper_tick_id,
-                    pool_address: event.pool_address.to_string(),
-                    idx: Some(upper_tick_idx.into()),
-                    price0: Some(upper_tick_price0.into()),
-                    price1: Some(upper_upper_price1.into()),
-                    created_at_timestamp: event.timestamp,
-                    created_at_block_number: event.created_at_block_number,
-                    fee_growth_outside_0x_128: Some(upper_tick_result.0.into()),
-                    fee_growth_outside_1x_128: Some(upper_tick_result.1.into()),
-                    log_ordinal: event.log_ordinal,
-                    amount: burn.amount,
-                    r#type: Upper as i32,
-                    origin: Burn as i32,
-                };
+    Ok(Sandwiches { sandwiches })
+}
 
-                out.ticks.push(tick_lower);
-                out.ticks.push(tick_upper);
-            }
-            MintEvent(mint) => {
-                log::debug!("mint event transaction_id: {}", event.transaction_id);
-                let lower_tick_id: String = format!(
-                    "{}#{}",
-                    &event.pool_address,
-                    mint.tick_lower.as_ref().unwrap().value
-                );
-                let lower_tick_idx: BigInt = mint.tick_lower.unwrap().into();
-                let lower_tick_price0 = math::big_decimal_exponated(
-                    BigDecimal::try_from(1.0001).unwrap().with_prec(100),
-                    lower_tick_idx.clone(),
-                );
-                let lower_tick_price1 =
-                    math::safe_div(&BigDecimal::from(1 as i32), &lower_tick_price0);
+#[substreams::handlers::map]
+pub fn map_jit_liquidity(
+    events: Events,
+    all_positions: Positions,
+    position_owners_store: StoreGetString,
+    eth_prices_store: StoreGetBigDecimal,
+) -> Result<JitLiquidityEvents, Error> {
+    // liquidity minted through the NonfungiblePositionManager is identified by the NFT modified
+    // right after the pool event, in the same transaction
+    let nft_position = |event: &Event, position_type: PositionType| -> Option<String> {
+        all_positions
+            .positions
+            .iter()
+            .find(|position| {
+                position.convert_position_type() == position_type
+                    && position.transaction == event.transaction_id
+                    && position.pool == event.pool_address
+                    && position.log_ordinal > event.log_ordinal
+            })
+            .map(|position| position.id.clone())
+    };
+    let npm_address = Hex(NON_FUNGIBLE_POSITION_MANAGER).to_string();
 
-                let lower_tick_result =
-                    rpc::fee_growth_outside_x128_call(&event.pool_address, &lower_tick_idx);
+    let mut jit_liquidity_events = vec![];
+    let mut open_mints: Vec<(String, JitLiquidityEvent)> = vec![];
 
-                // in the subgraph, there is a `load` which is done to see if the tick
-                // exists and if it doesn't exist, createTick()
-                let tick_lower: Tick = Tick {
-                    id: lower_tick_id,
-                    pool_address: event.pool_address.to_string(),
-                    idx: Some(lower_tick_idx.into()),
-                    price0: Some(lower_tick_price0.into()),
-                    price1: Some(lower_tick_price1.into()),
-                    created_at_timestamp: event.timestamp,
-                    created_at_block_number: event.created_at_block_number,
-                    fee_growth_outside_0x_128: Some(lower_tick_result.0.into()),
-                    fee_growth_outside_1x_128: Some(lower_tick_result.1.into()),
-                    log_ordinal: event.log_ordinal,
-                    amount: mint.amount.clone(),
-                    r#type: Lower as i32,
-                    origin: Mint as i32,
+    for event in events.events.iter() {
+        match event.r#type.as_ref().unwrap() {
+            MintEvent(mint) => {
+                let (identity, owner, position_id) = if mint.owner == npm_address {
+                    let position_id = match nft_position(event, IncreaseLiquidity) {
+                        None => continue,
+                        Some(position_id) => position_id,
+                    };
+                    let owner = position_owners_store
+                        .get_last(keyer::position_owner(&position_id))
+                        .unwrap_or(Hex(ZERO_ADDRESS).to_string());
+                    (format!("position:{}", position_id), owner, position_id)
+                } else {
+                    (
+                        format!("owner:{}", mint.owner),
+                        mint.owner.clone(),
+                        String::new(),
+                    )
                 };
 
-                let upper_tick_id: String = format!(
-                    "{}#{}",
-                    &event.pool_address,
-                    mint.tick_upper.as_ref().unwrap().value
+                open_mints.push((
+                    identity,
+                    JitLiquidityEvent {
+                        id: format!("{}#{}", event.pool_address, event.log_ordinal),
+                        pool: event.pool_address.clone(),
+                        owner,
+                        position_id,
+                        tick_lower: mint.tick_lower.clone(),
+                        tick_upper: mint.tick_upper.clone(),
+                        liquidity: mint.amount.clone(),
+                        mint_transaction: event.transaction_id.clone(),
+                        burn_transaction: String::new(),
+                        swap_count: 0,
+                        swap_volume_usd: Some(BigDecimal::zero().into()),
+                        fees_token0: Some(BigDecimal::zero().into()),
+                        fees_token1: Some(BigDecimal::zero().into()),
+                        fees_usd: Some(BigDecimal::zero().into()),
+                        passive_fees_usd: Some(BigDecimal::zero().into()),
+                        block_number: event.created_at_block_number,
+                        timestamp: event.timestamp,
+                        log_ordinal: event.log_ordinal,
+                    },
+                ));
+            }
+            SwapEvent(swap) => {
+                let swap_leg = event.swap_leg().unwrap();
+                let tick: BigInt = swap.tick.clone().unwrap().into();
+                let swap_liquidity: BigDecimal = swap.liquidity.clone().unwrap().into();
+                let fee_tier = BigDecimal::from_str(&event.fee).unwrap();
+                let fee = swap_leg
+                    .amount_in
+                    .clone()
+                    .mul(fee_tier)
+                    .div(BigDecimal::from(1_000_000 as i32));
+                let fee_usd = utils::token_amount_usd(
+                    &fee,
+                    &swap_leg.token_in,
+                    event.log_ordinal,
+                    &eth_prices_store,
                 );
-                let upper_tick_idx: BigInt = mint.tick_upper.unwrap().into();
-                let upper_tick_price0 = math::big_decimal_exponated(
-                    BigDecimal::try_from(1.0001).unwrap().with_prec(100),
-                    upper_tick_idx.clone(),
+                let volume_usd = utils::token_amount_usd(
+                    &swap_leg.amount_in,
+                    &swap_leg.token_in,
+                    event.log_ordinal,
+                    &eth_prices_store,
                 );
-                let upper_tick_price1 =
-                    math::safe_div(&BigDecimal::from(1 as i32), &upper_tick_price0);
 
-                let upper_tick_result =
-                    rpc::fee_growth_outside_x128_call(&event.pool_address, &upper_tick_idx);
+                for (_, jit) in open_mints.iter_mut() {
+                    let tick_lower: BigInt = jit.tick_lower.clone().unwrap().into();
+                    let tick_upper: BigInt = jit.tick_upper.clone().unwrap().into();
+                    // the swap is counted when it ends within the range, the active liquidity
+                    // reported by the swap includes the JIT liquidity
+                    if jit.pool != event.pool_address
+                        || tick.lt(&tick_lower)
+                        || !tick.lt(&tick_upper)
+                    {
+                        continue;
+                    }
 
-                let tick_upper: Tick = Tick {
-                    id: upper_tick_id,
-                    pool_address: event.pool_address.to_string(),
-                    idx: Some(upper_tick_idx.into()),
-                    price0: Some(upper_tick_price0.into()),
-                    price1: Some(upper_tick_price1.into()),
-                    created_at_timestamp: event.timestamp,
-                    created_at_block_number: event.created_at_block_number,
-                    fee_growth_outside_0x_128: Some(upper_tick_result.0.into()),
-                    fee_growth_outside_1x_128: Some(upper_tick_result.1.into()),
-                    log_ordinal: event.log_ordinal,
-                    amount: mint.amount,
-                    r#type: Upper as i32,
-                    origin: Mint as i32,
+                    let liquidity: BigDecimal = jit.liquidity.clone().unwrap().into();
+                    let share = math::safe_div(&liquidity, &swap_liquidity);
+                    let jit_fee = fee.clone().mul(share.clone());
+                    let jit_fee_usd = fee_usd.clone().mul(share);
+
+                    if swap_leg.token_in == event.token0 {
+                        jit.fees_token0 = Some(
+                            BigDecimal::from(jit.fees_token0.clone().unwrap())
+                                .add(jit_fee)
+                                .into(),
+                        );
+                    } else {
+                        jit.fees_token1 = Some(
+                            BigDecimal::from(jit.fees_token1.clone().unwrap())
+                                .add(jit_fee)
+                                .into(),
+                        );
+                    }
+                    jit.passive_fees_usd = Some(
+                        BigDecimal::from(jit.passive_fees_usd.clone().unwrap())
+                            .add(fee_usd.clone().sub(jit_fee_usd.clone()))
+                            .into(),
+                    );
+                    jit.fees_usd = Some(
+                        BigDecimal::from(jit.fees_usd.clone().unwrap())
+                            .add(jit_fee_usd)
+                            .into(),
+                    );
+                    jit.swap_volume_usd = Some(
+                        BigDecimal::from(jit.swap_volume_usd.clone().unwrap())
+                            .add(volume_usd.clone())
+                            .into(),
+                    );
+                    jit.swap_count += 1;
+                }
+            }
+            BurnEvent(burn) => {
+                let identity = if burn.owner == npm_address {
+                    match nft_position(event, DecreaseLiquidity) {
+                        None => continue,
+                        Some(position_id) => format!("position:{}", position_id),
+                    }
+                } else {
+                    format!("owner:{}", burn.owner)
                 };
 
-                out.ticks.push(tick_lower);
-                out.ticks.push(tick_upper);
+                let open_mint = open_mints.iter().position(|(open_identity, jit)| {
+                    open_identity.eq(&identity)
+                        && jit.pool == event.pool_address
+                        && jit.tick_lower == burn.tick_lower
+                        && jit.tick_upper == burn.tick_upper
+                });
+                if let Some(index) = open_mint {
+                    let (_, mut jit) = open_mints.remove(index);
+                    if jit.swap_count == 0 {
+                        continue;
+                    }
+                    jit.burn_transaction = event.transaction_id.clone();
+                    jit.log_ordinal = event.log_ordinal;
+                    jit_liquidity_events.push(jit);
+                }
             }
-            _ => {}
         }
     }
-    Ok(out)
+
+    Ok(JitLiquidityEvents {
+        jit_liquidity_events,
+    })
 }
 
-#[substreams::handlers::store]
-pub fn store_ticks(ticks: Ticks, output: StoreSetProto<Tick>) {
-    for tick in ticks.ticks {
-        output.set(tick.log_ordinal, keyer::ticks(&tick.id), &tick);
+#[substreams::handlers::map]
+pub fn map_arbitrages(
+    trades: Trades,
+    transactions: Transactions,
+    eth_prices_store: StoreGetBigDecimal,
+) -> Result<Arbitrages, Error> {
+    // a trade going through several pools and back to its input token is an arbitrage
+    let cyclic_trades: Vec<Trade> = trades
+        .trades
+        .into_iter()
+        .filter(|trade| trade.token_in == trade.token_out && trade.pools.len() > 1)
+        .collect();
+
+    let mut arbitrages = vec![];
+    for trade in cyclic_trades.iter() {
+        let transaction = match transactions
+            .transactions
+            .iter()
+            .find(|transaction| transaction.id == trade.transaction_id)
+        {
+            None => continue,
+            Some(transaction) => transaction,
+        };
+        let transaction_arbitrages = cyclic_trades
+            .iter()
+            .filter(|other| other.transaction_id == trade.transaction_id)
+            .count();
+
+        let amount_in = BigDecimal::from(trade.amount_in.clone().unwrap());
+        let amount_out = BigDecimal::from(trade.amount_out.clone().unwrap());
+        let profit = amount_out.clone().sub(amount_in.clone());
+        let profit_usd = utils::token_amount_usd(
+            &profit,
+            &trade.token_in,
+            trade.log_ordinal,
+            &eth_prices_store,
+        );
+
+        // traces without a gas price leave the gas cost at zero
+        let gas_price = match transaction.gas_price.clone() {
+            None => BigInt::zero(),
+            Some(gas_price) => BigInt::from(gas_price),
+        };
+        let gas_cost_eth = BigInt::from(transaction.gas_used)
+            .mul(gas_price.clone())
+            .to_decimal(18)
+            .div(BigDecimal::from(transaction_arbitrages as i32));
+        let bundle_eth_price = eth_prices_store
+            .get_at(trade.log_ordinal, keyer::bundle_eth_price())
+            .unwrap_or(BigDecimal::zero());
+        let gas_cost_usd = gas_cost_eth.clone().mul(bundle_eth_price);
+
+        arbitrages.push(Arbitrage {
+            id: trade.id.clone(),
+            transaction_id: trade.transaction_id.clone(),
+            arbitrageur: trade.origin.clone(),
+            pools: trade.pools.clone(),
+            path: trade.path.clone(),
+            token: trade.token_in.clone(),
+            amount_in: Some(amount_in.into()),
+            amount_out: Some(amount_out.into()),
+            profit: Some(profit.into()),
+            profit_usd: Some(profit_usd.clone().into()),
+            volume_usd: trade.amount_usd.clone(),
+            gas_used: transaction.gas_used,
+            gas_price: Some(gas_price.into()),
+            gas_cost_eth: Some(gas_cost_eth.into()),
+            gas_cost_usd: Some(gas_cost_usd.clone().into()),
+            net_profit_usd: Some(profit_usd.sub(gas_cost_usd).into()),
+            block_number: trade.block_number,
+            timestamp: trade.timestamp,
+            log_ordinal: trade.log_ordinal,
+        });
     }
+
+    Ok(Arbitrages { arbitrages })
 }
 
 #[substreams::handlers::store]
-pub fn store_ticks_liquidities(ticks: Ticks, output: StoreAddBigInt) {
-    for tick in ticks.ticks {
-        log::debug!("tick id: {}", tick.id);
-        if tick.origin == Mint as i32 {
-            if tick.r#type == Lower as i32 {
-                output.add(
-                    tick.log_ordinal,
-                    keyer::tick_liquidities_net(&tick.id),
-                    &BigInt::from(tick.amount.as_ref().unwrap()),
-                );
-            } else {
-                // upper
-                output.add(
-                    tick.log_ordinal,
-                    keyer::tick_liquidities_net(&tick.id),
-                    &BigInt::from(tick.amount.as_ref().unwrap()).neg(),
-                );
-            }
-
-            output.add(
-                tick.log_ordinal,
-                keyer::tick_liquidities_gross(&tick.id),
-                &BigInt::from(tick.amount.unwrap()),
+pub fn store_pool_arbitrage_volumes(arbitrages: Arbitrages, store: StoreAddBigDecimal) {
+    for arbitrage in arbitrages.arbitrages {
+        let volume_usd = BigDecimal::from(arbitrage.volume_usd.unwrap());
+        for pool_address in arbitrage.pools.iter() {
+            store.add(
+                arbitrage.log_ordinal,
+                keyer::pool_arbitrage_volume_usd(pool_address),
+                &volume_usd,
             );
-        } else if tick.origin == Burn as i32 {
-            if tick.r#type == Lower as i32 {
-                output.add(
-                    tick.log_ordinal,
-                    keyer::tick_liquidities_net(&tick.id),
-                    &BigInt::from(tick.amount.as_ref().unwrap()).neg(),
-                );
-            } else {
-                // upper
-                output.add(
-                    tick.log_ordinal,
-                    keyer::tick_liquidities_net(&tick.id),
-                    &BigInt::from(tick.amount.as_ref().unwrap()),
-                );
+        }
+    }
+}
+
+#[substreams::handlers::map]
+pub fn map_pool_token_transfers(
+    block: Block,
+    pools_store: StoreGetProto<Pool>,
+) -> Result<PoolTokenTransfers, Error> {
+    let mut pool_token_transfers = vec![];
+    for log in block.logs() {
+        let transfer = match abi::erc20::events::Transfer::match_and_decode(log) {
+            None => continue,
+            Some(transfer) => transfer,
+        };
+        let token_address = Hex(log.address()).to_string();
+        let value = transfer.value;
+
+        for (pool_address, amount) in [
+            (Hex(&transfer.to).to_string(), value.clone()),
+            (Hex(&transfer.from).to_string(), value.neg()),
+        ] {
+            let pool = match pools_store.get_last(keyer::pool_key(&pool_address)) {
+                None => continue,
+                Some(pool) => pool,
+            };
+            if pool.token0_ref().address != token_address
+                && pool.token1_ref().address != token_address
+            {
+                continue;
             }
 
-            output.add(
-                tick.log_ordinal,
-                keyer::tick_liquidities_gross(&tick.id),
-                &BigInt::from(tick.amount.unwrap()).neg(),
-            );
+            pool_token_transfers.push(PoolTokenTransfer {
+                pool_address,
+                token_address: token_address.clone(),
+                amount: Some(amount.into()),
+                transaction_id: Hex(&log.receipt.transaction.hash).to_string(),
+                log_ordinal: log.ordinal(),
+            });
         }
     }
+
+    Ok(PoolTokenTransfers {
+        pool_token_transfers,
+    })
 }
 
 #[substreams::handlers::map]
-pub fn map_all_positions(
+pub fn map_token_transfer_behaviors(
     block: Block,
-    store_pool: StoreGetProto<Pool>,
-) -> Result<Positions, Error> {
-    let mut positions: Positions = Positions { positions: vec![] };
+    pools_store: StoreGetProto<Pool>,
+    pool_token_transfers: PoolTokenTransfers,
+) -> Result<TokenTransferBehaviors, Error> {
+    // keyed by (transaction, pool, token)
+    let mut transfer_amounts: HashMap<(String, String, String), BigInt> = HashMap::new();
+    for transfer in pool_token_transfers.pool_token_transfers {
+        let key = (
+            transfer.transaction_id,
+            transfer.pool_address,
+            transfer.token_address,
+        );
+        let amount = BigInt::from(transfer.amount.unwrap());
+        let total = match transfer_amounts.remove(&key) {
+            None => amount,
+            Some(total) => total.add(amount),
+        };
+        transfer_amounts.insert(key, total);
+    }
 
+    let mut ordered_keys: Vec<(String, String, String)> = vec![];
+    let mut event_amounts: HashMap<(String, String, String), (BigInt, u64)> = HashMap::new();
     for log in block.logs() {
-        if log.address() != NON_FUNGIBLE_POSITION_MANAGER {
+        // amounts moved in (positive) or out (negative) of the pool as accounted by the pool itself
+        let (amount0, amount1): (BigInt, BigInt) = if let Some(mint) =
+            abi::pool::events::Mint::match_and_decode(log)
+        {
+            (mint.amount0, mint.amount1)
+        } else if let Some(swap) = Swap::match_and_decode(log) {
+            (swap.amount0, swap.amount1)
+        } else if let Some(collect) = abi::pool::events::Collect::match_and_decode(log) {
+            (collect.amount0.neg(), collect.amount1.neg())
+        } else if let Some(collect) = abi::pool::events::CollectProtocol::match_and_decode(log) {
+            (collect.amount0.neg(), collect.amount1.neg())
+        } else if let Some(flash) = abi::pool::events::Flash::match_and_decode(log) {
+            (flash.paid0, flash.paid1)
+        } else {
             continue;
+        };
+
+        let pool = match pools_store.get_last(keyer::pool_key(&Hex(log.address()).to_string())) {
+            None => continue,
+            Some(pool) => pool,
+        };
+        let transaction_id = Hex(&log.receipt.transaction.hash).to_string();
+
+        for (token_address, amount) in [
+            (pool.token0_ref().address.clone(), amount0),
+            (pool.token1_ref().address.clone(), amount1),
+        ] {
+            let key = (transaction_id.clone(), pool.address.clone(), token_address);
+            let (total, log_ordinal) = match event_amounts.remove(&key) {
+                None => {
+                    ordered_keys.push(key.clone());
+                    (amount, log.ordinal())
+                }
+                Some((total, log_ordinal)) => (total.add(amount), log_ordinal),
+            };
+            event_amounts.insert(key, (total, log_ordinal));
         }
+    }
 
-        if let Some(event) = abi::positionmanager::events::IncreaseLiquidity::match_and_decode(log)
-        {
-            if let Some(position) = utils::get_position(
-                &store_pool,
-                &Hex(log.address()).to_string(),
-                &log.receipt.transaction.hash,
-                IncreaseLiquidity,
-                log.ordinal(),
-                block
-                    .header
-                    .as_ref()
-                    .unwrap()
-                    .timestamp
-                    .as_ref()
-                    .unwrap()
-                    .seconds as u64,
-                block.number,
-                PositionEvent {
-                    event: PositionEventType::IncreaseLiquidity(event),
-                },
-            ) {
-                positions.positions.push(position);
+    let mut token_transfer_behaviors: Vec<TokenTransferBehavior> = vec![];
+    for key in ordered_keys {
+        let (event_amount, log_ordinal) = event_amounts.remove(&key).unwrap();
+        let transfer_amount = transfer_amounts.remove(&key).unwrap_or(BigInt::zero());
+
+        let behavior_type = utils::classify_transfer_behavior(&transfer_amount, &event_amount);
+        if behavior_type == TransferBehaviorType::Standard {
+            continue;
+        }
+
+        let (transaction_id, pool_address, token_address) = key;
+        if token_transfer_behaviors
+            .iter()
+            .any(|behavior| behavior.token_address == token_address)
+        {
+            continue;
+        }
+        log::info!(
+            "token {} transfers in pool {} don't match events, transfers: {} events: {} trx_id: {}",
+            token_address,
+            pool_address,
+            transfer_amount,
+            event_amount,
+            transaction_id
+        );
+        token_transfer_behaviors.push(TokenTransferBehavior {
+            token_address,
+            pool_address,
+            transaction_id,
+            transfer_amount: Some(transfer_amount.into()),
+            event_amount: Some(event_amount.into()),
+            r#type: behavior_type as i32,
+            log_ordinal,
+        });
+    }
+
+    Ok(TokenTransferBehaviors {
+        token_transfer_behaviors,
+    })
+}
+
+// tokens are STANDARD until a fee on transfer is detected, which then sticks: the
+// first detection is kept and a token never goes back to STANDARD
+#[substreams::handlers::store]
+pub fn store_token_transfer_behaviors(
+    token_transfer_behaviors: TokenTransferBehaviors,
+    store: StoreSetIfNotExistsProto<TokenTransferBehavior>,
+) {
+    for token_transfer_behavior in token_transfer_behaviors.token_transfer_behaviors {
+        store.set_if_not_exists(
+            token_transfer_behavior.log_ordinal,
+            keyer::token_transfer_behavior(&token_transfer_behavior.token_address),
+            &token_transfer_behavior,
+        );
+    }
+}
+
+#[substreams::handlers::map]
+pub fn map_event_amounts(events: Events) -> Result<uniswap::EventAmounts, Error> {
+    let mut event_amounts = vec![];
+    for event in events.events {
+        log::debug!("transaction id: {}", event.transaction_id);
+        if event.r#type.is_none() {
+            continue;
+        }
+
+        if event.r#type.is_some() {
+            match event.r#type.unwrap() {
+                BurnEvent(burn) => {
+                    log::debug!("handling burn for pool {}", event.pool_address);
+                    let amount0: BigDecimal = burn.amount_0.unwrap().into();
+                    let amount1: BigDecimal = burn.amount_1.unwrap().into();
+                    event_amounts.push(EventAmount {
+                        pool_address: event.pool_address,
+                        log_ordinal: event.log_ordinal,
+                        token0_addr: event.token0,
+                        amount0_value: Some(amount0.neg().into()),
+                        token1_addr: event.token1,
+                        amount1_value: Some(amount1.neg().into()),
+                        ..Default::default()
+                    });
+                }
+                MintEvent(mint) => {
+                    log::debug!("handling mint for pool {}", event.pool_address);
+                    let amount0: BigDecimal = mint.amount_0.unwrap().into();
+                    let amount1: BigDecimal = mint.amount_1.unwrap().into();
+                    event_amounts.push(EventAmount {
+                        pool_address: event.pool_address,
+                        log_ordinal: event.log_ordinal,
+                        token0_addr: event.token0,
+                        amount0_value: Some(amount0.into()),
+                        token1_addr: event.token1,
+                        amount1_value: Some(amount1.into()),
+                        ..Default::default()
+                    });
+                }
+                SwapEvent(swap) => {
+                    log::debug!("handling swap for pool {}", event.pool_address);
+                    let amount0: BigDecimal = swap.amount_0.unwrap().into();
+                    let amount1: BigDecimal = swap.amount_1.unwrap().into();
+                    event_amounts.push(EventAmount {
+                        pool_address: event.pool_address,
+                        log_ordinal: event.log_ordinal,
+                        token0_addr: event.token0,
+                        amount0_value: Some(amount0.into()),
+                        token1_addr: event.token1,
+                        amount1_value: Some(amount1.into()),
+                        ..Default::default()
+                    });
+                }
             }
-        } else if let Some(event) = abi::positionmanager::events::Collect::match_and_decode(log) {
-            if let Some(position) = utils::get_position(
-                &store_pool,
-                &Hex(log.address()).to_string(),
-                &log.receipt.transaction.hash,
-                Collect,
-                log.ordinal(),
-                block
-                    .header
-                    .as_ref()
-                    .unwrap()
-                    .timestamp
-                    .as_ref()
-                    .unwrap()
-                    .seconds as u64,
-                block.number,
-                PositionEvent {
-                    event: PositionEventType::Collect(event),
-                },
-            ) {
-                positions.positions.push(position);
+        }
+    }
+    Ok(uniswap::EventAmounts { event_amounts })
+}
+
+#[substreams::handlers::map]
+pub fn map_transactions(
+    block: Block,
+    pools_store: StoreGetProto<Pool>,
+) -> Result<Transactions, Error> {
+    let mut transactions: Transactions = Transactions {
+        transactions: vec![],
+    };
+
+    for log in block.logs() {
+        let mut add_transaction = false;
+        let pool_key = &format!("pool:{}", Hex(&log.address()).to_string());
+
+        if let Some(_) = abi::pool::events::Burn::match_and_decode(log) {
+            match pools_store.get_last(pool_key) {
+                None => continue,
+
+                Some(_) => add_transaction = true,
             }
-        } else if let Some(event) =
+        } else if let Some(_) = abi::pool::events::Mint::match_and_decode(log) {
+            match pools_store.get_last(pool_key) {
+                None => continue,
+                Some(_) => add_transaction = true,
+            }
+        } else if let Some(_) = abi::pool::events::Swap::match_and_decode(log) {
+            match pools_store.get_last(pool_key) {
+                None => continue,
+                Some(_) => add_transaction = true,
+            }
+        } else if let Some(_) =
+            abi::positionmanager::events::IncreaseLiquidity::match_and_decode(log)
+        {
+            if log.address() == NON_FUNGIBLE_POSITION_MANAGER {
+                add_transaction = true;
+            }
+        } else if let Some(_) = abi::positionmanager::events::Collect::match_and_decode(log) {
+            if log.address() == NON_FUNGIBLE_POSITION_MANAGER {
+                add_transaction = true;
+            }
+        } else if let Some(_) =
             abi::positionmanager::events::DecreaseLiquidity::match_and_decode(log)
         {
-            if let Some(position) = utils::get_position(
-                &store_pool,
-                &Hex(log.address()).to_string(),
-                &log.receipt.transaction.hash,
-                DecreaseLiquidity,
-                log.ordinal(),
-                block
-                    .header
-                    .as_ref()
-                    .unwrap()
-                    .timestamp
-                    .as_ref()
-                    .unwrap()
-                    .seconds as u64,
+            if log.address() == NON_FUNGIBLE_POSITION_MANAGER {
+                add_transaction = true;
+            }
+        } else if let Some(_) = abi::positionmanager::events::Transfer::match_and_decode(log) {
+            if log.address() == NON_FUNGIBLE_POSITION_MANAGER {
+                add_transaction = true;
+            }
+        }
+
+        if add_transaction {
+            transactions.transactions.push(utils::load_transaction(
                 block.number,
-                PositionEvent {
-                    event: PositionEventType::DecreaseLiquidity(event),
-                },
-            ) {
-                positions.positions.push(position);
+                block.timestamp_seconds(),
+                log.ordinal(),
+                log.receipt.transaction,
+            ));
+        }
+    }
+
+    Ok(transactions)
+}
+
+#[substreams::handlers::store]
+pub fn store_totals(
+    clock: Clock,
+    total_value_locked_deltas: store::Deltas<DeltaBigDecimal>,
+    store: StoreAddBigDecimal,
+) {
+    let timestamp_seconds = clock.timestamp.unwrap().seconds;
+    let day_id: i64 = timestamp_seconds / 86400;
+    store.delete_prefix(0, &format!("uniswap_day_data:{}:", day_id - 1));
+
+    // store_total_value_locked sets the pool values, their differences are summed up here
+    for delta in total_value_locked_deltas.deltas {
+        if !delta.key.starts_with("pool:") {
+            continue;
+        }
+        let diff: BigDecimal = delta.new_value.clone().sub(delta.old_value);
+        let chunks: Vec<&str> = delta.key.split(":").collect();
+        match chunks.as_slice() {
+            ["pool", _, "eth"] => store.add(
+                delta.ordinal,
+                keyer::factory_total_value_locked_eth(),
+                &diff,
+            ),
+            ["pool", _, "usd"] => {
+                store.add(
+                    delta.ordinal,
+                    keyer::factory_total_value_locked_usd(),
+                    &diff,
+                );
+                store.add(
+                    delta.ordinal,
+                    keyer::uniswap_total_value_locked_usd(day_id.to_string()),
+                    &delta.new_value,
+                )
+            }
+            ["pool", _, "ethUntracked"] => store.add(
+                delta.ordinal,
+                keyer::factory_total_value_locked_eth_untracked(),
+                &diff,
+            ),
+            ["pool", _, "usdUntracked"] => store.add(
+                delta.ordinal,
+                keyer::factory_total_value_locked_usd_untracked(),
+                &diff,
+            ),
+            ["pool", _, token_address, "trackedUsd"] => store.add(
+                delta.ordinal,
+                keyer::token_usd_total_value_locked(&token_address.to_string()),
+                &diff,
+            ),
+            _ => continue,
+        }
+    }
+}
+
+#[substreams::handlers::store]
+pub fn store_total_tx_counts(clock: Clock, events: Events, output: StoreAddBigInt) {
+    let timestamp_seconds = clock.timestamp.unwrap().seconds;
+    let day_id: i64 = timestamp_seconds / 86400;
+    output.delete_prefix(0, &format!("uniswap_day_data:{}:", day_id - 1));
+
+    for event in events.events {
+        let keys: Vec<String> = vec![
+            keyer::pool_total_tx_count(&event.pool_address),
+            keyer::token_total_tx_count(&event.token0),
+            keyer::token_total_tx_count(&event.token1),
+            keyer::factory_total_tx_count(),
+            keyer::uniswap_data_data_tx_count(day_id.to_string()),
+        ];
+        output.add_many(event.log_ordinal, &keys, &BigInt::from(1 as i32));
+    }
+}
+
+// the policy of store_swaps_volume, store_total_value_locked and the swap, mint and burn
+// entities, configured once
+#[substreams::handlers::map]
+pub fn map_tracking_policy(
+    params: String,
+    _clock: Clock,
+) -> Result<uniswap::TrackingPolicy, Error> {
+    Ok(tracking::parse_tracking_policy_params(&params).into())
+}
+
+#[substreams::handlers::store]
+pub fn store_swaps_volume(
+    tracking_policy: uniswap::TrackingPolicy,
+    clock: Clock,
+    events: Events,
+    store_pool: StoreGetProto<Pool>,
+    store_total_tx_counts: StoreGetBigInt,
+    store_eth_prices: StoreGetBigDecimal,
+    native_total_value_locked_store: StoreGetBigDecimal,
+    output: StoreAddBigDecimal,
+) {
+    let tracking_policy = tracking::TrackingPolicy::from(tracking_policy);
+    let timestamp_seconds = clock.timestamp.unwrap().seconds;
+    let day_id: i64 = timestamp_seconds / 86400;
+    output.delete_prefix(0, &format!("uniswap_day_data:{}:", day_id - 1));
+
+    for event in events.events {
+        let pool: Pool = match store_pool.get_last(keyer::pool_key(&event.pool_address)) {
+            None => continue,
+            Some(pool) => pool,
+        };
+        match store_total_tx_counts.get_last(keyer::pool_total_tx_count(&event.pool_address)) {
+            None => {}
+            Some(_) => match event.r#type.unwrap() {
+                SwapEvent(swap) => {
+                    let eth_price_in_usd: BigDecimal =
+                        match store_eth_prices.get_last(&keyer::bundle_eth_price()) {
+                            None => {
+                                panic!("bundle eth price not found")
+                            }
+                            Some(price) => price,
+                        };
+
+                    let token0_derived_eth_price: BigDecimal =
+                        match store_eth_prices.get_last(keyer::token_eth_price(&event.token0)) {
+                            None => continue,
+                            Some(price) => price,
+                        };
+
+                    let token1_derived_eth_price: BigDecimal =
+                        match store_eth_prices.get_last(keyer::token_eth_price(&event.token1)) {
+                            None => continue,
+                            Some(price) => price,
+                        };
+
+                    let mut amount0_abs: BigDecimal = BigDecimal::from(swap.amount_0.unwrap());
+                    if amount0_abs.lt(&BigDecimal::from(0 as u64)) {
+                        amount0_abs = amount0_abs.mul(BigDecimal::from(-1 as i64))
+                    }
+
+                    let mut amount1_abs: BigDecimal = BigDecimal::from(swap.amount_1.unwrap());
+                    if amount1_abs.lt(&BigDecimal::from(0 as u64)) {
+                        amount1_abs = amount1_abs.mul(BigDecimal::from(-1 as i64))
+                    }
+
+                    log::debug!("trx_id: {}", event.transaction_id);
+                    log::debug!("bundle.ethPriceUSD: {}", eth_price_in_usd);
+                    log::debug!("token0_derived_eth_price: {}", token0_derived_eth_price);
+                    log::debug!("token1_derived_eth_price: {}", token1_derived_eth_price);
+                    log::debug!("amount0_abs: {}", amount0_abs);
+                    log::debug!("amount1_abs: {}", amount1_abs);
+
+                    let pool_eth_locked = tracking::pool_eth_locked(
+                        event.log_ordinal,
+                        &event.pool_address,
+                        &event.token0,
+                        &event.token1,
+                        &native_total_value_locked_store,
+                        &store_eth_prices,
+                    );
+                    let amount_total_usd_tracked: BigDecimal = tracking_policy
+                        .tracked_amount_usd(
+                            &event.token0,
+                            &event.token1,
+                            &amount0_abs,
+                            &amount1_abs,
+                            &token0_derived_eth_price
+                                .clone()
+                                .mul(eth_price_in_usd.clone()),
+                            &token1_derived_eth_price
+                                .clone()
+                                .mul(eth_price_in_usd.clone()),
+                            &pool_eth_locked,
+                        )
+                        .div(BigDecimal::from(2 as i32));
+
+                    let amount_total_eth_tracked =
+                        math::safe_div(&amount_total_usd_tracked, &eth_price_in_usd);
+
+                    let amount_total_usd_untracked: BigDecimal = utils::calculate_amount_usd(
+                        &amount0_abs,
+                        &amount1_abs,
+                        &token0_derived_eth_price,
+                        &token1_derived_eth_price,
+                        &eth_price_in_usd,
+                    )
+                    .div(BigDecimal::from(2 as i32));
+
+                    let fee_tier: BigDecimal = BigDecimal::from(pool.fee_tier.unwrap());
+                    let fee_usd: BigDecimal = amount_total_usd_tracked
+                        .clone()
+                        .mul(fee_tier.clone())
+                        .div(BigDecimal::from(1000000 as u64));
+                    let fee_eth: BigDecimal = amount_total_eth_tracked
+                        .clone()
+                        .mul(fee_tier)
+                        .div(BigDecimal::from(1000000 as u64));
+
+                    output.add_many(
+                        event.log_ordinal,
+                        &vec![
+                            keyer::swap_volume_token_0(&event.pool_address),
+                            keyer::swap_token_volume(&event.token0, "token0".to_string()),
+                        ],
+                        &amount0_abs,
+                    );
+                    output.add_many(
+                        event.log_ordinal,
+                        &vec![
+                            keyer::swap_volume_token_1(&event.pool_address),
+                            keyer::swap_token_volume(&event.token1, "token1".to_string()),
+                        ],
+                        &amount1_abs,
+                    );
+                    output.add_many(
+                        event.log_ordinal,
+                        &vec![
+                            keyer::swap_volume_usd(&event.pool_address),
+                            keyer::swap_token_volume_usd(&event.token0),
+                            keyer::swap_token_volume_usd(&event.token1),
+                            keyer::swap_factory_total_volume_usd(),
+                            keyer::swap_uniswap_day_data_volume_usd(day_id.to_string()),
+                        ],
+                        &amount_total_usd_tracked,
+                    );
+                    output.add_many(
+                        event.log_ordinal,
+                        &vec![
+                            keyer::swap_untracked_volume_usd(&event.pool_address),
+                            keyer::swap_token_volume_untracked_volume_usd(&event.token0),
+                            keyer::swap_token_volume_untracked_volume_usd(&event.token1),
+                            keyer::swap_factory_untracked_volume_usd(),
+                        ],
+                        &amount_total_usd_untracked,
+                    );
+                    output.add_many(
+                        event.log_ordinal,
+                        &vec![
+                            keyer::swap_fee_usd(&event.pool_address),
+                            keyer::swap_token_fee_usd(&event.token0),
+                            keyer::swap_token_fee_usd(&event.token1),
+                            keyer::swap_factory_total_fees_usd(),
+                            keyer::swap_uniswap_day_data_fees_usd(day_id.to_string()),
+                        ],
+                        &fee_usd,
+                    );
+                    output.add(
+                        event.log_ordinal,
+                        keyer::swap_factory_total_volume_eth(),
+                        &amount_total_eth_tracked.clone(),
+                    );
+                    output.add(
+                        event.log_ordinal,
+                        keyer::swap_factory_total_fees_eth(),
+                        &fee_eth,
+                    );
+                    output.add(
+                        event.log_ordinal,
+                        keyer::swap_uniswap_day_data_volume_eth(day_id.to_string()),
+                        &amount_total_eth_tracked,
+                    );
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+#[substreams::handlers::store]
+pub fn store_pool_fee_growth_global_x128(
+    pools: Pools,
+    block: Block,
+    pools_store: StoreGetProto<Pool>,
+    store: StoreSetBigInt,
+) {
+    for pool in pools.pools {
+        log::info!(
+            "pool address: {} trx_id:{}",
+            pool.address,
+            pool.transaction_id
+        );
+        let (big_int_1, big_int_2) = rpc::fee_growth_global_x128_call(&pool.address);
+        log::debug!("big decimal0: {}", big_int_1);
+        log::debug!("big decimal1: {}", big_int_2);
+
+        store.set(
+            pool.log_ordinal,
+            keyer::pool_fee_growth_global_x128(&pool.address, "token0".to_string()),
+            &big_int_1,
+        );
+        store.set(
+            pool.log_ordinal,
+            keyer::pool_fee_growth_global_x128(&pool.address, "token1".to_string()),
+            &big_int_2,
+        );
+    }
+
+    // the globals grow on every swap and flash, follow them through the pool storage
+    for trx in block.transaction_traces {
+        if trx.status != 1 {
+            continue;
+        }
+        for call in trx.calls {
+            if call.state_reverted {
+                continue;
+            }
+            for storage_change in call.storage_changes {
+                let token = match utils::fee_growth_global_x128_token(&storage_change.key) {
+                    None => continue,
+                    Some(token) => token,
+                };
+                let pool_address = Hex(&storage_change.address).to_string();
+                if pools_store
+                    .get_last(keyer::pool_key(&pool_address))
+                    .is_none()
+                {
+                    continue;
+                }
+
+                store.set(
+                    storage_change.ordinal,
+                    keyer::pool_fee_growth_global_x128(&pool_address, token.to_string()),
+                    &BigInt::from_unsigned_bytes_be(&storage_change.new_value),
+                );
+            }
+        }
+    }
+}
+
+#[substreams::handlers::store]
+pub fn store_native_total_value_locked(
+    event_amounts: uniswap::EventAmounts,
+    store: StoreAddBigDecimal,
+) {
+    for event_amount in event_amounts.event_amounts {
+        let amount0: BigDecimal = event_amount.amount0_value.unwrap().into();
+        let amount1: BigDecimal = event_amount.amount1_value.unwrap().into();
+        store.add_many(
+            event_amount.log_ordinal,
+            &vec![
+                keyer::token_native_total_value_locked(&event_amount.token0_addr),
+                keyer::pool_native_total_value_locked_token(
+                    &event_amount.pool_address,
+                    &event_amount.token0_addr,
+                ),
+            ],
+            &amount0,
+        );
+        store.add_many(
+            event_amount.log_ordinal,
+            &vec![
+                keyer::token_native_total_value_locked(&event_amount.token1_addr),
+                keyer::pool_native_total_value_locked_token(
+                    &event_amount.pool_address,
+                    &event_amount.token1_addr,
+                ),
+            ],
+            &amount1,
+        );
+    }
+}
+
+#[substreams::handlers::map]
+pub fn map_bundle_prices(
+    params: String,
+    pool_sqrt_prices: PoolSqrtPrices,
+    pools_store: StoreGetProto<Pool>,
+    prices_store: StoreGetBigDecimal,
+    pool_sqrt_prices_store: StoreGetProto<PoolSqrtPrice>,
+    pool_liquidities_store: StoreGetBigInt,
+) -> Result<BundlePrices, Error> {
+    let (method, reference_pools) = price::parse_bundle_price_params(&params);
+    let mut bundle_prices = vec![];
+
+    for pool_sqrt_price in pool_sqrt_prices.pool_sqrt_prices {
+        if !reference_pools.contains(&pool_sqrt_price.pool_address) {
+            continue;
+        }
+
+        match price::get_eth_price_in_usd(
+            &method,
+            &reference_pools,
+            pool_sqrt_price.ordinal,
+            &pools_store,
+            &prices_store,
+            &pool_sqrt_prices_store,
+            &pool_liquidities_store,
+        ) {
+            None => log::debug!("no reference pool priced ETH"),
+            Some((eth_price_usd, source, pools)) => bundle_prices.push(BundlePrice {
+                eth_price_usd: Some(eth_price_usd.into()),
+                source,
+                pools,
+                ordinal: pool_sqrt_price.ordinal,
+            }),
+        }
+    }
+
+    Ok(BundlePrices { bundle_prices })
+}
+
+#[substreams::handlers::store]
+pub fn store_bundle_prices(bundle_prices: BundlePrices, store: StoreSetProto<BundlePrice>) {
+    for bundle_price in bundle_prices.bundle_prices {
+        store.set(
+            bundle_price.ordinal,
+            keyer::bundle_eth_price(),
+            &bundle_price,
+        );
+    }
+}
+
+#[substreams::handlers::map]
+pub fn map_derived_eth_prices(
+    pool_sqrt_prices: PoolSqrtPrices,
+    pools_store: StoreGetProto<Pool>,
+    prices_store: StoreGetBigDecimal,
+    token_pools_store: StoreGetArray<String>,
+    total_native_value_locked_store: StoreGetBigDecimal,
+    pool_liquidities_store: StoreGetBigInt,
+    bundle_prices_store: StoreGetProto<BundlePrice>,
+    transfer_behaviors_store: StoreGetProto<TokenTransferBehavior>,
+) -> Result<DerivedEthPrices, Error> {
+    let mut derived_eth_prices = vec![];
+    for pool_sqrt_price in pool_sqrt_prices.pool_sqrt_prices {
+        log::debug!(
+            "handling pool price update - addr: {} price: {}",
+            pool_sqrt_price.pool_address,
+            pool_sqrt_price.sqrt_price.unwrap().value
+        );
+        let pool = pools_store.must_get_last(&keyer::pool_key(&pool_sqrt_price.pool_address));
+        let token_0 = pool.token0.as_ref().unwrap();
+        let token_1 = pool.token1.as_ref().unwrap();
+
+        token_0.log();
+        token_1.log();
+
+        let bundle_eth_price_usd: BigDecimal =
+            match bundle_prices_store.get_at(pool_sqrt_price.ordinal, keyer::bundle_eth_price()) {
+                None => BigDecimal::zero(),
+                Some(bundle_price) => bundle_price.eth_price_usd.unwrap().into(),
+            };
+        log::info!("bundle_eth_price_usd: {}", bundle_eth_price_usd);
+
+        for token in [token_0, token_1] {
+            let price_path = price::find_eth_per_token(
+                pool_sqrt_price.ordinal,
+                &token.address,
+                &pools_store,
+                &pool_liquidities_store,
+                &token_pools_store,
+                &total_native_value_locked_store,
+                &prices_store,
+                &transfer_behaviors_store,
+                &bundle_eth_price_usd,
+            );
+            log::info!(
+                "token {} derived eth price: {}",
+                token.address,
+                price_path.eth_price
+            );
+
+            derived_eth_prices.push(DerivedEthPrice {
+                token_address: token.address.clone(),
+                confidence: Some(price_path.confidence().into()),
+                eth_price: Some(price_path.eth_price.into()),
+                pools: price_path.pools,
+                path: price_path.tokens,
+                ordinal: pool_sqrt_price.ordinal,
+            });
+        }
+    }
+
+    Ok(DerivedEthPrices { derived_eth_prices })
+}
+
+#[substreams::handlers::map]
+pub fn map_price_anomalies(
+    params: String,
+    clock: Clock,
+    derived_eth_prices: DerivedEthPrices,
+    pools_store: StoreGetProto<Pool>,
+    prices_store: StoreGetBigDecimal,
+    pool_liquidities_store: StoreGetBigInt,
+    token_pools_store: StoreGetArray<String>,
+    pool_twaps_store: StoreGetBigDecimal,
+) -> Result<PriceAnomalies, Error> {
+    let guard = price::parse_price_guard_params(&params);
+    let timestamp = clock.timestamp.unwrap().seconds as u64;
+    let mut price_anomalies = vec![];
+
+    for derived_eth_price in derived_eth_prices.derived_eth_prices {
+        if let Some(price_anomaly) = price::check_derived_eth_price(
+            &derived_eth_price,
+            &guard,
+            &pools_store,
+            &prices_store,
+            &pool_liquidities_store,
+            &token_pools_store,
+            &pool_twaps_store,
+        ) {
+            log::info!(
+                "rejecting derived eth price {} of token {}",
+                derived_eth_price.eth_price.unwrap().value,
+                derived_eth_price.token_address
+            );
+            price_anomalies.push(PriceAnomaly {
+                id: format!(
+                    "{}-{}-{}",
+                    derived_eth_price.token_address, clock.number, derived_eth_price.ordinal
+                ),
+                block_number: clock.number,
+                timestamp,
+                ..price_anomaly
+            });
+        }
+    }
+
+    Ok(PriceAnomalies { price_anomalies })
+}
+
+// derived prices rejected by map_price_anomalies are dropped, the token keeping its last price
+#[substreams::handlers::store]
+pub fn store_eth_prices(
+    bundle_prices_deltas: store::Deltas<DeltaProto<BundlePrice>>,
+    derived_eth_prices: DerivedEthPrices,
+    price_anomalies: PriceAnomalies,
+    store: StoreSetBigDecimal,
+) {
+    for delta in bundle_prices_deltas.deltas {
+        store.set(
+            delta.ordinal,
+            keyer::bundle_eth_price(),
+            &BigDecimal::from(delta.new_value.eth_price_usd.unwrap()),
+        );
+    }
+
+    for derived_eth_price in derived_eth_prices.derived_eth_prices {
+        if price_anomalies.rejects(&derived_eth_price) {
+            continue;
+        }
+        store.set(
+            derived_eth_price.ordinal,
+            keyer::token_eth_price(&derived_eth_price.token_address),
+            &BigDecimal::from(derived_eth_price.eth_price.unwrap()),
+        );
+    }
+}
+
+// a token's ETH price key is created once, so the list holds every token at most once
+#[substreams::handlers::store]
+pub fn store_priced_tokens(
+    eth_prices_deltas: store::Deltas<DeltaBigDecimal>,
+    store: StoreAppend<String>,
+) {
+    for delta in eth_prices_deltas.deltas {
+        if !delta.key.starts_with("token:") || delta.operation != store::Operation::Create {
+            continue;
+        }
+        let token_address = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        store.append(delta.ordinal, keyer::priced_tokens(), token_address);
+    }
+}
+
+// a token's USD price moves with its derived ETH price and with the bundle: when the bundle
+// changes in a block, every token with a derived ETH price is repriced once, at the last
+// bundle change
+#[substreams::handlers::store]
+pub fn store_token_usd_prices(
+    eth_prices_deltas: store::Deltas<DeltaBigDecimal>,
+    eth_prices_store: StoreGetBigDecimal,
+    priced_tokens_store: StoreGetArray<String>,
+    store: StoreSetBigDecimal,
+) {
+    let mut last_bundle_ordinal: Option<u64> = None;
+    let mut last_ordinals: HashMap<String, u64> = HashMap::new();
+    for delta in eth_prices_deltas.deltas {
+        if delta.key == keyer::bundle_eth_price() {
+            last_bundle_ordinal = Some(delta.ordinal);
+            continue;
+        }
+        if !delta.key.starts_with("token:") {
+            continue;
+        }
+        let token_address = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        let eth_price_usd = eth_prices_store
+            .get_at(delta.ordinal, keyer::bundle_eth_price())
+            .unwrap_or(BigDecimal::zero());
+
+        store.set(
+            delta.ordinal,
+            keyer::token_usd_price(&token_address),
+            &delta.new_value.mul(eth_price_usd),
+        );
+        last_ordinals.insert(token_address, delta.ordinal);
+    }
+
+    let bundle_ordinal = match last_bundle_ordinal {
+        None => return,
+        Some(bundle_ordinal) => bundle_ordinal,
+    };
+    let eth_price_usd = eth_prices_store
+        .get_at(bundle_ordinal, keyer::bundle_eth_price())
+        .unwrap();
+
+    for token_address in priced_tokens_store
+        .get_last(keyer::priced_tokens())
+        .unwrap_or_default()
+    {
+        // already priced with this bundle by its own derived ETH price change
+        if let Some(ordinal) = last_ordinals.get(&token_address) {
+            if *ordinal >= bundle_ordinal {
+                continue;
+            }
+        }
+        let derived_eth =
+            match eth_prices_store.get_at(bundle_ordinal, keyer::token_eth_price(&token_address)) {
+                Some(derived_eth) if !derived_eth.is_zero() => derived_eth,
+                _ => continue,
+            };
+
+        store.set(
+            bundle_ordinal,
+            keyer::token_usd_price(&token_address),
+            &derived_eth.mul(eth_price_usd.clone()),
+        );
+    }
+}
+
+// the USD price changes of store_token_usd_prices, a change at the ordinal of the token's own
+// derived ETH price change is caused by it, any other by the bundle
+#[substreams::handlers::map]
+pub fn map_token_price_changes(
+    clock: Clock,
+    token_usd_prices_deltas: store::Deltas<DeltaBigDecimal>,
+    eth_prices_deltas: store::Deltas<DeltaBigDecimal>,
+    eth_prices_store: StoreGetBigDecimal,
+) -> Result<TokenPriceChanges, Error> {
+    let block_number = clock.number;
+    let timestamp = clock.timestamp.unwrap().seconds as u64;
+
+    let derived_eth_changes: Vec<(String, u64)> = eth_prices_deltas
+        .deltas
+        .into_iter()
+        .filter(|delta| delta.key.starts_with("token:"))
+        .map(|delta| {
+            let token_address = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+            (token_address, delta.ordinal)
+        })
+        .collect();
+
+    let mut token_price_changes: Vec<TokenPriceChange> = vec![];
+    for delta in token_usd_prices_deltas.deltas {
+        let token_address = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        let cause = match derived_eth_changes.contains(&(token_address.clone(), delta.ordinal)) {
+            true => TokenPriceChangeCause::DerivedEth,
+            false => TokenPriceChangeCause::Bundle,
+        };
+        let derived_eth = eth_prices_store
+            .get_at(delta.ordinal, keyer::token_eth_price(&token_address))
+            .unwrap_or(BigDecimal::zero());
+        let eth_price_usd = eth_prices_store
+            .get_at(delta.ordinal, keyer::bundle_eth_price())
+            .unwrap_or(BigDecimal::zero());
+
+        token_price_changes.push(TokenPriceChange {
+            token_address,
+            cause: cause as i32,
+            previous_price_usd: Some(delta.old_value.into()),
+            price_usd: Some(delta.new_value.into()),
+            derived_eth: Some(derived_eth.into()),
+            eth_price_usd: Some(eth_price_usd.into()),
+            block_number,
+            timestamp,
+            ordinal: delta.ordinal,
+        });
+    }
+
+    Ok(TokenPriceChanges {
+        token_price_changes,
+    })
+}
+
+#[substreams::handlers::store]
+pub fn store_total_value_locked_by_tokens(events: Events, store: StoreAddBigDecimal) {
+    for event in events.events {
+        log::debug!("trx_id: {}", event.transaction_id);
+        let mut amount0: BigDecimal;
+        let mut amount1: BigDecimal;
+
+        match event.r#type.unwrap() {
+            BurnEvent(burn) => {
+                amount0 = burn.amount_0.unwrap().into();
+                amount0 = amount0.neg();
+                amount1 = burn.amount_1.unwrap().into();
+                amount1 = amount1.neg();
+            }
+            MintEvent(mint) => {
+                amount0 = mint.amount_0.unwrap().into();
+                amount1 = mint.amount_1.unwrap().into();
+            }
+            SwapEvent(swap) => {
+                amount0 = swap.amount_0.unwrap().into();
+                amount1 = swap.amount_1.unwrap().into();
+            }
+        }
+
+        store.add(
+            event.log_ordinal,
+            keyer::total_value_locked_by_tokens(
+                &event.pool_address,
+                &event.token0,
+                "token0".to_string(),
+            ),
+            &amount0,
+        );
+        store.add(
+            event.log_ordinal,
+            keyer::total_value_locked_by_tokens(
+                &event.pool_address,
+                &event.token1,
+                "token1".to_string(),
+            ),
+            &amount1,
+        );
+    }
+}
+
+#[substreams::handlers::store]
+pub fn store_pool_balances(
+    pool_token_transfers: PoolTokenTransfers,
+    pools_store: StoreGetProto<Pool>,
+    store: StoreAddBigDecimal,
+) {
+    for transfer in pool_token_transfers.pool_token_transfers {
+        let pool = match pools_store.get_last(keyer::pool_key(&transfer.pool_address)) {
+            None => continue,
+            Some(pool) => pool,
+        };
+
+        let (token, decimals) = if pool.token0_ref().address == transfer.token_address {
+            ("token0", pool.token0_ref().decimals)
+        } else {
+            ("token1", pool.token1_ref().decimals)
+        };
+        let amount = BigInt::from(transfer.amount.unwrap()).to_decimal(decimals);
+
+        store.add(
+            transfer.log_ordinal,
+            keyer::pool_balance(
+                &transfer.pool_address,
+                &transfer.token_address,
+                token.to_string(),
+            ),
+            &amount,
+        );
+    }
+}
+
+#[substreams::handlers::store]
+pub fn store_pool_balance_reconciliations(
+    pool_balances_deltas: store::Deltas<DeltaBigDecimal>,
+    total_value_locked_by_tokens_deltas: store::Deltas<DeltaBigDecimal>,
+    pool_balances_store: StoreGetBigDecimal,
+    total_value_locked_by_tokens_store: StoreGetBigDecimal,
+    store: StoreSetBigDecimal,
+) {
+    // both stores are keyed by pool:{pool}:{token}:{token0|token1}
+    let mut ordered_keys: Vec<String> = vec![];
+    let mut ordinals: HashMap<String, u64> = HashMap::new();
+    for delta in pool_balances_deltas
+        .deltas
+        .into_iter()
+        .chain(total_value_locked_by_tokens_deltas.deltas.into_iter())
+    {
+        match ordinals.get_mut(&delta.key) {
+            None => {
+                ordered_keys.push(delta.key.clone());
+                ordinals.insert(delta.key, delta.ordinal);
+            }
+            Some(ordinal) => {
+                if delta.ordinal > *ordinal {
+                    *ordinal = delta.ordinal;
+                }
+            }
+        }
+    }
+
+    for key in ordered_keys {
+        let parts: Vec<&str> = key.split(":").collect();
+        let pool_address = parts[1].to_string();
+        let token_address = parts[2].to_string();
+        let token = parts[3].to_string();
+
+        let balance = pool_balances_store
+            .get_last(keyer::pool_balance(
+                &pool_address,
+                &token_address,
+                token.clone(),
+            ))
+            .unwrap_or(BigDecimal::zero());
+        let total_value_locked = total_value_locked_by_tokens_store
+            .get_last(keyer::total_value_locked_by_tokens(
+                &pool_address,
+                &token_address,
+                token.clone(),
+            ))
+            .unwrap_or(BigDecimal::zero());
+
+        store.set(
+            ordinals[&key],
+            keyer::pool_balance_reconciliation(&pool_address, &token_address, token),
+            &balance.sub(total_value_locked),
+        );
+    }
+}
+
+// pools are valued from both of their token balances at each change, the tracked part
+// following the tracking policy; tokens and the factory are summed up in store_totals.
+// A fee-on-transfer token's balance is taken from its Transfer logs, the pool events
+// overstating it by the fees
+#[substreams::handlers::store]
+pub fn store_total_value_locked(
+    tracking_policy: uniswap::TrackingPolicy,
+    native_total_value_locked_deltas: store::Deltas<DeltaBigDecimal>,
+    native_total_value_locked_store: StoreGetBigDecimal,
+    pools_store: StoreGetProto<Pool>,
+    eth_prices_store: StoreGetBigDecimal,
+    pool_balances_store: StoreGetBigDecimal,
+    transfer_behaviors_store: StoreGetProto<TokenTransferBehavior>,
+    store: StoreSetBigDecimal,
+) {
+    let tracking_policy = tracking::TrackingPolicy::from(tracking_policy);
+
+    // both token balances of a pool usually change at the same ordinal
+    let mut valued_pools: Vec<(String, u64)> = vec![];
+    for delta in native_total_value_locked_deltas.deltas {
+        let eth_price_usd = eth_prices_store
+            .get_at(delta.ordinal, keyer::bundle_eth_price())
+            .unwrap_or(BigDecimal::zero());
+        let derived_eth_price = |token_address: &String| -> BigDecimal {
+            eth_prices_store
+                .get_at(delta.ordinal, keyer::token_eth_price(token_address))
+                .unwrap_or(BigDecimal::zero())
+        };
+
+        if let Some(token_address) = keyer::native_token_from_key(&delta.key) {
+            let total_value_locked_usd = delta
+                .new_value
+                .clone()
+                .mul(derived_eth_price(&token_address))
+                .mul(eth_price_usd.clone());
+            store.set(
+                delta.ordinal,
+                keyer::token_usd_untracked_total_value_locked(&token_address),
+                &total_value_locked_usd,
+            );
+            continue;
+        }
+
+        let pool_address = match keyer::native_pool_from_key(&delta.key) {
+            None => continue,
+            Some((pool_address, _)) => pool_address,
+        };
+        if valued_pools.contains(&(pool_address.clone(), delta.ordinal)) {
+            continue;
+        }
+        valued_pools.push((pool_address.clone(), delta.ordinal));
+
+        let pool = pools_store.must_get_last(keyer::pool_key(&pool_address));
+        let token0_address = &pool.token0.as_ref().unwrap().address;
+        let token1_address = &pool.token1.as_ref().unwrap().address;
+        let balance = |token_address: &String, token: &str| -> BigDecimal {
+            if utils::is_fee_on_transfer(delta.ordinal, token_address, &transfer_behaviors_store) {
+                return pool_balances_store
+                    .get_at(
+                        delta.ordinal,
+                        keyer::pool_balance(&pool_address, token_address, token.to_string()),
+                    )
+                    .unwrap_or(BigDecimal::zero());
+            }
+            native_total_value_locked_store
+                .get_at(
+                    delta.ordinal,
+                    keyer::pool_native_total_value_locked_token(&pool_address, token_address),
+                )
+                .unwrap_or(BigDecimal::zero())
+        };
+        let balance0 = balance(token0_address, "token0");
+        let balance1 = balance(token1_address, "token1");
+        let token0_derived_eth_price = derived_eth_price(token0_address);
+        let token1_derived_eth_price = derived_eth_price(token1_address);
+
+        let eth_locked0 = balance0.clone().mul(token0_derived_eth_price.clone());
+        let eth_locked1 = balance1.clone().mul(token1_derived_eth_price.clone());
+        let pool_eth_locked = match eth_locked0.lt(&eth_locked1) {
+            true => eth_locked0.clone(),
+            false => eth_locked1.clone(),
+        };
+        let total_value_locked_eth_untracked = eth_locked0.add(eth_locked1);
+        let total_value_locked_usd_untracked = total_value_locked_eth_untracked
+            .clone()
+            .mul(eth_price_usd.clone());
+
+        let (tracked0_usd, tracked1_usd) = tracking_policy.tracked_amounts_usd(
+            token0_address,
+            token1_address,
+            &balance0,
+            &balance1,
+            &token0_derived_eth_price.mul(eth_price_usd.clone()),
+            &token1_derived_eth_price.mul(eth_price_usd.clone()),
+            &pool_eth_locked,
+        );
+        let total_value_locked_usd = tracked0_usd.clone().add(tracked1_usd.clone());
+        let total_value_locked_eth = math::safe_div(&total_value_locked_usd, &eth_price_usd);
+
+        store.set(
+            delta.ordinal,
+            keyer::pool_eth_total_value_locked(&pool_address),
+            &total_value_locked_eth,
+        );
+        store.set(
+            delta.ordinal,
+            keyer::pool_usd_total_value_locked(&pool_address),
+            &total_value_locked_usd,
+        );
+        store.set(
+            delta.ordinal,
+            keyer::pool_eth_untracked_total_value_locked(&pool_address),
+            &total_value_locked_eth_untracked,
+        );
+        store.set(
+            delta.ordinal,
+            keyer::pool_usd_untracked_total_value_locked(&pool_address),
+            &total_value_locked_usd_untracked,
+        );
+        store.set(
+            delta.ordinal,
+            keyer::pool_token_usd_total_value_locked(&pool_address, token0_address),
+            &tracked0_usd,
+        );
+        store.set(
+            delta.ordinal,
+            keyer::pool_token_usd_total_value_locked(&pool_address, token1_address),
+            &tracked1_usd,
+        );
+    }
+}
+
+#[substreams::handlers::map]
+pub fn map_ticks(events: Events) -> Result<Ticks, Error> {
+    let mut out: Ticks = Ticks { ticks: vec![] };
+    for event in events.events {
+        match event.r#type.unwrap() {
+            BurnEvent(burn) => {
+                log::debug!("burn event transaction_id: {}", event.transaction_id);
+                let lower_tick_id: String = format!(
+                    "{}#{}",
+                    &event.pool_address,
+                    burn.tick_lower.as_ref().unwrap().value
+                );
+                let lower_tick_idx: BigInt = burn.tick_lower.unwrap().into();
+                let lower_tick_price0 = math::big_decimal_exponated(
+                    BigDecimal::try_from(1.0001).unwrap().with_prec(100),
+                    lower_tick_idx.clone(),
+                );
+                let lower_tick_price1 =
+                    math::safe_div(&BigDecimal::from(1 as i32), &lower_tick_price0);
+
+                //todo: implement the fee_growth_outside_x128_call mimicked from the smart contract
+                // to reduce the number of rpc calls to do
+                let lower_tick_result =
+                    rpc::fee_growth_outside_x128_call(&event.pool_address, &lower_tick_idx);
+
+                let tick_lower: Tick = Tick {
+                    id: lower_tick_id,
+                    pool_address: event.pool_address.to_string(),
+                    idx: Some(lower_tick_idx.into()),
+                    price0: Some(lower_tick_price0.into()),
+                    price1: Some(lower_tick_price1.into()),
+                    created_at_timestamp: event.timestamp,
+                    created_at_block_number: event.created_at_block_number,
+                    fee_growth_outside_0x_128: Some(lower_tick_result.0.into()),
+                    fee_growth_outside_1x_128: Some(lower_tick_result.1.into()),
+                    log_ordinal: event.log_ordinal,
+                    amount: burn.amount.clone(),
+                    r#type: Lower as i32,
+                    origin: Burn as i32,
+                };
+
+                let upper_tick_id: String = format!(
+                    "{}#{}",
+                    &event.pool_address,
+                    burn.tick_upper.as_ref().unwrap().value
+                );
+                let upper_tick_idx: BigInt = burn.tick_upper.unwrap().into();
+                let upper_tick_price0 = math::big_decimal_exponated(
+                    BigDecimal::try_from(1.0001).unwrap().with_prec(100),
+                    upper_tick_idx.clone(),
+                );
+                let upper_upper_price1 =
+                    math::safe_div(&BigDecimal::from(1 as i32), &upper_tick_price0);
+
+                let upper_tick_result =
+                    rpc::fee_growth_outside_x128_call(&event.pool_address, &upper_tick_idx);
+
+                let tick_upper: Tick = Tick {
+                    id: upper_tick_id,
+                    pool_address: event.pool_address.to_string(),
+                    idx: Some(upper_tick_idx.into()),
+                    price0: Some(upper_tick_price0.into()),
+                    price1: Some(upper_upper_price1.into()),
+                    created_at_timestamp: event.timestamp,
+                    created_at_block_number: event.created_at_block_number,
+                    fee_growth_outside_0x_128: Some(upper_tick_result.0.into()),
+                    fee_growth_outside_1x_128: Some(upper_tick_result.1.into()),
+                    log_ordinal: event.log_ordinal,
+                    amount: burn.amount,
+                    r#type: Upper as i32,
+                    origin: Burn as i32,
+                };
+
+                out.ticks.push(tick_lower);
+                out.ticks.push(tick_upper);
+            }
+            MintEvent(mint) => {
+                log::debug!("mint event transaction_id: {}", event.transaction_id);
+                let lower_tick_id: String = format!(
+                    "{}#{}",
+                    &event.pool_address,
+                    mint.tick_lower.as_ref().unwrap().value
+                );
+                let lower_tick_idx: BigInt = mint.tick_lower.unwrap().into();
+                let lower_tick_price0 = math::big_decimal_exponated(
+                    BigDecimal::try_from(1.0001).unwrap().with_prec(100),
+                    lower_tick_idx.clone(),
+                );
+                let lower_tick_price1 =
+                    math::safe_div(&BigDecimal::from(1 as i32), &lower_tick_price0);
+
+                let lower_tick_result =
+                    rpc::fee_growth_outside_x128_call(&event.pool_address, &lower_tick_idx);
+
+                // in the subgraph, there is a `load` which is done to see if the tick
+                // exists and if it doesn't exist, createTick()
+                let tick_lower: Tick = Tick {
+                    id: lower_tick_id,
+                    pool_address: event.pool_address.to_string(),
+                    idx: Some(lower_tick_idx.into()),
+                    price0: Some(lower_tick_price0.into()),
+                    price1: Some(lower_tick_price1.into()),
+                    created_at_timestamp: event.timestamp,
+                    created_at_block_number: event.created_at_block_number,
+                    fee_growth_outside_0x_128: Some(lower_tick_result.0.into()),
+                    fee_growth_outside_1x_128: Some(lower_tick_result.1.into()),
+                    log_ordinal: event.log_ordinal,
+                    amount: mint.amount.clone(),
+                    r#type: Lower as i32,
+                    origin: Mint as i32,
+                };
+
+                let upper_tick_id: String = format!(
+                    "{}#{}",
+                    &event.pool_address,
+                    mint.tick_upper.as_ref().unwrap().value
+                );
+                let upper_tick_idx: BigInt = mint.tick_upper.unwrap().into();
+                let upper_tick_price0 = math::big_decimal_exponated(
+                    BigDecimal::try_from(1.0001).unwrap().with_prec(100),
+                    upper_tick_idx.clone(),
+                );
+                let upper_tick_price1 =
+                    math::safe_div(&BigDecimal::from(1 as i32), &upper_tick_price0);
+
+                let upper_tick_result =
+                    rpc::fee_growth_outside_x128_call(&event.pool_address, &upper_tick_idx);
+
+                let tick_upper: Tick = Tick {
+                    id: upper_tick_id,
+                    pool_address: event.pool_address.to_string(),
+                    idx: Some(upper_tick_idx.into()),
+                    price0: Some(upper_tick_price0.into()),
+                    price1: Some(upper_tick_price1.into()),
+                    created_at_timestamp: event.timestamp,
+                    created_at_block_number: event.created_at_block_number,
+                    fee_growth_outside_0x_128: Some(upper_tick_result.0.into()),
+                    fee_growth_outside_1x_128: Some(upper_tick_result.1.into()),
+                    log_ordinal: event.log_ordinal,
+                    amount: mint.amount,
+                    r#type: Upper as i32,
+                    origin: Mint as i32,
+                };
+
+                out.ticks.push(tick_lower);
+                out.ticks.push(tick_upper);
+            }
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+#[substreams::handlers::store]
+pub fn store_ticks(ticks: Ticks, output: StoreSetProto<Tick>) {
+    for tick in ticks.ticks {
+        output.set(tick.log_ordinal, keyer::ticks(&tick.id), &tick);
+    }
+}
+
+#[substreams::handlers::store]
+pub fn store_ticks_liquidities(ticks: Ticks, output: StoreAddBigInt) {
+    for tick in ticks.ticks {
+        log::debug!("tick id: {}", tick.id);
+        if tick.origin == Mint as i32 {
+            if tick.r#type == Lower as i32 {
+                output.add(
+                    tick.log_ordinal,
+                    keyer::tick_liquidities_net(&tick.id),
+                    &BigInt::from(tick.amount.as_ref().unwrap()),
+                );
+            } else {
+                // upper
+                output.add(
+                    tick.log_ordinal,
+                    keyer::tick_liquidities_net(&tick.id),
+                    &BigInt::from(tick.amount.as_ref().unwrap()).neg(),
+                );
+            }
+
+            output.add(
+                tick.log_ordinal,
+                keyer::tick_liquidities_gross(&tick.id),
+                &BigInt::from(tick.amount.unwrap()),
+            );
+        } else if tick.origin == Burn as i32 {
+            if tick.r#type == Lower as i32 {
+                output.add(
+                    tick.log_ordinal,
+                    keyer::tick_liquidities_net(&tick.id),
+                    &BigInt::from(tick.amount.as_ref().unwrap()).neg(),
+                );
+            } else {
+                // upper
+                output.add(
+                    tick.log_ordinal,
+                    keyer::tick_liquidities_net(&tick.id),
+                    &BigInt::from(tick.amount.as_ref().unwrap()),
+                );
+            }
+
+            output.add(
+                tick.log_ordinal,
+                keyer::tick_liquidities_gross(&tick.id),
+                &BigInt::from(tick.amount.unwrap()).neg(),
+            );
+        }
+    }
+}
+
+#[substreams::handlers::store]
+pub fn store_position_static(
+    block: Block,
+    pools_store: StoreGetProto<Pool>,
+    store: StoreSetIfNotExistsProto<PositionStatic>,
+) {
+    let timestamp = block
+        .header
+        .as_ref()
+        .unwrap()
+        .timestamp
+        .as_ref()
+        .unwrap()
+        .seconds as u64;
+
+    // the NonfungiblePositionManager mints on the pool right before emitting IncreaseLiquidity
+    let mut last_pool_mint: Option<(Vec<u8>, String, abi::pool::events::Mint)> = None;
+    for log in block.logs() {
+        if let Some(event) = abi::pool::events::Mint::match_and_decode(log) {
+            if event.owner == NON_FUNGIBLE_POSITION_MANAGER {
+                last_pool_mint = Some((
+                    log.receipt.transaction.hash.clone(),
+                    Hex(log.address()).to_string(),
+                    event,
+                ));
+            }
+            continue;
+        }
+
+        if log.address() != NON_FUNGIBLE_POSITION_MANAGER {
+            continue;
+        }
+
+        if let Some(event) = abi::positionmanager::events::IncreaseLiquidity::match_and_decode(log)
+        {
+            let (transaction_hash, pool_address, pool_mint) = match last_pool_mint.take() {
+                None => continue,
+                Some(last_pool_mint) => last_pool_mint,
+            };
+            if transaction_hash != log.receipt.transaction.hash
+                || pool_mint.amount != event.liquidity
+            {
+                log::info!(
+                    "no matching pool mint for position {} trx_id: {}",
+                    event.token_id,
+                    Hex(&log.receipt.transaction.hash).to_string()
+                );
+                continue;
+            }
+
+            let pool = match pools_store.get_last(keyer::pool_key(&pool_address)) {
+                None => continue,
+                Some(pool) => pool,
+            };
+
+            let position_id = event.token_id.to_string();
+            store.set_if_not_exists(
+                log.ordinal(),
+                keyer::position_static(&position_id),
+                &PositionStatic {
+                    id: position_id.clone(),
+                    pool: pool.address.clone(),
+                    token0: pool.token0_ref().address.clone(),
+                    token1: pool.token1_ref().address.clone(),
+                    fee_tier: pool.fee_tier.clone(),
+                    tick_lower: Some(pool_mint.tick_lower.into()),
+                    tick_upper: Some(pool_mint.tick_upper.into()),
+                    transaction: Hex(&log.receipt.transaction.hash).to_string(),
+                    timestamp,
+                    block_number: block.number,
+                    log_ordinal: log.ordinal(),
+                },
+            );
+        }
+    }
+}
+
+#[substreams::handlers::map]
+pub fn map_all_positions(
+    block: Block,
+    position_static_store: StoreGetProto<PositionStatic>,
+    store_pool: StoreGetProto<Pool>,
+    tick_fee_growth_outside_store: StoreGetBigInt,
+    pool_sqrt_price_store: StoreGetProto<PoolSqrtPrice>,
+    pool_fee_growth_global_x128_store: StoreGetBigInt,
+) -> Result<Positions, Error> {
+    let mut positions: Positions = Positions { positions: vec![] };
+
+    for log in block.logs() {
+        if log.address() != NON_FUNGIBLE_POSITION_MANAGER {
+            continue;
+        }
+
+        if let Some(event) = abi::positionmanager::events::IncreaseLiquidity::match_and_decode(log)
+        {
+            if let Some(position) = utils::get_position(
+                &position_static_store,
+                &store_pool,
+                &tick_fee_growth_outside_store,
+                &pool_sqrt_price_store,
+                &pool_fee_growth_global_x128_store,
+                &log.receipt.transaction.hash,
+                IncreaseLiquidity,
+                log.ordinal(),
+                block
+                    .header
+                    .as_ref()
+                    .unwrap()
+                    .timestamp
+                    .as_ref()
+                    .unwrap()
+                    .seconds as u64,
+                block.number,
+                PositionEvent {
+                    event: PositionEventType::IncreaseLiquidity(event),
+                },
+            ) {
+                positions.positions.push(position);
+            }
+        } else if let Some(event) = abi::positionmanager::events::Collect::match_and_decode(log) {
+            if let Some(position) = utils::get_position(
+                &position_static_store,
+                &store_pool,
+                &tick_fee_growth_outside_store,
+                &pool_sqrt_price_store,
+                &pool_fee_growth_global_x128_store,
+                &log.receipt.transaction.hash,
+                Collect,
+                log.ordinal(),
+                block
+                    .header
+                    .as_ref()
+                    .unwrap()
+                    .timestamp
+                    .as_ref()
+                    .unwrap()
+                    .seconds as u64,
+                block.number,
+                PositionEvent {
+                    event: PositionEventType::Collect(event),
+                },
+            ) {
+                positions.positions.push(position);
+            }
+        } else if let Some(event) =
+            abi::positionmanager::events::DecreaseLiquidity::match_and_decode(log)
+        {
+            if let Some(position) = utils::get_position(
+                &position_static_store,
+                &store_pool,
+                &tick_fee_growth_outside_store,
+                &pool_sqrt_price_store,
+                &pool_fee_growth_global_x128_store,
+                &log.receipt.transaction.hash,
+                DecreaseLiquidity,
+                log.ordinal(),
+                block
+                    .header
+                    .as_ref()
+                    .unwrap()
+                    .timestamp
+                    .as_ref()
+                    .unwrap()
+                    .seconds as u64,
+                block.number,
+                PositionEvent {
+                    event: PositionEventType::DecreaseLiquidity(event),
+                },
+            ) {
+                positions.positions.push(position);
+            }
+        } else if let Some(event) = abi::positionmanager::events::Transfer::match_and_decode(log) {
+            if let Some(position) = utils::get_position(
+                &position_static_store,
+                &store_pool,
+                &tick_fee_growth_outside_store,
+                &pool_sqrt_price_store,
+                &pool_fee_growth_global_x128_store,
+                &log.receipt.transaction.hash,
+                Transfer,
+                log.ordinal(),
+                block
+                    .header
+                    .as_ref()
+                    .unwrap()
+                    .timestamp
+                    .as_ref()
+                    .unwrap()
+                    .seconds as u64,
+                block.number,
+                PositionEvent {
+                    event: PositionEventType::Transfer(event.clone()),
+                },
+            ) {
+                positions.positions.push(position);
+            }
+        }
+    }
+
+    Ok(positions)
+}
+
+#[substreams::handlers::store]
+pub fn store_all_positions(positions: Positions, store: StoreSetProto<Position>) {
+    for position in positions.positions {
+        store.set(
+            position.log_ordinal,
+            keyer::all_position(
+                &position.id,
+                &PositionType::get_position_type(position.position_type).to_string(),
+            ),
+            &position,
+        )
+    }
+}
+
+#[substreams::handlers::map]
+pub fn map_positions(
+    block: Block,
+    all_positions_store: StoreGetProto<Position>,
+) -> Result<Positions, Error> {
+    let mut positions: Positions = Positions { positions: vec![] };
+    let mut ordered_positions: Vec<String> = vec![];
+    let mut enriched_positions: HashMap<String, Position> = HashMap::new();
+
+    for log in block.logs() {
+        if log.address() != NON_FUNGIBLE_POSITION_MANAGER {
+            continue;
+        }
+
+        if let Some(event) = abi::positionmanager::events::IncreaseLiquidity::match_and_decode(log)
+        {
+            let token_id: String = event.token_id.to_string();
+            if !enriched_positions.contains_key(&token_id) {
+                match all_positions_store.get_last(keyer::all_position(
+                    &token_id,
+                    &IncreaseLiquidity.to_string(),
+                )) {
+                    None => {
+                        log::debug!("increase liquidity for id {} doesn't exist", token_id);
+                        continue;
+                    }
+                    Some(pos) => {
+                        enriched_positions.insert(token_id.clone(), pos);
+                        if !ordered_positions.contains(&String::from(token_id.clone())) {
+                            ordered_positions.push(String::from(token_id))
+                        }
+                    }
+                }
+            }
+        } else if let Some(event) = abi::positionmanager::events::Collect::match_and_decode(log) {
+            let token_id: String = event.token_id.to_string();
+            let mut position = if !enriched_positions.contains_key(&token_id) {
+                match all_positions_store
+                    .get_last(keyer::all_position(&token_id, &Collect.to_string()))
+                {
+                    None => {
+                        log::debug!("increase liquidity for id {} doesn't exist", token_id);
+                        continue;
+                    }
+                    Some(pos) => pos,
+                }
+            } else {
+                enriched_positions
+                    .remove(&event.token_id.to_string())
+                    .unwrap()
+            };
+
+            // the collect record already carries the fee growth inside computed at collect time
+            if let Some(collect_position) =
+                all_positions_store.get_last(keyer::all_position(&token_id, &Collect.to_string()))
+            {
+                position.fee_growth_inside_0_last_x_128 =
+                    collect_position.fee_growth_inside_0_last_x_128;
+                position.fee_growth_inside_1_last_x_128 =
+                    collect_position.fee_growth_inside_1_last_x_128;
+            }
+            enriched_positions.insert(token_id.clone(), position);
+            if !ordered_positions.contains(&String::from(token_id.clone())) {
+                ordered_positions.push(String::from(token_id))
+            }
+        } else if let Some(event) =
+            abi::positionmanager::events::DecreaseLiquidity::match_and_decode(log)
+        {
+            let token_id: String = event.token_id.to_string();
+            if !enriched_positions.contains_key(&token_id) {
+                match all_positions_store.get_last(keyer::all_position(
+                    &event.token_id.to_string(),
+                    &DecreaseLiquidity.to_string(),
+                )) {
+                    None => {
+                        log::debug!("increase liquidity for id {} doesn't exist", token_id);
+                        continue;
+                    }
+                    Some(pos) => {
+                        enriched_positions.insert(token_id.clone(), pos);
+                        if !ordered_positions.contains(&String::from(token_id.clone())) {
+                            ordered_positions.push(String::from(token_id))
+                        }
+                    }
+                }
+            }
+        } else if let Some(event) = abi::positionmanager::events::Transfer::match_and_decode(log) {
+            let token_id: String = event.token_id.to_string();
+            let mut position = if !enriched_positions.contains_key(&token_id) {
+                match all_positions_store
+                    .get_last(keyer::all_position(&token_id, &Transfer.to_string()))
+                {
+                    None => {
+                        log::debug!("increase liquidity for id {} doesn't exist", token_id);
+                        continue;
+                    }
+                    Some(pos) => pos,
+                }
+            } else {
+                enriched_positions.remove(&token_id).unwrap()
+            };
+
+            position.owner = Hex(event.to.as_slice()).to_string();
+            enriched_positions.insert(token_id.clone(), position);
+            if !ordered_positions.contains(&String::from(token_id.clone())) {
+                ordered_positions.push(String::from(token_id))
+            }
+        }
+    }
+
+    log::debug!("len of map: {}", enriched_positions.len());
+    for element in ordered_positions.iter() {
+        let pos = enriched_positions.remove(element);
+        if pos.is_some() {
+            positions.positions.push(pos.unwrap());
+        }
+    }
+
+    Ok(positions)
+}
+
+#[substreams::handlers::store]
+pub fn store_position_changes(all_positions: Positions, store: StoreAddBigDecimal) {
+    for position in all_positions.positions {
+        match position.convert_position_type() {
+            IncreaseLiquidity => {
+                store.add(
+                    position.log_ordinal,
+                    keyer::position_liquidity(&position.id),
+                    &BigDecimal::from(position.liquidity.unwrap()),
+                );
+                store.add(
+                    position.log_ordinal,
+                    keyer::position_deposited_token(&position.id, "Token0"),
+                    &BigDecimal::from(position.amount0.unwrap()),
+                );
+                store.add(
+                    position.log_ordinal,
+                    keyer::position_deposited_token(&position.id, "Token1"),
+                    &BigDecimal::from(position.amount1.unwrap()),
+                );
+            }
+            DecreaseLiquidity => {
+                store.add(
+                    position.log_ordinal,
+                    keyer::position_liquidity(&position.id),
+                    &BigDecimal::from(position.liquidity.unwrap()).neg(),
+                );
+                store.add(
+                    position.log_ordinal,
+                    keyer::position_withdrawn_token(&position.id, "Token0"),
+                    &BigDecimal::from(position.amount0.unwrap()),
+                );
+                store.add(
+                    position.log_ordinal,
+                    keyer::position_withdrawn_token(&position.id, "Token1"),
+                    &BigDecimal::from(position.amount1.unwrap()),
+                );
+            }
+            Collect => {
+                store.add(
+                    position.log_ordinal,
+                    keyer::position_collected_fees_token(&position.id, "Token0"),
+                    &BigDecimal::from(position.amount0.unwrap()),
+                );
+                store.add(
+                    position.log_ordinal,
+                    keyer::position_collected_fees_token(&position.id, "Token1"),
+                    &BigDecimal::from(position.amount1.unwrap()),
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+#[substreams::handlers::store]
+pub fn store_position_fee_growth_inside_last(all_positions: Positions, store: StoreSetBigInt) {
+    for position in all_positions.positions {
+        match position.convert_position_type() {
+            IncreaseLiquidity | DecreaseLiquidity | Collect => {}
+            _ => continue,
+        }
+
+        store.set(
+            position.log_ordinal,
+            keyer::position_fee_growth_inside_last_x128(&position.id, "Token0"),
+            &BigInt::from(position.fee_growth_inside_0_last_x_128.unwrap()),
+        );
+        store.set(
+            position.log_ordinal,
+            keyer::position_fee_growth_inside_last_x128(&position.id, "Token1"),
+            &BigInt::from(position.fee_growth_inside_1_last_x_128.unwrap()),
+        );
+    }
+}
+
+// fees the PositionManager credits to tokensOwed every time it pokes the position
+#[substreams::handlers::store]
+pub fn store_position_fees_accrued(
+    all_positions: Positions,
+    pools_store: StoreGetProto<Pool>,
+    position_changes_store: StoreGetBigDecimal,
+    fee_growth_inside_last_store: StoreGetBigInt,
+    store: StoreAddBigDecimal,
+) {
+    for position in all_positions.positions {
+        match position.convert_position_type() {
+            IncreaseLiquidity | DecreaseLiquidity | Collect => {}
+            _ => continue,
+        }
+
+        let pool = match pools_store.get_last(keyer::pool_key(&position.pool)) {
+            None => continue,
+            Some(pool) => pool,
+        };
+
+        // state of the position right before this event
+        let previous_ordinal = position.log_ordinal - 1;
+        let liquidity = position_changes_store
+            .get_at(previous_ordinal, keyer::position_liquidity(&position.id))
+            .unwrap_or(BigDecimal::zero());
+        if !liquidity.gt(&BigDecimal::zero()) {
+            continue;
+        }
+
+        for (token, decimals, fee_growth_inside_x128) in [
+            (
+                "Token0",
+                pool.token0_ref().decimals,
+                BigInt::from(position.fee_growth_inside_0_last_x_128.clone().unwrap()),
+            ),
+            (
+                "Token1",
+                pool.token1_ref().decimals,
+                BigInt::from(position.fee_growth_inside_1_last_x_128.clone().unwrap()),
+            ),
+        ] {
+            let fee_growth_inside_last_x128 = match fee_growth_inside_last_store.get_at(
+                previous_ordinal,
+                keyer::position_fee_growth_inside_last_x128(&position.id, token),
+            ) {
+                None => continue,
+                Some(fee_growth_inside_last_x128) => fee_growth_inside_last_x128,
+            };
+
+            store.add(
+                position.log_ordinal,
+                keyer::position_fees_accrued_token(&position.id, token),
+                &math::fees_earned(
+                    &liquidity,
+                    &fee_growth_inside_x128,
+                    &fee_growth_inside_last_x128,
+                    decimals,
+                ),
+            );
+        }
+    }
+}
+
+#[substreams::handlers::store]
+pub fn store_tick_fee_growth_outside_slots(ticks: Ticks, store: StoreSetIfNotExistsString) {
+    for tick in ticks.ticks {
+        let tick_idx = BigInt::from(tick.idx.unwrap());
+        for (slot, token) in utils::tick_fee_growth_outside_x128_slots(&tick_idx) {
+            store.set_if_not_exists(
+                tick.log_ordinal,
+                keyer::tick_fee_growth_outside_slot(&tick.pool_address, &slot),
+                &format!("{}:{}", tick_idx, token),
+            );
+        }
+    }
+}
+
+// feeGrowthOutside is written when a tick gets initialized and flipped every time a swap
+// crosses it, follow it through the pool storage
+#[substreams::handlers::store]
+pub fn store_tick_fee_growth_outside_x128(
+    block: Block,
+    pools_store: StoreGetProto<Pool>,
+    tick_slots_store: StoreGetString,
+    store: StoreSetBigInt,
+) {
+    let mut known_pools: HashMap<Vec<u8>, bool> = HashMap::new();
+    for trx in block.transaction_traces {
+        if trx.status != 1 {
+            continue;
+        }
+        for call in trx.calls {
+            if call.state_reverted {
+                continue;
+            }
+            for storage_change in call.storage_changes {
+                let pool_address = Hex(&storage_change.address).to_string();
+                let is_pool = *known_pools
+                    .entry(storage_change.address.clone())
+                    .or_insert_with(|| {
+                        pools_store
+                            .get_last(keyer::pool_key(&pool_address))
+                            .is_some()
+                    });
+                if !is_pool {
+                    continue;
+                }
+
+                let tick_slot =
+                    match tick_slots_store.get_last(keyer::tick_fee_growth_outside_slot(
+                        &pool_address,
+                        &Hex(&storage_change.key).to_string(),
+                    )) {
+                        None => continue,
+                        Some(tick_slot) => tick_slot,
+                    };
+                let (tick_idx, token) = tick_slot.split_once(":").unwrap();
+
+                store.set(
+                    storage_change.ordinal,
+                    keyer::tick_fee_growth_outside_x128(
+                        &format!("{}#{}", pool_address, tick_idx),
+                        token,
+                    ),
+                    &BigInt::from_unsigned_bytes_be(&storage_change.new_value),
+                );
+            }
+        }
+    }
+}
+
+#[substreams::handlers::store]
+pub fn store_pool_ticks(ticks_deltas: store::Deltas<DeltaProto<Tick>>, store: StoreAppend<String>) {
+    // ticks are never removed from store_ticks, so each one is listed once
+    for delta in ticks_deltas.deltas {
+        if delta.operation != store::Operation::Create {
+            continue;
+        }
+        store.append(
+            delta.ordinal,
+            keyer::pool_ticks(&delta.new_value.pool_address),
+            BigInt::from(delta.new_value.idx.unwrap()).to_string(),
+        );
+    }
+}
+
+// positions that got liquidity on a tick boundary, the list being dropped once the tick
+// is deinitialized as none of its positions can have liquidity left
+#[substreams::handlers::store]
+pub fn store_tick_positions(
+    position_changes_deltas: store::Deltas<DeltaBigDecimal>,
+    ticks_liquidities_deltas: store::Deltas<DeltaBigInt>,
+    position_static_store: StoreGetProto<PositionStatic>,
+    output_append: StoreAppend<String>,
+) {
+    // (ordinal, tick id, position id), no position id dropping the tick list
+    let mut changes: Vec<(u64, String, Option<String>)> = vec![];
+    for delta in position_changes_deltas.deltas {
+        if delta.key.as_str().split(":").last().unwrap() != "liquidity"
+            || delta.old_value.gt(&BigDecimal::zero())
+            || !delta.new_value.gt(&BigDecimal::zero())
+        {
+            continue;
+        }
+
+        let position_id = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        let position_static =
+            match position_static_store.get_last(keyer::position_static(&position_id)) {
+                None => continue,
+                Some(position_static) => position_static,
+            };
+        for tick_idx in [
+            BigInt::from(position_static.tick_lower.clone().unwrap()),
+            BigInt::from(position_static.tick_upper.clone().unwrap()),
+        ] {
+            changes.push((
+                delta.ordinal,
+                format!("{}#{}", position_static.pool, tick_idx),
+                Some(position_id.clone()),
+            ));
+        }
+    }
+    for delta in ticks_liquidities_deltas.deltas {
+        let tick_id = match delta
+            .key
+            .strip_prefix("tick:")
+            .and_then(|key| key.strip_suffix(":liquidityGross"))
+        {
+            None => continue,
+            Some(tick_id) => tick_id.to_string(),
+        };
+        if delta.new_value.eq(&BigInt::zero()) {
+            changes.push((delta.ordinal, tick_id, None));
+        }
+    }
+
+    changes.sort_by(|a, b| a.0.cmp(&b.0));
+    for (ordinal, tick_id, position_id) in changes {
+        match position_id {
+            None => output_append.delete_prefix(ordinal as i64, &keyer::tick_positions(&tick_id)),
+            Some(position_id) => {
+                output_append.append(ordinal, keyer::tick_positions(&tick_id), position_id)
+            }
+        }
+    }
+}
+
+#[substreams::handlers::map]
+pub fn map_position_uncollected_fees(
+    all_positions: Positions,
+    tick_fee_growth_outside_deltas: store::Deltas<DeltaBigInt>,
+    pool_fee_growth_global_x128_deltas: store::Deltas<DeltaBigInt>,
+    pool_ticks_store: StoreGetRaw,
+    tick_positions_store: StoreGetRaw,
+    ticks_liquidities_store: StoreGetBigInt,
+    position_static_store: StoreGetProto<PositionStatic>,
+    pools_store: StoreGetProto<Pool>,
+    tick_fee_growth_outside_store: StoreGetBigInt,
+    pool_sqrt_price_store: StoreGetProto<PoolSqrtPrice>,
+    pool_fee_growth_global_x128_store: StoreGetBigInt,
+    fee_growth_inside_last_store: StoreGetBigInt,
+    fees_accrued_store: StoreGetBigDecimal,
+    position_changes_store: StoreGetBigDecimal,
+    eth_prices_store: StoreGetBigDecimal,
+) -> Result<PositionUncollectedFees, Error> {
+    let mut ordered_positions: Vec<String> = vec![];
+    let mut ordinals: HashMap<String, u64> = HashMap::new();
+    let mut touch = |position_id: String, ordinal: u64| match ordinals.get_mut(&position_id) {
+        None => {
+            ordered_positions.push(position_id.clone());
+            ordinals.insert(position_id, ordinal);
+        }
+        Some(last_ordinal) => {
+            if ordinal > *last_ordinal {
+                *last_ordinal = ordinal;
+            }
+        }
+    };
+
+    let tick_positions = |tick_id: &String| -> Vec<String> {
+        match tick_positions_store.get_last(keyer::tick_positions(tick_id)) {
+            None => vec![],
+            Some(bytes) => String::from_utf8(bytes.to_vec())
+                .unwrap()
+                .split(";")
+                .filter(|position_id| !position_id.is_empty())
+                .map(|position_id| position_id.to_string())
+                .collect(),
+        }
+    };
+    let has_liquidity = |ordinal: u64, position_id: &String| -> bool {
+        position_changes_store
+            .get_at(ordinal, keyer::position_liquidity(position_id))
+            .unwrap_or(BigDecimal::zero())
+            .gt(&BigDecimal::zero())
+    };
+
+    for position in all_positions.positions {
+        touch(position.id, position.log_ordinal);
+    }
+
+    // a crossed tick changes the fee growth inside of the ranges it bounds
+    for delta in tick_fee_growth_outside_deltas.deltas {
+        let tick_id = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        for position_id in tick_positions(&tick_id) {
+            if has_liquidity(delta.ordinal, &position_id) {
+                touch(position_id, delta.ordinal);
+            }
+        }
+    }
+
+    // fees grow inside the ranges holding the current tick, keys are fee:{pool}:{token}
+    let mut pool_ordinals: Vec<(String, u64)> = vec![];
+    for delta in pool_fee_growth_global_x128_deltas.deltas {
+        let pool_address = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        match pool_ordinals
+            .iter_mut()
+            .find(|(address, _)| *address == pool_address)
+        {
+            None => pool_ordinals.push((pool_address, delta.ordinal)),
+            Some((_, last_ordinal)) => {
+                if delta.ordinal > *last_ordinal {
+                    *last_ordinal = delta.ordinal;
+                }
+            }
+        }
+    }
+    for (pool_address, ordinal) in pool_ordinals {
+        let tick_current = match pool_sqrt_price_store
+            .get_at(ordinal, keyer::pool_sqrt_price_key(&pool_address))
+        {
+            None => continue,
+            Some(pool_sqrt_price) => pool_sqrt_price.tick(),
+        };
+        let pool_ticks = match pool_ticks_store.get_last(keyer::pool_ticks(&pool_address)) {
+            None => continue,
+            Some(bytes) => String::from_utf8(bytes.to_vec()).unwrap(),
+        };
+
+        for tick_idx in pool_ticks
+            .split(";")
+            .filter(|tick_idx| !tick_idx.is_empty())
+        {
+            if BigInt::from_str(tick_idx).unwrap().gt(&tick_current) {
+                continue;
+            }
+            let tick_id = format!("{}#{}", pool_address, tick_idx);
+            let liquidity_gross = ticks_liquidities_store
+                .get_at(ordinal, keyer::tick_liquidities_gross(&tick_id))
+                .unwrap_or(BigInt::zero());
+            if liquidity_gross.eq(&BigInt::zero()) {
+                continue;
+            }
+
+            for position_id in tick_positions(&tick_id) {
+                let position_static =
+                    match position_static_store.get_last(keyer::position_static(&position_id)) {
+                        None => continue,
+                        Some(position_static) => position_static,
+                    };
+                let tick_lower = BigInt::from(position_static.tick_lower.unwrap());
+                let tick_upper = BigInt::from(position_static.tick_upper.unwrap());
+                if tick_lower.to_string() != tick_idx || !tick_upper.gt(&tick_current) {
+                    continue;
+                }
+                if has_liquidity(ordinal, &position_id) {
+                    touch(position_id, ordinal);
+                }
+            }
+        }
+    }
+
+    let bundle_eth_price = eth_prices_store
+        .get_last(keyer::bundle_eth_price())
+        .unwrap_or(BigDecimal::zero());
+
+    let mut position_uncollected_fees = vec![];
+    for position_id in ordered_positions {
+        let position_static =
+            match position_static_store.get_last(keyer::position_static(&position_id)) {
+                None => continue,
+                Some(position_static) => position_static,
+            };
+        let pool = match pools_store.get_last(keyer::pool_key(&position_static.pool)) {
+            None => continue,
+            Some(pool) => pool,
+        };
+
+        let ordinal = ordinals[&position_id];
+        let position_change = |key: String| -> BigDecimal {
+            position_changes_store
+                .get_at(ordinal, key)
+                .unwrap_or(BigDecimal::zero())
+        };
+        let liquidity = position_change(keyer::position_liquidity(&position_id));
+        let (fee_growth_inside_0_x128, fee_growth_inside_1_x128) =
+            match utils::get_fee_growth_inside_x128(
+                ordinal,
+                &pool.address,
+                &BigInt::from(position_static.tick_lower.unwrap()),
+                &BigInt::from(position_static.tick_upper.unwrap()),
+                &tick_fee_growth_outside_store,
+                &pool_sqrt_price_store,
+                &pool_fee_growth_global_x128_store,
+            ) {
+                None => continue,
+                Some(fee_growth_inside_x128) => fee_growth_inside_x128,
+            };
+
+        let mut uncollected_fees: Vec<BigDecimal> = vec![];
+        for (token, decimals, fee_growth_inside_x128) in [
+            (
+                "Token0",
+                pool.token0_ref().decimals,
+                fee_growth_inside_0_x128,
+            ),
+            (
+                "Token1",
+                pool.token1_ref().decimals,
+                fee_growth_inside_1_x128,
+            ),
+        ] {
+            // tokensOwed: principal withdrawn and fees credited, minus what was already collected
+            let owed = position_change(keyer::position_withdrawn_token(&position_id, token))
+                .add(
+                    fees_accrued_store
+                        .get_at(
+                            ordinal,
+                            keyer::position_fees_accrued_token(&position_id, token),
+                        )
+                        .unwrap_or(BigDecimal::zero()),
+                )
+                .sub(position_change(keyer::position_collected_fees_token(
+                    &position_id,
+                    token,
+                )));
+
+            let pending = match fee_growth_inside_last_store.get_at(
+                ordinal,
+                keyer::position_fee_growth_inside_last_x128(&position_id, token),
+            ) {
+                None => BigDecimal::zero(),
+                Some(fee_growth_inside_last_x128) => math::fees_earned(
+                    &liquidity,
+                    &fee_growth_inside_x128,
+                    &fee_growth_inside_last_x128,
+                    decimals,
+                ),
+            };
+
+            let uncollected = owed.add(pending);
+            if uncollected.lt(&BigDecimal::zero()) {
+                uncollected_fees.push(BigDecimal::zero());
+            } else {
+                uncollected_fees.push(uncollected);
+            }
+        }
+
+        let token0_derived_eth_price = eth_prices_store
+            .get_last(keyer::token_eth_price(&position_static.token0))
+            .unwrap_or(BigDecimal::zero());
+        let token1_derived_eth_price = eth_prices_store
+            .get_last(keyer::token_eth_price(&position_static.token1))
+            .unwrap_or(BigDecimal::zero());
+        let uncollected_fees_usd = utils::calculate_amount_usd(
+            &uncollected_fees[0],
+            &uncollected_fees[1],
+            &token0_derived_eth_price,
+            &token1_derived_eth_price,
+            &bundle_eth_price,
+        );
+
+        position_uncollected_fees.push(PositionUncollectedFee {
+            position_id: position_id.clone(),
+            uncollected_fees_token0: Some(uncollected_fees[0].clone().into()),
+            uncollected_fees_token1: Some(uncollected_fees[1].clone().into()),
+            uncollected_fees_usd: Some(uncollected_fees_usd.into()),
+            log_ordinal: ordinals[&position_id],
+        });
+    }
+
+    Ok(PositionUncollectedFees {
+        position_uncollected_fees,
+    })
+}
+
+//todo: maybe exact the some/none part in a macro and use it in the db?
+// as in the string is empty/0 in this use-case it would mean the same thing
+#[substreams::handlers::map]
+pub fn map_position_snapshots(
+    positions: Positions,
+    position_changes_store: StoreGetBigDecimal,
+) -> Result<SnapshotPositions, Error> {
+    let mut snapshot_positions: SnapshotPositions = SnapshotPositions {
+        snapshot_positions: vec![],
+    };
+
+    for position in positions.positions {
+        let mut snapshot_position: SnapshotPosition = SnapshotPosition {
+            id: format!("{}#{}", position.id, position.block_number),
+            owner: position.owner,
+            pool: position.pool,
+            position: position.id.clone(),
+            block_number: position.block_number,
+            timestamp: position.timestamp,
+            transaction: position.transaction,
+            fee_growth_inside_0_last_x_128: position.fee_growth_inside_0_last_x_128,
+            fee_growth_inside_1_last_x_128: position.fee_growth_inside_1_last_x_128,
+            log_ordinal: position.log_ordinal,
+            ..Default::default()
+        };
+
+        match position_changes_store.get_last(keyer::position_liquidity(&position.id)) {
+            Some(liquidity) => snapshot_position.liquidity = Some(liquidity.into()),
+            _ => snapshot_position.liquidity = None,
+        }
+
+        match position_changes_store
+            .get_last(keyer::position_deposited_token(&position.id, "Token0"))
+        {
+            Some(deposited_token0) => {
+                snapshot_position.deposited_token0 = Some(deposited_token0.into());
+            }
+            _ => snapshot_position.deposited_token0 = Some(BigDecimal::zero().into()),
+        }
+
+        match position_changes_store
+            .get_last(keyer::position_deposited_token(&position.id, "Token1"))
+        {
+            Some(deposited_token1) => {
+                snapshot_position.deposited_token1 = Some(deposited_token1.into());
+            }
+            _ => snapshot_position.deposited_token1 = Some(BigDecimal::zero().into()),
+        }
+
+        match position_changes_store
+            .get_last(keyer::position_withdrawn_token(&position.id, "Token0"))
+        {
+            Some(withdrawn_token0) => {
+                snapshot_position.withdrawn_token0 = Some(withdrawn_token0.into());
+            }
+            _ => snapshot_position.withdrawn_token0 = Some(BigDecimal::zero().into()),
+        }
+
+        match position_changes_store
+            .get_last(keyer::position_withdrawn_token(&position.id, "Token1"))
+        {
+            Some(withdrawn_token1) => {
+                snapshot_position.withdrawn_token1 = Some(withdrawn_token1.into());
+            }
+            _ => snapshot_position.withdrawn_token1 = Some(BigDecimal::zero().into()),
+        }
+
+        match position_changes_store
+            .get_last(keyer::position_collected_fees_token(&position.id, "Token0"))
+        {
+            Some(collected_fees_token0) => {
+                snapshot_position.collected_fees_token0 = Some(collected_fees_token0.into());
+            }
+            _ => snapshot_position.collected_fees_token0 = Some(BigDecimal::zero().into()),
+        }
+
+        match position_changes_store
+            .get_last(keyer::position_collected_fees_token(&position.id, "Token1"))
+        {
+            Some(collected_fees_token1) => {
+                snapshot_position.collected_fees_token1 = Some(collected_fees_token1.into());
+            }
+            _ => snapshot_position.collected_fees_token1 = Some(BigDecimal::zero().into()),
+        }
+
+        snapshot_positions
+            .snapshot_positions
+            .push(snapshot_position);
+    }
+
+    Ok(snapshot_positions)
+}
+
+#[substreams::handlers::store]
+pub fn store_pool_active_positions(
+    position_changes_deltas: store::Deltas<DeltaBigDecimal>,
+    position_static_store: StoreGetProto<PositionStatic>,
+    store: StoreSetString,
+) {
+    // values are {tick_lower}:{tick_upper}
+    for delta in position_changes_deltas.deltas {
+        if delta.key.as_str().split(":").last().unwrap() != "liquidity" {
+            continue;
+        }
+        let was_active = delta.old_value.gt(&BigDecimal::zero());
+        let is_active = delta.new_value.gt(&BigDecimal::zero());
+        if was_active == is_active {
+            continue;
+        }
+
+        let position_id = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        let position_static =
+            match position_static_store.get_last(keyer::position_static(&position_id)) {
+                None => continue,
+                Some(position_static) => position_static,
+            };
+        let key = keyer::pool_active_position(&position_static.pool, &position_id);
+        if is_active {
+            store.set(
+                delta.ordinal,
+                key,
+                &format!(
+                    "{}:{}",
+                    BigInt::from(position_static.tick_lower.unwrap()),
+                    BigInt::from(position_static.tick_upper.unwrap())
+                ),
+            );
+        } else {
+            store.delete_prefix(delta.ordinal as i64, &key);
+        }
+    }
+}
+
+#[substreams::handlers::map]
+pub fn map_position_range_events(
+    clock: Clock,
+    pool_active_positions_deltas: store::Deltas<DeltaString>,
+    pool_sqrt_price_deltas: store::Deltas<DeltaProto<PoolSqrtPrice>>,
+    pool_ticks_store: StoreGetRaw,
+    tick_positions_store: StoreGetRaw,
+    pool_active_positions_store: StoreGetString,
+    pool_sqrt_price_store: StoreGetProto<PoolSqrtPrice>,
+) -> Result<PositionRangeEvents, Error> {
+    let timestamp = clock.timestamp.unwrap().seconds as u64;
+    let in_range = |tick: &BigInt, tick_lower: &BigInt, tick_upper: &BigInt| -> bool {
+        !tick.lt(tick_lower) && tick.lt(tick_upper)
+    };
+    let range_event =
+        |position_id: &String, pool: &String, entered: bool, tick: BigInt, ordinal: u64| {
+            let event_type = if entered {
+                RangeEventType::Entered
+            } else {
+                RangeEventType::Exited
+            };
+            PositionRangeEvent {
+                id: format!("{}#{}#{}", position_id, clock.number, ordinal),
+                position_id: position_id.clone(),
+                pool: pool.clone(),
+                r#type: event_type as i32,
+                tick: Some(tick.into()),
+                block_number: clock.number,
+                timestamp,
+                log_ordinal: ordinal,
+            }
+        };
+    let parse_range = |range: &String| -> (BigInt, BigInt) {
+        let (tick_lower, tick_upper) = range.split_once(":").unwrap();
+        (
+            BigInt::from_str(tick_lower).unwrap(),
+            BigInt::from_str(tick_upper).unwrap(),
+        )
+    };
+    let split_list = |bytes: Vec<u8>| -> Vec<String> {
+        String::from_utf8(bytes)
+            .unwrap()
+            .split(";")
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| entry.to_string())
+            .collect()
+    };
+
+    let mut position_range_events = vec![];
+
+    // a position earns fees while it has liquidity, so gaining or losing all of it
+    // in range enters or exits the range, keys are pool:{pool}:activePosition:{id}:range
+    for delta in pool_active_positions_deltas.deltas {
+        let (entered, range) = match delta.operation {
+            store::Operation::Create => (true, &delta.new_value),
+            store::Operation::Delete => (false, &delta.old_value),
+            _ => continue,
+        };
+        let pool_address = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        let position_id = delta.key.as_str().split(":").nth(3).unwrap().to_string();
+
+        let tick = match pool_sqrt_price_store
+            .get_at(delta.ordinal, keyer::pool_sqrt_price_key(&pool_address))
+        {
+            None => continue,
+            Some(pool_sqrt_price) => pool_sqrt_price.tick(),
+        };
+        let (tick_lower, tick_upper) = parse_range(range);
+        if in_range(&tick, &tick_lower, &tick_upper) {
+            position_range_events.push(range_event(
+                &position_id,
+                &pool_address,
+                entered,
+                tick,
+                delta.ordinal,
+            ));
+        }
+    }
+
+    // only positions bounded by a tick the price moved over can change range
+    for delta in pool_sqrt_price_deltas.deltas {
+        // the first delta of a pool is its initialization, no position exists yet
+        if delta.operation == store::Operation::Create {
+            continue;
+        }
+        let old_tick = delta.old_value.tick();
+        let new_tick = delta.new_value.tick();
+        if old_tick == new_tick {
+            continue;
+        }
+        let (lowest_tick, highest_tick) = if old_tick.lt(&new_tick) {
+            (&old_tick, &new_tick)
+        } else {
+            (&new_tick, &old_tick)
+        };
+
+        let pool_address = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        let pool_ticks = match pool_ticks_store.get_last(keyer::pool_ticks(&pool_address)) {
+            None => continue,
+            Some(bytes) => split_list(bytes),
+        };
+
+        let mut checked_positions: Vec<String> = vec![];
+        for tick_idx in pool_ticks {
+            let tick = BigInt::from_str(&tick_idx).unwrap();
+            if !tick.gt(lowest_tick) || tick.gt(highest_tick) {
+                continue;
             }
-        } else if let Some(event) = abi::positionmanager::events::Transfer::match_and_decode(log) {
-            if let Some(position) = utils::get_position(
-                &store_pool,
-                &Hex(log.address()).to_string(),
-                &log.receipt.transaction.hash,
-                Transfer,
-                log.ordinal(),
-                block
-                    .header
-                    .as_ref()
-                    .unwrap()
-                    .timestamp
-                    .as_ref()
-                    .unwrap()
-                    .seconds as u64,
-                block.number,
-                PositionEvent {
-                    event: PositionEventType::Transfer(event.clone()),
-                },
-            ) {
-                positions.positions.push(position);
+            let tick_positions = match tick_positions_store.get_last(keyer::tick_positions(
+                &format!("{}#{}", pool_address, tick_idx),
+            )) {
+                None => continue,
+                Some(bytes) => split_list(bytes),
+            };
+
+            for position_id in tick_positions {
+                if checked_positions.contains(&position_id) {
+                    continue;
+                }
+                checked_positions.push(position_id.clone());
+
+                let range = match pool_active_positions_store.get_at(
+                    delta.ordinal,
+                    keyer::pool_active_position(&pool_address, &position_id),
+                ) {
+                    None => continue,
+                    Some(range) => range,
+                };
+                let (tick_lower, tick_upper) = parse_range(&range);
+                let was_in_range = in_range(&old_tick, &tick_lower, &tick_upper);
+                let is_in_range = in_range(&new_tick, &tick_lower, &tick_upper);
+                if was_in_range != is_in_range {
+                    position_range_events.push(range_event(
+                        &position_id,
+                        &pool_address,
+                        is_in_range,
+                        new_tick.clone(),
+                        delta.ordinal,
+                    ));
+                }
             }
         }
     }
 
-    Ok(positions)
+    Ok(PositionRangeEvents {
+        position_range_events,
+    })
 }
 
 #[substreams::handlers::store]
-pub fn store_all_positions(positions: Positions, store: StoreSetProto<Position>) {
-    for position in positions.positions {
+pub fn store_position_range_status(
+    position_range_events: PositionRangeEvents,
+    store: StoreSetProto<PositionRangeEvent>,
+) {
+    for range_event in position_range_events.position_range_events {
         store.set(
-            position.log_ordinal,
-            keyer::all_position(
-                &position.id,
-                &PositionType::get_position_type(position.position_type).to_string(),
-            ),
-            &position,
-        )
+            range_event.log_ordinal,
+            keyer::position_range_status(&range_event.position_id),
+            &range_event,
+        );
+    }
+}
+
+#[substreams::handlers::store]
+pub fn store_position_time_in_range(
+    position_range_events: PositionRangeEvents,
+    position_range_status_store: StoreGetProto<PositionRangeEvent>,
+    store: StoreAddBigInt,
+) {
+    for range_event in position_range_events.position_range_events {
+        if range_event.is_entered() {
+            continue;
+        }
+
+        let entered = match position_range_status_store.get_at(
+            range_event.log_ordinal - 1,
+            keyer::position_range_status(&range_event.position_id),
+        ) {
+            None => continue,
+            Some(entered) => entered,
+        };
+        if !entered.is_entered() {
+            continue;
+        }
+
+        store.add(
+            range_event.log_ordinal,
+            keyer::position_time_in_range(&range_event.position_id),
+            &BigInt::from(range_event.timestamp - entered.timestamp),
+        );
     }
 }
 
 #[substreams::handlers::map]
-pub fn map_positions(
-    block: Block,
-    all_positions_store: StoreGetProto<Position>,
-) -> Result<Positions, Error> {
-    let mut positions: Positions = Positions { positions: vec![] };
-    let mut ordered_positions: Vec<String> = vec![];
-    let mut enriched_positions: HashMap<String, Position> = HashMap::new();
+pub fn map_position_analytics(
+    snapshot_positions: SnapshotPositions,
+    position_static_store: StoreGetProto<PositionStatic>,
+    pools_store: StoreGetProto<Pool>,
+    pool_sqrt_price_store: StoreGetProto<PoolSqrtPrice>,
+    eth_prices_store: StoreGetBigDecimal,
+) -> Result<PositionPerformances, Error> {
+    let bundle_eth_price = eth_prices_store
+        .get_last(keyer::bundle_eth_price())
+        .unwrap_or(BigDecimal::zero());
+    let seconds_per_year = BigDecimal::from(31536000 as u64);
+
+    let mut position_performances = vec![];
+    for snapshot_position in snapshot_positions.snapshot_positions {
+        let position_static = match position_static_store
+            .get_last(keyer::position_static(&snapshot_position.position))
+        {
+            None => continue,
+            Some(position_static) => position_static,
+        };
+        let pool = match pools_store.get_last(keyer::pool_key(&position_static.pool)) {
+            None => continue,
+            Some(pool) => pool,
+        };
+        let sqrt_price =
+            match pool_sqrt_price_store.get_last(keyer::pool_sqrt_price_key(&pool.address)) {
+                None => continue,
+                Some(pool_sqrt_price) => math::sqrt_price_from_x96(&pool_sqrt_price.sqrt_price()),
+            };
+
+        let token0_derived_eth_price = eth_prices_store
+            .get_last(keyer::token_eth_price(&position_static.token0))
+            .unwrap_or(BigDecimal::zero());
+        let token1_derived_eth_price = eth_prices_store
+            .get_last(keyer::token_eth_price(&position_static.token1))
+            .unwrap_or(BigDecimal::zero());
+        let amount_usd = |amount0: &BigDecimal, amount1: &BigDecimal| -> BigDecimal {
+            utils::calculate_amount_usd(
+                amount0,
+                amount1,
+                &token0_derived_eth_price,
+                &token1_derived_eth_price,
+                &bundle_eth_price,
+            )
+        };
+
+        let liquidity = snapshot_position
+            .liquidity
+            .clone()
+            .map(BigDecimal::from)
+            .unwrap_or(BigDecimal::zero());
+        let (current_amount0, current_amount1) = math::amounts_for_liquidity(
+            &liquidity,
+            &sqrt_price,
+            &math::sqrt_price_at_tick(&position_static.tick_lower.unwrap().into()),
+            &math::sqrt_price_at_tick(&position_static.tick_upper.unwrap().into()),
+        );
+        let current_value_usd = amount_usd(
+            &current_amount0.div(math::exponent_to_big_decimal(&BigInt::from(
+                pool.token0_ref().decimals,
+            ))),
+            &current_amount1.div(math::exponent_to_big_decimal(&BigInt::from(
+                pool.token1_ref().decimals,
+            ))),
+        );
+
+        let deposited0 = BigDecimal::from(snapshot_position.deposited_token0.unwrap());
+        let deposited1 = BigDecimal::from(snapshot_position.deposited_token1.unwrap());
+        let withdrawn0 = BigDecimal::from(snapshot_position.withdrawn_token0.unwrap());
+        let withdrawn1 = BigDecimal::from(snapshot_position.withdrawn_token1.unwrap());
+        let collected0 = BigDecimal::from(snapshot_position.collected_fees_token0.unwrap());
+        let collected1 = BigDecimal::from(snapshot_position.collected_fees_token1.unwrap());
+
+        let deposited_usd = amount_usd(&deposited0, &deposited1);
+        let withdrawn_usd = amount_usd(&withdrawn0, &withdrawn1);
+        let hodl_value_usd = amount_usd(
+            &deposited0.clone().sub(withdrawn0.clone()),
+            &deposited1.clone().sub(withdrawn1.clone()),
+        );
+
+        // collects pay out the withdrawn principal along with the fees
+        let mut realized_fees0 = collected0.sub(withdrawn0);
+        if realized_fees0.lt(&BigDecimal::zero()) {
+            realized_fees0 = BigDecimal::zero();
+        }
+        let mut realized_fees1 = collected1.sub(withdrawn1);
+        if realized_fees1.lt(&BigDecimal::zero()) {
+            realized_fees1 = BigDecimal::zero();
+        }
+        let realized_fees_usd = amount_usd(&realized_fees0, &realized_fees1);
+
+        let time_in_position = snapshot_position
+            .timestamp
+            .saturating_sub(position_static.timestamp);
+        let fee_apr = if time_in_position == 0 {
+            BigDecimal::zero()
+        } else {
+            math::safe_div(&realized_fees_usd, &hodl_value_usd)
+                .mul(seconds_per_year.clone())
+                .div(BigDecimal::from(time_in_position))
+        };
+
+        position_performances.push(PositionPerformance {
+            id: snapshot_position.position.clone(),
+            snapshot: snapshot_position.id.clone(),
+            block_number: snapshot_position.block_number,
+            timestamp: snapshot_position.timestamp,
+            current_value_usd: Some(current_value_usd.clone().into()),
+            hodl_value_usd: Some(hodl_value_usd.clone().into()),
+            impermanent_loss_usd: Some(current_value_usd.clone().sub(hodl_value_usd).into()),
+            realized_fees_usd: Some(realized_fees_usd.clone().into()),
+            pnl_usd: Some(
+                current_value_usd
+                    .add(withdrawn_usd)
+                    .add(realized_fees_usd)
+                    .sub(deposited_usd)
+                    .into(),
+            ),
+            time_in_position,
+            fee_apr: Some(fee_apr.into()),
+            log_ordinal: snapshot_position.log_ordinal,
+        });
+    }
 
+    Ok(PositionPerformances {
+        position_performances,
+    })
+}
+
+#[substreams::handlers::store]
+pub fn store_position_owners(block: Block, store: StoreSetString) {
+    let zero_address = Hex(ZERO_ADDRESS).to_string();
     for log in block.logs() {
         if log.address() != NON_FUNGIBLE_POSITION_MANAGER {
             continue;
         }
 
-        if let Some(event) = abi::positionmanager::events::IncreaseLiquidity::match_and_decode(log)
-        {
-            let token_id: String = event.token_id.to_string();
-            if !enriched_positions.contains_key(&token_id) {
-                match all_positions_store.get_last(keyer::all_position(
-                    &token_id,
-                    &IncreaseLiquidity.to_string(),
-                )) {
-                    None => {
-                        log::debug!("increase liquidity for id {} doesn't exist", token_id);
-                        continue;
-                    }
-                    Some(pos) => {
-                        enriched_positions.insert(token_id.clone(), pos);
-                        if !ordered_positions.contains(&String::from(token_id.clone())) {
-                            ordered_positions.push(String::from(token_id))
-                        }
-                    }
-                }
+        if let Some(event) = abi::positionmanager::events::Transfer::match_and_decode(log) {
+            let position_id = event.token_id.to_string();
+            let from = Hex(&event.from).to_string();
+            let to = Hex(&event.to).to_string();
+
+            if from != zero_address {
+                store.delete_prefix(
+                    log.ordinal() as i64,
+                    &keyer::owner_position(&from, &position_id),
+                );
             }
-        } else if let Some(event) = abi::positionmanager::events::Collect::match_and_decode(log) {
-            let token_id: String = event.token_id.to_string();
-            let mut position = if !enriched_positions.contains_key(&token_id) {
-                match all_positions_store
-                    .get_last(keyer::all_position(&token_id, &Collect.to_string()))
-                {
-                    None => {
-                        log::debug!("increase liquidity for id {} doesn't exist", token_id);
-                        continue;
-                    }
-                    Some(pos) => pos,
-                }
-            } else {
-                enriched_positions
-                    .remove(&event.token_id.to_string())
-                    .unwrap()
-            };
+            if to != zero_address {
+                store.set(
+                    log.ordinal(),
+                    keyer::owner_position(&to, &position_id),
+                    &position_id,
+                );
+                store.set(log.ordinal(), keyer::position_owner(&position_id), &to);
+            }
+        }
+    }
+}
 
-            if let Some(position_call_result) =
-                rpc::positions_call(&Hex(log.address()).to_string(), event.token_id)
-            {
-                position.fee_growth_inside_0_last_x_128 = Some(position_call_result.5.into());
-                position.fee_growth_inside_1_last_x_128 = Some(position_call_result.6.into());
-                enriched_positions.insert(token_id.clone(), position);
-                if !ordered_positions.contains(&String::from(token_id.clone())) {
-                    ordered_positions.push(String::from(token_id))
+#[substreams::handlers::store]
+pub fn store_account_position_counts(
+    position_owners_deltas: store::Deltas<DeltaString>,
+    position_changes_deltas: store::Deltas<DeltaBigDecimal>,
+    position_owners_store: StoreGetString,
+    position_changes_store: StoreGetBigDecimal,
+    store: StoreAddBigInt,
+) {
+    let is_active = |ordinal: u64, position_id: &String| -> bool {
+        position_changes_store
+            .get_at(ordinal, keyer::position_liquidity(position_id))
+            .unwrap_or(BigDecimal::zero())
+            .gt(&BigDecimal::zero())
+    };
+
+    // owner:{owner}:position:{id}:owned is created when the owner receives the
+    // position and deleted when it leaves
+    for delta in position_owners_deltas.deltas {
+        if !delta.key.starts_with("owner:") {
+            continue;
+        }
+        let value = match delta.operation {
+            store::Operation::Create => 1,
+            store::Operation::Delete => -1,
+            _ => continue,
+        };
+
+        let owner = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        let position_id = delta.key.as_str().split(":").nth(3).unwrap().to_string();
+        store.add(
+            delta.ordinal,
+            keyer::account_position_count(&owner, "positionCount"),
+            &BigInt::from(value),
+        );
+        if is_active(delta.ordinal, &position_id) {
+            store.add(
+                delta.ordinal,
+                keyer::account_position_count(&owner, "activePositionCount"),
+                &BigInt::from(value),
+            );
+        }
+    }
+
+    for delta in position_changes_deltas.deltas {
+        if delta.key.as_str().split(":").last().unwrap() != "liquidity" {
+            continue;
+        }
+        let was_active = delta.old_value.gt(&BigDecimal::zero());
+        let is_active = delta.new_value.gt(&BigDecimal::zero());
+        let value = match (was_active, is_active) {
+            (false, true) => 1,
+            (true, false) => -1,
+            _ => continue,
+        };
+
+        let position_id = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        let owner = match position_owners_store
+            .get_at(delta.ordinal, keyer::position_owner(&position_id))
+        {
+            None => continue,
+            Some(owner) => owner,
+        };
+        store.add(
+            delta.ordinal,
+            keyer::account_position_count(&owner, "activePositionCount"),
+            &BigInt::from(value),
+        );
+    }
+}
+
+// owner:{owner}:activePosition:{id}:active exists while the owner holds the position
+// and it has liquidity
+#[substreams::handlers::store]
+pub fn store_account_active_positions(
+    position_owners_deltas: store::Deltas<DeltaString>,
+    position_changes_deltas: store::Deltas<DeltaBigDecimal>,
+    position_owners_store: StoreGetString,
+    position_changes_store: StoreGetBigDecimal,
+    store: StoreSetString,
+) {
+    // (ordinal, owner, position id, active)
+    let mut changes: Vec<(u64, String, String, bool)> = vec![];
+    for delta in position_owners_deltas.deltas {
+        if !delta.key.starts_with("owner:") {
+            continue;
+        }
+        let owner = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        let position_id = delta.key.as_str().split(":").nth(3).unwrap().to_string();
+        match delta.operation {
+            store::Operation::Create => {
+                let is_active = position_changes_store
+                    .get_at(delta.ordinal, keyer::position_liquidity(&position_id))
+                    .unwrap_or(BigDecimal::zero())
+                    .gt(&BigDecimal::zero());
+                if is_active {
+                    changes.push((delta.ordinal, owner, position_id, true));
                 }
             }
-        } else if let Some(event) =
-            abi::positionmanager::events::DecreaseLiquidity::match_and_decode(log)
+            store::Operation::Delete => {
+                changes.push((delta.ordinal, owner, position_id, false));
+            }
+            _ => continue,
+        }
+    }
+
+    for delta in position_changes_deltas.deltas {
+        if delta.key.as_str().split(":").last().unwrap() != "liquidity" {
+            continue;
+        }
+        let was_active = delta.old_value.gt(&BigDecimal::zero());
+        let is_active = delta.new_value.gt(&BigDecimal::zero());
+        if was_active == is_active {
+            continue;
+        }
+
+        let position_id = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        let owner = match position_owners_store
+            .get_at(delta.ordinal, keyer::position_owner(&position_id))
         {
-            let token_id: String = event.token_id.to_string();
-            if !enriched_positions.contains_key(&token_id) {
-                match all_positions_store.get_last(keyer::all_position(
-                    &event.token_id.to_string(),
-                    &DecreaseLiquidity.to_string(),
-                )) {
-                    None => {
-                        log::debug!("increase liquidity for id {} doesn't exist", token_id);
-                        continue;
-                    }
-                    Some(pos) => {
-                        enriched_positions.insert(token_id.clone(), pos);
-                        if !ordered_positions.contains(&String::from(token_id.clone())) {
-                            ordered_positions.push(String::from(token_id))
-                        }
-                    }
-                }
+            None => continue,
+            Some(owner) => owner,
+        };
+        changes.push((delta.ordinal, owner, position_id, is_active));
+    }
+
+    changes.sort_by_key(|(ordinal, _, _, _)| *ordinal);
+    for (ordinal, owner, position_id, is_active) in changes {
+        let key = keyer::account_active_position(&owner, &position_id);
+        match is_active {
+            true => store.set(ordinal, key, &position_id),
+            false => store.delete_prefix(ordinal as i64, &key),
+        }
+    }
+}
+
+#[substreams::handlers::store]
+pub fn store_account_totals(
+    all_positions: Positions,
+    position_owners_store: StoreGetString,
+    eth_prices_store: StoreGetBigDecimal,
+    store: StoreAddBigDecimal,
+) {
+    let bundle_eth_price = eth_prices_store
+        .get_last(keyer::bundle_eth_price())
+        .unwrap_or(BigDecimal::zero());
+
+    for position in all_positions.positions {
+        let name = match position.convert_position_type() {
+            IncreaseLiquidity => "depositedUSD",
+            DecreaseLiquidity => "withdrawnUSD",
+            Collect => "collectedFeesUSD",
+            _ => continue,
+        };
+
+        let owner = match position_owners_store
+            .get_at(position.log_ordinal, keyer::position_owner(&position.id))
+        {
+            None => {
+                log::debug!("no owner found for position {}", position.id);
+                continue;
             }
-        } else if let Some(event) = abi::positionmanager::events::Transfer::match_and_decode(log) {
-            let token_id: String = event.token_id.to_string();
-            let mut position = if !enriched_positions.contains_key(&token_id) {
-                match all_positions_store
-                    .get_last(keyer::all_position(&token_id, &Transfer.to_string()))
-                {
-                    None => {
-                        log::debug!("increase liquidity for id {} doesn't exist", token_id);
-                        continue;
-                    }
-                    Some(pos) => pos,
+            Some(owner) => owner,
+        };
+
+        let token0_derived_eth_price = eth_prices_store
+            .get_last(keyer::token_eth_price(&position.token0))
+            .unwrap_or(BigDecimal::zero());
+        let token1_derived_eth_price = eth_prices_store
+            .get_last(keyer::token_eth_price(&position.token1))
+            .unwrap_or(BigDecimal::zero());
+
+        let amount_usd = utils::calculate_amount_usd(
+            &BigDecimal::from(position.amount0.unwrap()),
+            &BigDecimal::from(position.amount1.unwrap()),
+            &token0_derived_eth_price,
+            &token1_derived_eth_price,
+            &bundle_eth_price,
+        );
+
+        store.add(
+            position.log_ordinal,
+            keyer::account_total(&owner, name),
+            &amount_usd,
+        );
+    }
+}
+
+#[substreams::handlers::map]
+pub fn map_accounts(
+    account_position_counts_deltas: store::Deltas<DeltaBigInt>,
+    account_totals_deltas: store::Deltas<DeltaBigDecimal>,
+    account_position_counts_store: StoreGetBigInt,
+    account_totals_store: StoreGetBigDecimal,
+) -> Result<Accounts, Error> {
+    // both stores are keyed owner:{owner}:{name}
+    let mut touched_owners: Vec<(String, u64)> = vec![];
+    for delta in account_position_counts_deltas.deltas {
+        let owner = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        touched_owners.push((owner, delta.ordinal));
+    }
+    for delta in account_totals_deltas.deltas {
+        let owner = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        touched_owners.push((owner, delta.ordinal));
+    }
+
+    let mut ordered_owners: Vec<String> = vec![];
+    let mut ordinals: HashMap<String, u64> = HashMap::new();
+    for (owner, ordinal) in touched_owners {
+        match ordinals.get_mut(&owner) {
+            None => {
+                ordered_owners.push(owner.clone());
+                ordinals.insert(owner, ordinal);
+            }
+            Some(last_ordinal) => {
+                if ordinal > *last_ordinal {
+                    *last_ordinal = ordinal;
                 }
+            }
+        }
+    }
+
+    let mut accounts: Accounts = Accounts { accounts: vec![] };
+    for owner in ordered_owners {
+        let position_count = |name: &str| -> u64 {
+            account_position_counts_store
+                .get_last(keyer::account_position_count(&owner, name))
+                .unwrap_or(BigInt::zero())
+                .to_u64()
+        };
+        let account_total = |name: &str| -> BigDecimal {
+            account_totals_store
+                .get_last(keyer::account_total(&owner, name))
+                .unwrap_or(BigDecimal::zero())
+        };
+
+        let deposited_usd = account_total("depositedUSD");
+        let withdrawn_usd = account_total("withdrawnUSD");
+        accounts.accounts.push(Account {
+            id: owner.clone(),
+            position_count: position_count("positionCount"),
+            active_position_count: position_count("activePositionCount"),
+            net_deposited_usd: Some(deposited_usd.clone().sub(withdrawn_usd.clone()).into()),
+            deposited_usd: Some(deposited_usd.into()),
+            withdrawn_usd: Some(withdrawn_usd.into()),
+            collected_fees_usd: Some(account_total("collectedFeesUSD").into()),
+            log_ordinal: ordinals[&owner],
+        });
+    }
+
+    Ok(accounts)
+}
+
+#[substreams::handlers::map]
+pub fn map_raw_position_events(
+    block: Block,
+    pools_store: StoreGetProto<Pool>,
+) -> Result<RawPositionEvents, Error> {
+    let timestamp = block.timestamp_seconds();
+    let mut raw_position_events = vec![];
+
+    for log in block.logs() {
+        // liquidity owned by the NonfungiblePositionManager is tracked through the NFT positions
+        let (owner, tick_lower, tick_upper, liquidity, amount0, amount1, event_type) =
+            if let Some(mint) = abi::pool::events::Mint::match_and_decode(log) {
+                (
+                    mint.owner,
+                    mint.tick_lower,
+                    mint.tick_upper,
+                    mint.amount,
+                    mint.amount0,
+                    mint.amount1,
+                    RawPositionEventType::Mint,
+                )
+            } else if let Some(burn) = abi::pool::events::Burn::match_and_decode(log) {
+                (
+                    burn.owner,
+                    burn.tick_lower,
+                    burn.tick_upper,
+                    burn.amount,
+                    burn.amount0,
+                    burn.amount1,
+                    RawPositionEventType::Burn,
+                )
+            } else if let Some(collect) = abi::pool::events::Collect::match_and_decode(log) {
+                (
+                    collect.owner,
+                    collect.tick_lower,
+                    collect.tick_upper,
+                    BigInt::zero(),
+                    collect.amount0,
+                    collect.amount1,
+                    RawPositionEventType::Collect,
+                )
             } else {
-                enriched_positions.remove(&token_id).unwrap()
+                continue;
             };
 
-            position.owner = Hex(event.to.as_slice()).to_string();
-            enriched_positions.insert(token_id.clone(), position);
-            if !ordered_positions.contains(&String::from(token_id.clone())) {
-                ordered_positions.push(String::from(token_id))
-            }
+        if owner == NON_FUNGIBLE_POSITION_MANAGER {
+            continue;
         }
-    }
 
-    log::debug!("len of map: {}", enriched_positions.len());
-    for element in ordered_positions.iter() {
-        let pos = enriched_positions.remove(element);
-        if pos.is_some() {
-            positions.positions.push(pos.unwrap());
+        let pool = match pools_store.get_last(keyer::pool_key(&Hex(log.address()).to_string())) {
+            None => continue,
+            Some(pool) => pool,
+        };
+        if !pool.should_handle_mint_and_burn() {
+            continue;
         }
+
+        let token0 = pool.token0_ref();
+        let token1 = pool.token1_ref();
+        let owner = Hex(&owner).to_string();
+        raw_position_events.push(RawPositionEvent {
+            id: format!("{}#{}#{}#{}", pool.address, owner, tick_lower, tick_upper),
+            pool: pool.address.clone(),
+            owner,
+            token0: token0.address.clone(),
+            token1: token1.address.clone(),
+            tick_lower: Some(tick_lower.into()),
+            tick_upper: Some(tick_upper.into()),
+            transaction: Hex(&log.receipt.transaction.hash).to_string(),
+            liquidity: Some(liquidity.into()),
+            amount0: Some(amount0.to_decimal(token0.decimals).into()),
+            amount1: Some(amount1.to_decimal(token1.decimals).into()),
+            r#type: event_type as i32,
+            timestamp,
+            block_number: block.number,
+            log_ordinal: log.ordinal(),
+        });
     }
 
-    Ok(positions)
+    Ok(RawPositionEvents {
+        raw_position_events,
+    })
 }
 
 #[substreams::handlers::store]
-pub fn store_position_changes(all_positions: Positions, store: StoreAddBigDecimal) {
-    for position in all_positions.positions {
-        match position.convert_position_type() {
-            IncreaseLiquidity => {
-                store.add(
-                    position.log_ordinal,
-                    keyer::position_liquidity(&position.id),
-                    &BigDecimal::from(position.liquidity.unwrap()),
-                );
+pub fn store_raw_positions(
+    raw_position_events: RawPositionEvents,
+    store: StoreSetIfNotExistsProto<RawPositionEvent>,
+) {
+    // the first mint of a raw position holds its static data
+    for raw_position_event in raw_position_events.raw_position_events {
+        if raw_position_event.r#type != RawPositionEventType::Mint as i32 {
+            continue;
+        }
+        store.set_if_not_exists(
+            raw_position_event.log_ordinal,
+            keyer::raw_position(&raw_position_event.id),
+            &raw_position_event,
+        );
+    }
+}
+
+#[substreams::handlers::store]
+pub fn store_raw_position_changes(
+    raw_position_events: RawPositionEvents,
+    store: StoreAddBigDecimal,
+) {
+    for raw_position_event in raw_position_events.raw_position_events {
+        let id = &raw_position_event.id;
+        let ordinal = raw_position_event.log_ordinal;
+        let liquidity = BigDecimal::from(raw_position_event.liquidity.unwrap());
+        let amount0 = BigDecimal::from(raw_position_event.amount0.unwrap());
+        let amount1 = BigDecimal::from(raw_position_event.amount1.unwrap());
+
+        match raw_position_event.r#type {
+            t if t == RawPositionEventType::Mint as i32 => {
+                store.add(ordinal, keyer::raw_position_liquidity(id), &liquidity);
                 store.add(
-                    position.log_ordinal,
-                    keyer::position_deposited_token(&position.id, "Token0"),
-                    &BigDecimal::from(position.amount0.unwrap()),
+                    ordinal,
+                    keyer::raw_position_deposited_token(id, "Token0"),
+                    &amount0,
                 );
                 store.add(
-                    position.log_ordinal,
-                    keyer::position_deposited_token(&position.id, "Token1"),
-                    &BigDecimal::from(position.amount1.unwrap()),
+                    ordinal,
+                    keyer::raw_position_deposited_token(id, "Token1"),
+                    &amount1,
                 );
             }
-            DecreaseLiquidity => {
-                store.add(
-                    position.log_ordinal,
-                    keyer::position_liquidity(&position.id),
-                    &BigDecimal::from(position.liquidity.unwrap()).neg(),
-                );
+            t if t == RawPositionEventType::Burn as i32 => {
+                store.add(ordinal, keyer::raw_position_liquidity(id), &liquidity.neg());
                 store.add(
-                    position.log_ordinal,
-                    keyer::position_withdrawn_token(&position.id, "Token0"),
-                    &BigDecimal::from(position.amount0.unwrap()),
+                    ordinal,
+                    keyer::raw_position_withdrawn_token(id, "Token0"),
+                    &amount0,
                 );
                 store.add(
-                    position.log_ordinal,
-                    keyer::position_withdrawn_token(&position.id, "Token1"),
-                    &BigDecimal::from(position.amount1.unwrap()),
+                    ordinal,
+                    keyer::raw_position_withdrawn_token(id, "Token1"),
+                    &amount1,
                 );
             }
-            Collect => {
+            t if t == RawPositionEventType::Collect as i32 => {
                 store.add(
-                    position.log_ordinal,
-                    keyer::position_collected_fees_token(&position.id, "Token0"),
-                    &BigDecimal::from(position.amount0.unwrap()),
+                    ordinal,
+                    keyer::raw_position_collected_fees_token(id, "Token0"),
+                    &amount0,
                 );
                 store.add(
-                    position.log_ordinal,
-                    keyer::position_collected_fees_token(&position.id, "Token1"),
-                    &BigDecimal::from(position.amount1.unwrap()),
+                    ordinal,
+                    keyer::raw_position_collected_fees_token(id, "Token1"),
+                    &amount1,
                 );
             }
             _ => {}
@@ -1721,97 +5056,163 @@ pub fn store_position_changes(all_positions: Positions, store: StoreAddBigDecima
     }
 }
 
-//todo: maybe exact the some/none part in a macro and use it in the db?
-// as in the string is empty/0 in this use-case it would mean the same thing
-#[substreams::handlers::map]
-pub fn map_position_snapshots(
-    positions: Positions,
+#[substreams::handlers::store]
+pub fn store_liquidity_provider_positions(
+    position_changes_deltas: store::Deltas<DeltaBigDecimal>,
+    raw_position_changes_deltas: store::Deltas<DeltaBigDecimal>,
+    position_owners_deltas: store::Deltas<DeltaString>,
+    position_static_store: StoreGetProto<PositionStatic>,
+    position_owners_store: StoreGetString,
     position_changes_store: StoreGetBigDecimal,
-) -> Result<SnapshotPositions, Error> {
-    let mut snapshot_positions: SnapshotPositions = SnapshotPositions {
-        snapshot_positions: vec![],
+    store: StoreAddBigInt,
+) {
+    // counts, per owner, the positions with liquidity in a pool and on each tick boundary
+    let add_active_positions = |ordinal: u64,
+                                pool: &String,
+                                tick_lower: &String,
+                                tick_upper: &String,
+                                owner: &String,
+                                value: i32| {
+        let value = BigInt::from(value);
+        store.add(
+            ordinal,
+            keyer::pool_owner_active_positions(pool, owner),
+            &value,
+        );
+        for tick_idx in [tick_lower, tick_upper] {
+            store.add(
+                ordinal,
+                keyer::tick_owner_active_positions(&format!("{}#{}", pool, tick_idx), owner),
+                &value,
+            );
+        }
     };
-
-    for position in positions.positions {
-        let mut snapshot_position: SnapshotPosition = SnapshotPosition {
-            id: format!("{}#{}", position.id, position.block_number),
-            owner: position.owner,
-            pool: position.pool,
-            position: position.id.clone(),
-            block_number: position.block_number,
-            timestamp: position.timestamp,
-            transaction: position.transaction,
-            fee_growth_inside_0_last_x_128: position.fee_growth_inside_0_last_x_128,
-            fee_growth_inside_1_last_x_128: position.fee_growth_inside_1_last_x_128,
-            log_ordinal: position.log_ordinal,
-            ..Default::default()
-        };
-
-        match position_changes_store.get_last(keyer::position_liquidity(&position.id)) {
-            Some(liquidity) => snapshot_position.liquidity = Some(liquidity.into()),
-            _ => snapshot_position.liquidity = None,
+    let transition = |old_value: &BigDecimal, new_value: &BigDecimal| -> i32 {
+        let was_active = old_value.gt(&BigDecimal::zero());
+        let is_active = new_value.gt(&BigDecimal::zero());
+        match (was_active, is_active) {
+            (false, true) => 1,
+            (true, false) => -1,
+            _ => 0,
         }
+    };
 
-        match position_changes_store
-            .get_last(keyer::position_deposited_token(&position.id, "Token0"))
-        {
-            Some(deposited_token0) => {
-                snapshot_position.deposited_token0 = Some(deposited_token0.into());
-            }
-            _ => snapshot_position.deposited_token0 = Some(BigDecimal::zero().into()),
+    for delta in position_changes_deltas.deltas {
+        if delta.key.as_str().split(":").last().unwrap() != "liquidity" {
+            continue;
         }
-
-        match position_changes_store
-            .get_last(keyer::position_deposited_token(&position.id, "Token1"))
-        {
-            Some(deposited_token1) => {
-                snapshot_position.deposited_token1 = Some(deposited_token1.into());
-            }
-            _ => snapshot_position.deposited_token1 = Some(BigDecimal::zero().into()),
+        let value = transition(&delta.old_value, &delta.new_value);
+        if value == 0 {
+            continue;
         }
 
-        match position_changes_store
-            .get_last(keyer::position_withdrawn_token(&position.id, "Token0"))
+        let position_id = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        let position_static =
+            match position_static_store.get_last(keyer::position_static(&position_id)) {
+                None => continue,
+                Some(position_static) => position_static,
+            };
+        let owner = match position_owners_store
+            .get_at(delta.ordinal, keyer::position_owner(&position_id))
         {
-            Some(withdrawn_token0) => {
-                snapshot_position.withdrawn_token0 = Some(withdrawn_token0.into());
-            }
-            _ => snapshot_position.withdrawn_token0 = Some(BigDecimal::zero().into()),
-        }
+            None => continue,
+            Some(owner) => owner,
+        };
 
-        match position_changes_store
-            .get_last(keyer::position_withdrawn_token(&position.id, "Token1"))
-        {
-            Some(withdrawn_token1) => {
-                snapshot_position.withdrawn_token1 = Some(withdrawn_token1.into());
-            }
-            _ => snapshot_position.withdrawn_token1 = Some(BigDecimal::zero().into()),
+        add_active_positions(
+            delta.ordinal,
+            &position_static.pool,
+            &BigInt::from(position_static.tick_lower.unwrap()).to_string(),
+            &BigInt::from(position_static.tick_upper.unwrap()).to_string(),
+            &owner,
+            value,
+        );
+    }
+
+    // raw position ids are <pool>#<owner>#<tick lower>#<tick upper>
+    for delta in raw_position_changes_deltas.deltas {
+        if delta.key.as_str().split(":").last().unwrap() != "liquidity" {
+            continue;
+        }
+        let value = transition(&delta.old_value, &delta.new_value);
+        if value == 0 {
+            continue;
         }
 
-        match position_changes_store
-            .get_last(keyer::position_collected_fees_token(&position.id, "Token0"))
-        {
-            Some(collected_fees_token0) => {
-                snapshot_position.collected_fees_token0 = Some(collected_fees_token0.into());
-            }
-            _ => snapshot_position.collected_fees_token0 = Some(BigDecimal::zero().into()),
+        let raw_position_id = delta.key.as_str().split(":").nth(1).unwrap();
+        let parts: Vec<String> = raw_position_id.split("#").map(|p| p.to_string()).collect();
+        add_active_positions(
+            delta.ordinal,
+            &parts[0],
+            &parts[2],
+            &parts[3],
+            &parts[1],
+            value,
+        );
+    }
+
+    // a transferred position with liquidity moves to its new owner
+    for delta in position_owners_deltas.deltas {
+        if !delta.key.starts_with("position:") || delta.operation == store::Operation::Create {
+            continue;
         }
 
-        match position_changes_store
-            .get_last(keyer::position_collected_fees_token(&position.id, "Token1"))
-        {
-            Some(collected_fees_token1) => {
-                snapshot_position.collected_fees_token1 = Some(collected_fees_token1.into());
-            }
-            _ => snapshot_position.collected_fees_token1 = Some(BigDecimal::zero().into()),
+        let position_id = delta.key.as_str().split(":").nth(1).unwrap().to_string();
+        let liquidity = position_changes_store
+            .get_at(delta.ordinal, keyer::position_liquidity(&position_id))
+            .unwrap_or(BigDecimal::zero());
+        if !liquidity.gt(&BigDecimal::zero()) {
+            continue;
         }
+        let position_static =
+            match position_static_store.get_last(keyer::position_static(&position_id)) {
+                None => continue,
+                Some(position_static) => position_static,
+            };
 
-        snapshot_positions
-            .snapshot_positions
-            .push(snapshot_position);
+        let tick_lower = BigInt::from(position_static.tick_lower.unwrap()).to_string();
+        let tick_upper = BigInt::from(position_static.tick_upper.unwrap()).to_string();
+        add_active_positions(
+            delta.ordinal,
+            &position_static.pool,
+            &tick_lower,
+            &tick_upper,
+            &delta.old_value,
+            -1,
+        );
+        add_active_positions(
+            delta.ordinal,
+            &position_static.pool,
+            &tick_lower,
+            &tick_upper,
+            &delta.new_value,
+            1,
+        );
     }
+}
 
-    Ok(snapshot_positions)
+#[substreams::handlers::store]
+pub fn store_liquidity_provider_counts(
+    liquidity_provider_positions_deltas: store::Deltas<DeltaBigInt>,
+    store: StoreAddBigInt,
+) {
+    // an owner becomes a liquidity provider with its first active position
+    for delta in liquidity_provider_positions_deltas.deltas {
+        let was_provider = delta.old_value.gt(&BigInt::zero());
+        let is_provider = delta.new_value.gt(&BigInt::zero());
+        if was_provider == is_provider {
+            continue;
+        }
+
+        let entity = delta.key.as_str().split(":").nth(0).unwrap();
+        let id = delta.key.as_str().split(":").nth(1).unwrap();
+        let value = if is_provider { 1 } else { -1 };
+        store.add(
+            delta.ordinal,
+            keyer::liquidity_provider_count(entity, id),
+            &BigInt::from(value),
+        );
+    }
 }
 
 #[substreams::handlers::map]
@@ -1849,6 +5250,7 @@ pub fn map_flashes(block: Block, pool_store: StoreGetProto<Pool>) -> Result<Flas
 pub fn map_bundle_entities(
     block: Block,
     derived_eth_prices_deltas: store::Deltas<DeltaBigDecimal>,
+    bundle_prices_deltas: store::Deltas<DeltaProto<BundlePrice>>,
 ) -> Result<EntityChanges, Error> {
     let mut entity_changes: EntityChanges = Default::default();
 
@@ -1860,6 +5262,7 @@ pub fn map_bundle_entities(
         &mut entity_changes,
         derived_eth_prices_deltas,
     );
+    db::bundle_price_source_bundle_entity_change(&mut entity_changes, bundle_prices_deltas);
 
     Ok(entity_changes)
 }
@@ -1898,6 +5301,12 @@ pub fn map_pool_entities(
     price_deltas: store::Deltas<DeltaBigDecimal>,
     tx_count_deltas: store::Deltas<DeltaBigInt>,
     swaps_volume_deltas: store::Deltas<DeltaBigDecimal>,
+    pool_balances_deltas: store::Deltas<DeltaBigDecimal>,
+    pool_balance_reconciliations_deltas: store::Deltas<DeltaBigDecimal>,
+    liquidity_provider_counts_deltas: store::Deltas<DeltaBigInt>,
+    pool_arbitrage_volumes_deltas: store::Deltas<DeltaBigDecimal>,
+    pool_tick_cumulatives_deltas: store::Deltas<DeltaBigInt>,
+    pool_observation_states_deltas: store::Deltas<DeltaBigInt>,
 ) -> Result<EntityChanges, Error> {
     let mut entity_changes: EntityChanges = Default::default();
     db::pools_created_pool_entity_change(pools_created, &mut entity_changes);
@@ -1915,26 +5324,44 @@ pub fn map_pool_entities(
     db::price_pool_entity_change(&mut entity_changes, price_deltas);
     db::tx_count_pool_entity_change(&mut entity_changes, tx_count_deltas);
     db::swap_volume_pool_entity_change(&mut entity_changes, swaps_volume_deltas);
+    db::pool_balances_pool_entity_change(&mut entity_changes, pool_balances_deltas);
+    db::pool_balance_reconciliations_pool_entity_change(
+        &mut entity_changes,
+        pool_balance_reconciliations_deltas,
+    );
+    db::liquidity_provider_count_entity_change(
+        &mut entity_changes,
+        liquidity_provider_counts_deltas,
+        "pool",
+        "Pool",
+    );
+    db::arbitrage_volume_pool_entity_change(&mut entity_changes, pool_arbitrage_volumes_deltas);
+    db::tick_cumulative_pool_entity_change(&mut entity_changes, pool_tick_cumulatives_deltas);
+    db::observation_state_pool_entity_change(&mut entity_changes, pool_observation_states_deltas);
     Ok(entity_changes)
 }
 
-//todo: when a pool is created, we also save the token
-// (id, name, symbol, decimals and total supply)
-// issue here is what if we have multiple pools with t1-t2, t1-t3, t1-t4, etc.
-// we will have t1 generate multiple entity changes for nothing since it has
-// already been emitted -- subgraph doesn't solve this either (Optimization)
 #[substreams::handlers::map]
 pub fn map_tokens_entities(
-    pools_created: Pools,
+    tokens_deltas: store::Deltas<DeltaProto<Erc20Token>>,
     swaps_volume_deltas: store::Deltas<DeltaBigDecimal>,
     tx_count_deltas: store::Deltas<DeltaBigInt>,
     total_value_locked_by_deltas: store::Deltas<DeltaBigDecimal>,
     total_value_locked_deltas: store::Deltas<DeltaBigDecimal>,
+    totals_deltas: store::Deltas<DeltaBigDecimal>,
     derived_eth_prices_deltas: store::Deltas<DeltaBigDecimal>,
-    tokens_whitelist_pools: store::Deltas<DeltaArray<String>>,
+    token_pools_deltas: store::Deltas<DeltaArray<String>>,
+    token_total_supply_deltas: store::Deltas<DeltaBigInt>,
+    token_transfer_behaviors_deltas: store::Deltas<DeltaProto<TokenTransferBehavior>>,
+    token_usd_prices_deltas: store::Deltas<DeltaBigDecimal>,
+    mut derived_eth_prices: DerivedEthPrices,
+    price_anomalies: PriceAnomalies,
 ) -> Result<EntityChanges, Error> {
     let mut entity_changes: EntityChanges = Default::default();
-    db::tokens_created_token_entity_change(&mut entity_changes, pools_created);
+    derived_eth_prices
+        .derived_eth_prices
+        .retain(|derived_eth_price| !price_anomalies.rejects(derived_eth_price));
+    db::tokens_created_token_entity_change(&mut entity_changes, tokens_deltas);
     db::swap_volume_token_entity_change(&mut entity_changes, swaps_volume_deltas);
     db::tx_count_token_entity_change(&mut entity_changes, tx_count_deltas);
     db::total_value_locked_by_token_token_entity_change(
@@ -1942,8 +5369,13 @@ pub fn map_tokens_entities(
         total_value_locked_by_deltas,
     );
     db::total_value_locked_usd_token_entity_change(&mut entity_changes, total_value_locked_deltas);
+    db::total_value_locked_usd_token_entity_change(&mut entity_changes, totals_deltas);
     db::derived_eth_prices_token_entity_change(&mut entity_changes, derived_eth_prices_deltas);
-    db::whitelist_token_entity_change(&mut entity_changes, tokens_whitelist_pools);
+    db::token_usd_price_token_entity_change(&mut entity_changes, token_usd_prices_deltas);
+    db::derived_eth_path_token_entity_change(&mut entity_changes, derived_eth_prices);
+    db::token_pools_token_entity_change(&mut entity_changes, token_pools_deltas);
+    db::total_supply_token_entity_change(&mut entity_changes, token_total_supply_deltas);
+    db::transfer_behavior_token_entity_change(&mut entity_changes, token_transfer_behaviors_deltas);
 
     Ok(entity_changes)
 }
@@ -1952,10 +5384,17 @@ pub fn map_tokens_entities(
 pub fn map_tick_entities(
     ticks_deltas: store::Deltas<DeltaProto<Tick>>,
     ticks_liquidities_deltas: store::Deltas<DeltaBigInt>,
+    liquidity_provider_counts_deltas: store::Deltas<DeltaBigInt>,
 ) -> Result<EntityChanges, Error> {
     let mut entity_changes: EntityChanges = Default::default();
     db::create_or_update_ticks_entity_change(&mut entity_changes, ticks_deltas);
     db::ticks_liquidities_tick_entity_change(&mut entity_changes, ticks_liquidities_deltas);
+    db::liquidity_provider_count_entity_change(
+        &mut entity_changes,
+        liquidity_provider_counts_deltas,
+        "tick",
+        "Tick",
+    );
     Ok(entity_changes)
 }
 
@@ -1963,19 +5402,93 @@ pub fn map_tick_entities(
 pub fn map_position_entities(
     positions: Positions,
     positions_changes_deltas: store::Deltas<DeltaBigDecimal>,
+    position_uncollected_fees: PositionUncollectedFees,
+    position_range_events: PositionRangeEvents,
+    position_time_in_range_deltas: store::Deltas<DeltaBigInt>,
 ) -> Result<EntityChanges, Error> {
     let mut entity_changes: EntityChanges = Default::default();
     db::position_create_entity_change(positions, &mut entity_changes);
     db::positions_changes_entity_change(&mut entity_changes, positions_changes_deltas);
+    db::uncollected_fees_position_entity_change(position_uncollected_fees, &mut entity_changes);
+    db::range_events_position_entity_change(position_range_events, &mut entity_changes);
+    db::time_in_range_position_entity_change(&mut entity_changes, position_time_in_range_deltas);
     Ok(entity_changes)
 }
 
 #[substreams::handlers::map]
 pub fn map_position_snapshot_entities(
     snapshot_positions: SnapshotPositions,
+    position_performances: PositionPerformances,
 ) -> Result<EntityChanges, Error> {
     let mut entity_changes: EntityChanges = Default::default();
     db::snapshot_position_entity_change(snapshot_positions, &mut entity_changes);
+    db::position_performance_entity_change(position_performances, &mut entity_changes);
+    Ok(entity_changes)
+}
+
+#[substreams::handlers::map]
+pub fn map_account_entities(
+    accounts: Accounts,
+    account_active_positions_deltas: store::Deltas<DeltaString>,
+) -> Result<EntityChanges, Error> {
+    let mut entity_changes: EntityChanges = Default::default();
+    db::account_entity_change(accounts, &mut entity_changes);
+    db::account_position_entity_change(account_active_positions_deltas, &mut entity_changes);
+    Ok(entity_changes)
+}
+
+#[substreams::handlers::map]
+pub fn map_raw_position_entities(
+    raw_positions_deltas: store::Deltas<DeltaProto<RawPositionEvent>>,
+    raw_position_changes_deltas: store::Deltas<DeltaBigDecimal>,
+) -> Result<EntityChanges, Error> {
+    let mut entity_changes: EntityChanges = Default::default();
+    db::raw_position_create_entity_change(raw_positions_deltas, &mut entity_changes);
+    db::raw_position_changes_entity_change(&mut entity_changes, raw_position_changes_deltas);
+    Ok(entity_changes)
+}
+
+#[substreams::handlers::map]
+pub fn map_sandwich_entities(sandwiches: Sandwiches) -> Result<EntityChanges, Error> {
+    let mut entity_changes: EntityChanges = Default::default();
+    db::sandwich_entity_change(sandwiches, &mut entity_changes);
+    Ok(entity_changes)
+}
+
+#[substreams::handlers::map]
+pub fn map_jit_liquidity_entities(
+    jit_liquidity_events: JitLiquidityEvents,
+) -> Result<EntityChanges, Error> {
+    let mut entity_changes: EntityChanges = Default::default();
+    db::jit_liquidity_event_entity_change(jit_liquidity_events, &mut entity_changes);
+    Ok(entity_changes)
+}
+
+#[substreams::handlers::map]
+pub fn map_arbitrage_entities(arbitrages: Arbitrages) -> Result<EntityChanges, Error> {
+    let mut entity_changes: EntityChanges = Default::default();
+    db::arbitrage_entity_change(arbitrages, &mut entity_changes);
+    Ok(entity_changes)
+}
+
+#[substreams::handlers::map]
+pub fn map_pool_candle_entities(pool_candles: PoolCandles) -> Result<EntityChanges, Error> {
+    let mut entity_changes: EntityChanges = Default::default();
+    db::pool_candle_entity_change(pool_candles, &mut entity_changes);
+    Ok(entity_changes)
+}
+
+#[substreams::handlers::map]
+pub fn map_pool_twap_entities(pool_twaps: PoolTwaps) -> Result<EntityChanges, Error> {
+    let mut entity_changes: EntityChanges = Default::default();
+    db::pool_twap_entity_change(pool_twaps, &mut entity_changes);
+    Ok(entity_changes)
+}
+
+#[substreams::handlers::map]
+pub fn map_price_anomaly_entities(price_anomalies: PriceAnomalies) -> Result<EntityChanges, Error> {
+    let mut entity_changes: EntityChanges = Default::default();
+    db::price_anomaly_entity_change(price_anomalies, &mut entity_changes);
     Ok(entity_changes)
 }
 
@@ -1988,15 +5501,19 @@ pub fn map_transaction_entities(transactions: Transactions) -> Result<EntityChan
 
 #[substreams::handlers::map]
 pub fn map_swaps_mints_burns_entities(
+    tracking_policy: uniswap::TrackingPolicy,
     events: Events,
     tx_count_store: StoreGetBigInt,
     store_eth_prices: StoreGetBigDecimal,
+    native_total_value_locked_store: StoreGetBigDecimal,
 ) -> Result<EntityChanges, Error> {
     let mut entity_changes: EntityChanges = Default::default();
     db::swaps_mints_burns_created_entity_change(
         events,
+        tracking_policy.into(),
         tx_count_store,
         store_eth_prices,
+        native_total_value_locked_store,
         &mut entity_changes,
     );
     Ok(entity_changes)
@@ -2022,6 +5539,17 @@ pub fn map_uniswap_day_data_entities(
     Ok(entity_changes)
 }
 
+#[substreams::handlers::map]
+pub fn map_interface_day_data_entities(
+    swap_count_deltas: store::Deltas<DeltaBigInt>,
+    volume_deltas: store::Deltas<DeltaBigDecimal>,
+) -> Result<EntityChanges, Error> {
+    let mut entity_changes: EntityChanges = Default::default();
+    db::interface_day_data_swap_count_entity_change(&mut entity_changes, swap_count_deltas);
+    db::interface_day_data_volume_entity_change(&mut entity_changes, volume_deltas);
+    Ok(entity_changes)
+}
+
 //todo: check if we want to check the block ordinal here and sort by the ordinal
 // or simply stream out all the entity changes
 #[substreams::handlers::map]
@@ -2034,8 +5562,17 @@ pub fn graph_out(
     tick_entities: EntityChanges,
     position_entities: EntityChanges,
     position_snapshot_entities: EntityChanges,
+    account_entities: EntityChanges,
+    raw_position_entities: EntityChanges,
     flash_entities: EntityChanges,
     swaps_mints_burns_entities: EntityChanges,
+    interface_day_data_entities: EntityChanges,
+    sandwich_entities: EntityChanges,
+    jit_liquidity_entities: EntityChanges,
+    arbitrage_entities: EntityChanges,
+    pool_candle_entities: EntityChanges,
+    pool_twap_entities: EntityChanges,
+    price_anomaly_entities: EntityChanges,
 ) -> Result<EntityChanges, Error> {
     Ok(EntityChanges {
         entity_changes: [
@@ -2047,8 +5584,17 @@ pub fn graph_out(
             tick_entities.entity_changes,
             position_entities.entity_changes,
             position_snapshot_entities.entity_changes,
+            account_entities.entity_changes,
+            raw_position_entities.entity_changes,
             flash_entities.entity_changes,
             swaps_mints_burns_entities.entity_changes,
+            interface_day_data_entities.entity_changes,
+            sandwich_entities.entity_changes,
+            jit_liquidity_entities.entity_changes,
+            arbitrage_entities.entity_changes,
+            pool_candle_entities.entity_changes,
+            pool_twap_entities.entity_changes,
+            price_anomaly_entities.entity_changes,
         ]
         .concat(),
     })
@@ -1,6 +1,7 @@
 use crate::BigInt;
 use std::borrow::Borrow;
-use std::ops::{Add, Div, Mul};
+use std::ops::{Add, Div, Mul, Sub};
+use std::str::FromStr;
 use substreams::scalar::BigDecimal;
 
 pub fn big_decimal_exponated(amount: BigDecimal, exponent: BigInt) -> BigDecimal {
@@ -51,3 +52,146 @@ pub fn exponent_to_big_decimal(decimals: &BigInt) -> BigDecimal {
 
     return result;
 }
+
+// mirrors Tick.getFeeGrowthInside, values wrap around like the uint256 of the contract
+pub fn fee_growth_inside_x128(
+    tick_current: &BigInt,
+    tick_lower: &BigInt,
+    tick_upper: &BigInt,
+    fee_growth_global_x128: &BigInt,
+    lower_fee_growth_outside_x128: &BigInt,
+    upper_fee_growth_outside_x128: &BigInt,
+) -> BigInt {
+    let fee_growth_below_x128 = if tick_current.lt(tick_lower) {
+        fee_growth_global_x128
+            .clone()
+            .sub(lower_fee_growth_outside_x128.clone())
+    } else {
+        lower_fee_growth_outside_x128.clone()
+    };
+
+    let fee_growth_above_x128 = if tick_current.lt(tick_upper) {
+        upper_fee_growth_outside_x128.clone()
+    } else {
+        fee_growth_global_x128
+            .clone()
+            .sub(upper_fee_growth_outside_x128.clone())
+    };
+
+    return wrap_uint256(
+        fee_growth_global_x128
+            .clone()
+            .sub(fee_growth_below_x128)
+            .sub(fee_growth_above_x128),
+    );
+}
+
+pub fn wrap_uint256(value: BigInt) -> BigInt {
+    let two_pow_256 = BigInt::from_str(
+        "115792089237316195423570985008687907853269984665640564039457584007913129639936",
+    )
+    .unwrap();
+
+    let mut wrapped = value;
+    while wrapped.lt(&BigInt::zero()) {
+        wrapped = wrapped.add(two_pow_256.clone());
+    }
+    while !wrapped.lt(&two_pow_256) {
+        wrapped = wrapped.sub(two_pow_256.clone());
+    }
+
+    return wrapped;
+}
+
+// liquidity * (fee_growth_inside_x128 - fee_growth_inside_last_x128) / 2^128, as PositionManager does
+pub fn fees_earned(
+    liquidity: &BigDecimal,
+    fee_growth_inside_x128: &BigInt,
+    fee_growth_inside_last_x128: &BigInt,
+    decimals: u64,
+) -> BigDecimal {
+    let two_pow_128 = BigDecimal::from_str("340282366920938463463374607431768211456").unwrap();
+    let fee_growth_delta_x128 = wrap_uint256(
+        fee_growth_inside_x128
+            .clone()
+            .sub(fee_growth_inside_last_x128.clone()),
+    );
+
+    return liquidity
+        .clone()
+        .mul(fee_growth_delta_x128.to_decimal(0))
+        .div(two_pow_128)
+        .div(exponent_to_big_decimal(&BigInt::from(decimals)));
+}
+
+// exponentiation by squaring, ticks are too far apart for big_decimal_exponated
+pub fn big_decimal_pow(base: &BigDecimal, exponent: i64) -> BigDecimal {
+    let mut result = BigDecimal::one();
+    let mut factor = base.clone();
+    let mut remaining = exponent.unsigned_abs();
+    while remaining > 0 {
+        if remaining & 1 == 1 {
+            result = result.mul(factor.clone()).with_prec(100);
+        }
+        factor = factor.clone().mul(factor).with_prec(100);
+        remaining >>= 1;
+    }
+
+    if exponent < 0 {
+        return safe_div(&BigDecimal::one(), &result);
+    }
+    return result;
+}
+
+// sqrt(1.0001)^tick
+pub fn sqrt_price_at_tick(tick: &BigInt) -> BigDecimal {
+    let sqrt_1_0001 =
+        BigDecimal::from_str("1.00004999875006249609402341699379869721549895065686478843687")
+            .unwrap();
+    return big_decimal_pow(&sqrt_1_0001, tick.to_string().parse::<i64>().unwrap());
+}
+
+pub fn sqrt_price_from_x96(sqrt_price_x96: &BigInt) -> BigDecimal {
+    let two_pow_96 = BigDecimal::from_str("79228162514264337593543950336").unwrap();
+    return sqrt_price_x96.to_decimal(0).div(two_pow_96);
+}
+
+// raw token amounts backing the liquidity of a range at the given sqrt price, as in LiquidityAmounts
+pub fn amounts_for_liquidity(
+    liquidity: &BigDecimal,
+    sqrt_price: &BigDecimal,
+    sqrt_price_lower: &BigDecimal,
+    sqrt_price_upper: &BigDecimal,
+) -> (BigDecimal, BigDecimal) {
+    if !sqrt_price.gt(sqrt_price_lower) {
+        let amount0 = liquidity
+            .clone()
+            .mul(sqrt_price_upper.clone().sub(sqrt_price_lower.clone()));
+        return (
+            safe_div(
+                &amount0,
+                &sqrt_price_lower.clone().mul(sqrt_price_upper.clone()),
+            ),
+            BigDecimal::zero(),
+        );
+    }
+
+    if !sqrt_price.lt(sqrt_price_upper) {
+        return (
+            BigDecimal::zero(),
+            liquidity
+                .clone()
+                .mul(sqrt_price_upper.clone().sub(sqrt_price_lower.clone())),
+        );
+    }
+
+    let amount0 = liquidity
+        .clone()
+        .mul(sqrt_price_upper.clone().sub(sqrt_price.clone()));
+    return (
+        safe_div(&amount0, &sqrt_price.clone().mul(sqrt_price_upper.clone())),
+        liquidity
+            .clone()
+            .mul(sqrt_price.clone().sub(sqrt_price_lower.clone())),
+    );
+}
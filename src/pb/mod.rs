@@ -1,10 +1,15 @@
 use crate::pb::position_event::PositionEventType;
+use crate::uniswap::event::Type::Swap as SwapEvent;
 use crate::uniswap::position::PositionType;
+use crate::uniswap::position_range_event::Type as RangeEventType;
+use crate::uniswap::price_anomaly::Reason as PriceAnomalyReason;
+use crate::uniswap::token_transfer_behavior::Type as TransferBehaviorType;
 use crate::uniswap::{BigDecimal as PbBigDecimal, BigInt as PbBigInt};
 use crate::PositionType::Unset;
 use crate::{
-    BigInt, Collect, DecreaseLiquidity, Erc20Token, IncreaseLiquidity, Pool, PoolSqrtPrice,
-    Position, Transfer,
+    BigInt, Collect, DecreaseLiquidity, DerivedEthPrice, Erc20Token, Event, IncreaseLiquidity,
+    Pool, PoolSqrtPrice, Position, PositionRangeEvent, PriceAnomalies, PriceAnomaly,
+    TokenTransferBehavior, Transfer,
 };
 use ethabi::Uint;
 use std::str::FromStr;
@@ -191,6 +196,85 @@ impl Position {
     }
 }
 
+impl TokenTransferBehavior {
+    pub fn type_name(&self) -> String {
+        return match self.r#type {
+            t if t == TransferBehaviorType::Standard as i32 => "STANDARD".to_string(),
+            t if t == TransferBehaviorType::FeeOnTransfer as i32 => "FEE_ON_TRANSFER".to_string(),
+            _ => panic!("unhandled transfer behavior: {}", self.r#type),
+        };
+    }
+}
+
+impl PositionRangeEvent {
+    pub fn is_entered(&self) -> bool {
+        self.r#type == RangeEventType::Entered as i32
+    }
+
+    pub fn type_name(&self) -> String {
+        return match self.r#type {
+            t if t == RangeEventType::Entered as i32 => "ENTERED".to_string(),
+            t if t == RangeEventType::Exited as i32 => "EXITED".to_string(),
+            _ => panic!("unhandled range event: {}", self.r#type),
+        };
+    }
+}
+
+impl PriceAnomaly {
+    pub fn reason_name(&self) -> String {
+        return match self.reason {
+            r if r == PriceAnomalyReason::LowLiquidity as i32 => "LOW_LIQUIDITY".to_string(),
+            r if r == PriceAnomalyReason::Deviation as i32 => "DEVIATION".to_string(),
+            _ => panic!("unhandled price anomaly reason: {}", self.reason),
+        };
+    }
+}
+
+impl PriceAnomalies {
+    pub fn rejects(&self, derived_eth_price: &DerivedEthPrice) -> bool {
+        self.price_anomalies.iter().any(|price_anomaly| {
+            price_anomaly.ordinal == derived_eth_price.ordinal
+                && price_anomaly.token_address == derived_eth_price.token_address
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapLeg {
+    pub token_in: String,
+    pub amount_in: BigDecimal,
+    pub token_out: String,
+    pub amount_out: BigDecimal,
+}
+
+impl Event {
+    // the pool receives a positive amount of the input token and sends a negative amount of the
+    // output token
+    pub fn swap_leg(&self) -> Option<SwapLeg> {
+        let swap = match self.r#type.as_ref() {
+            Some(SwapEvent(swap)) => swap,
+            _ => return None,
+        };
+        let amount0: BigDecimal = swap.amount_0.clone().unwrap().into();
+        let amount1: BigDecimal = swap.amount_1.clone().unwrap().into();
+
+        if amount0.gt(&BigDecimal::zero()) {
+            return Some(SwapLeg {
+                token_in: self.token0.clone(),
+                amount_in: amount0,
+                token_out: self.token1.clone(),
+                amount_out: amount1.neg(),
+            });
+        }
+        Some(SwapLeg {
+            token_in: self.token1.clone(),
+            amount_in: amount1,
+            token_out: self.token0.clone(),
+            amount_out: amount0.neg(),
+        })
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct PositionEvent {
     pub event: PositionEventType,
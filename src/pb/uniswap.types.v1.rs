@@ -30,6 +30,22 @@ pub struct Erc20Token {
     pub whitelist_pools: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TokenSupplyChanges {
+    #[prost(message, repeated, tag="1")]
+    pub token_supply_changes: ::prost::alloc::vec::Vec<TokenSupplyChange>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TokenSupplyChange {
+    #[prost(string, tag="1")]
+    pub token_address: ::prost::alloc::string::String,
+    /// positive when minted, negative when burned
+    #[prost(message, optional, tag="2")]
+    pub amount: ::core::option::Option<BigInt>,
+    /// internals
+    #[prost(uint64, tag="30")]
+    pub log_ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Liquidity {
     #[prost(string, tag="1")]
     pub pool_address: ::prost::alloc::string::String,
@@ -66,6 +82,61 @@ pub struct Pool {
     pub ignore_pool: bool,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoolTokenTransfers {
+    #[prost(message, repeated, tag="1")]
+    pub pool_token_transfers: ::prost::alloc::vec::Vec<PoolTokenTransfer>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoolTokenTransfer {
+    #[prost(string, tag="1")]
+    pub pool_address: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub token_address: ::prost::alloc::string::String,
+    /// positive when the pool receives the tokens, negative when it sends them
+    #[prost(message, optional, tag="3")]
+    pub amount: ::core::option::Option<BigInt>,
+    #[prost(string, tag="4")]
+    pub transaction_id: ::prost::alloc::string::String,
+    /// internals
+    #[prost(uint64, tag="30")]
+    pub log_ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TokenTransferBehaviors {
+    #[prost(message, repeated, tag="1")]
+    pub token_transfer_behaviors: ::prost::alloc::vec::Vec<TokenTransferBehavior>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TokenTransferBehavior {
+    #[prost(string, tag="1")]
+    pub token_address: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pool_address: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub transaction_id: ::prost::alloc::string::String,
+    /// net amount moved in or out of the pool according to the Transfer logs
+    #[prost(message, optional, tag="4")]
+    pub transfer_amount: ::core::option::Option<BigInt>,
+    /// net amount moved in or out of the pool according to the pool events
+    #[prost(message, optional, tag="5")]
+    pub event_amount: ::core::option::Option<BigInt>,
+    #[prost(enumeration="token_transfer_behavior::Type", tag="6")]
+    pub r#type: i32,
+    /// internals
+    #[prost(uint64, tag="30")]
+    pub log_ordinal: u64,
+}
+/// Nested message and enum types in `TokenTransferBehavior`.
+pub mod token_transfer_behavior {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+    #[repr(i32)]
+    pub enum Type {
+        Unset = 0,
+        Standard = 1,
+        FeeOnTransfer = 2,
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct PoolSqrtPrices {
     #[prost(message, repeated, tag="1")]
     pub pool_sqrt_prices: ::prost::alloc::vec::Vec<PoolSqrtPrice>,
@@ -316,6 +387,33 @@ pub struct Transaction {
     pub log_ordinal: u64,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PositionStatic {
+    /// NFT token id
+    #[prost(string, tag="1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pool: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub token0: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub token1: ::prost::alloc::string::String,
+    #[prost(message, optional, tag="5")]
+    pub fee_tier: ::core::option::Option<BigInt>,
+    #[prost(message, optional, tag="6")]
+    pub tick_lower: ::core::option::Option<BigInt>,
+    #[prost(message, optional, tag="7")]
+    pub tick_upper: ::core::option::Option<BigInt>,
+    #[prost(string, tag="8")]
+    pub transaction: ::prost::alloc::string::String,
+    #[prost(uint64, tag="9")]
+    pub timestamp: u64,
+    #[prost(uint64, tag="10")]
+    pub block_number: u64,
+    /// internals
+    #[prost(uint64, tag="30")]
+    pub log_ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Positions {
     #[prost(message, repeated, tag="1")]
     pub positions: ::prost::alloc::vec::Vec<Position>,
@@ -371,6 +469,63 @@ pub mod position {
     }
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PositionUncollectedFees {
+    #[prost(message, repeated, tag="1")]
+    pub position_uncollected_fees: ::prost::alloc::vec::Vec<PositionUncollectedFee>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PositionUncollectedFee {
+    #[prost(string, tag="1")]
+    pub position_id: ::prost::alloc::string::String,
+    /// what collect would return now: owed amounts plus fees earned since the last position update
+    #[prost(message, optional, tag="2")]
+    pub uncollected_fees_token0: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="3")]
+    pub uncollected_fees_token1: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="4")]
+    pub uncollected_fees_usd: ::core::option::Option<BigDecimal>,
+    /// internals
+    #[prost(uint64, tag="30")]
+    pub log_ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PositionRangeEvents {
+    #[prost(message, repeated, tag="1")]
+    pub position_range_events: ::prost::alloc::vec::Vec<PositionRangeEvent>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PositionRangeEvent {
+    /// <NFT token id>#<block number>#<log ordinal>
+    #[prost(string, tag="1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub position_id: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub pool: ::prost::alloc::string::String,
+    #[prost(enumeration="position_range_event::Type", tag="4")]
+    pub r#type: i32,
+    /// pool tick after the move
+    #[prost(message, optional, tag="5")]
+    pub tick: ::core::option::Option<BigInt>,
+    #[prost(uint64, tag="6")]
+    pub block_number: u64,
+    #[prost(uint64, tag="7")]
+    pub timestamp: u64,
+    /// internals
+    #[prost(uint64, tag="30")]
+    pub log_ordinal: u64,
+}
+/// Nested message and enum types in `PositionRangeEvent`.
+pub mod position_range_event {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+    #[repr(i32)]
+    pub enum Type {
+        Unset = 0,
+        Entered = 1,
+        Exited = 2,
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SnapshotPositions {
     #[prost(message, repeated, tag="1")]
     pub snapshot_positions: ::prost::alloc::vec::Vec<SnapshotPosition>,
@@ -413,6 +568,616 @@ pub struct SnapshotPosition {
     #[prost(uint64, tag="17")]
     pub log_ordinal: u64,
 }
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Accounts {
+    #[prost(message, repeated, tag="1")]
+    pub accounts: ::prost::alloc::vec::Vec<Account>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Account {
+    /// owner address
+    #[prost(string, tag="1")]
+    pub id: ::prost::alloc::string::String,
+    /// positions currently owned
+    #[prost(uint64, tag="2")]
+    pub position_count: u64,
+    /// owned positions with liquidity
+    #[prost(uint64, tag="3")]
+    pub active_position_count: u64,
+    /// deposited minus withdrawn USD, at the time of each deposit and withdrawal
+    #[prost(message, optional, tag="4")]
+    pub net_deposited_usd: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="5")]
+    pub deposited_usd: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="6")]
+    pub withdrawn_usd: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="7")]
+    pub collected_fees_usd: ::core::option::Option<BigDecimal>,
+    /// internals
+    #[prost(uint64, tag="30")]
+    pub log_ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PositionPerformances {
+    #[prost(message, repeated, tag="1")]
+    pub position_performances: ::prost::alloc::vec::Vec<PositionPerformance>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PositionPerformance {
+    /// NFT token id
+    #[prost(string, tag="1")]
+    pub id: ::prost::alloc::string::String,
+    /// snapshot the performance was computed for
+    #[prost(string, tag="2")]
+    pub snapshot: ::prost::alloc::string::String,
+    #[prost(uint64, tag="3")]
+    pub block_number: u64,
+    #[prost(uint64, tag="4")]
+    pub timestamp: u64,
+    /// all values below are priced at the snapshot block
+    #[prost(message, optional, tag="5")]
+    pub current_value_usd: ::core::option::Option<BigDecimal>,
+    /// deposited minus withdrawn amounts, as if they were held instead of provided
+    #[prost(message, optional, tag="6")]
+    pub hodl_value_usd: ::core::option::Option<BigDecimal>,
+    /// current value minus hodl value
+    #[prost(message, optional, tag="7")]
+    pub impermanent_loss_usd: ::core::option::Option<BigDecimal>,
+    /// collected amounts above the withdrawn principal
+    #[prost(message, optional, tag="8")]
+    pub realized_fees_usd: ::core::option::Option<BigDecimal>,
+    /// current value plus withdrawn amounts and realized fees minus deposited amounts
+    #[prost(message, optional, tag="9")]
+    pub pnl_usd: ::core::option::Option<BigDecimal>,
+    /// seconds since the position was minted
+    #[prost(uint64, tag="10")]
+    pub time_in_position: u64,
+    /// realized fees over hodl value, annualized
+    #[prost(message, optional, tag="11")]
+    pub fee_apr: ::core::option::Option<BigDecimal>,
+    /// internals
+    #[prost(uint64, tag="30")]
+    pub log_ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RawPositionEvents {
+    #[prost(message, repeated, tag="1")]
+    pub raw_position_events: ::prost::alloc::vec::Vec<RawPositionEvent>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RawPositionEvent {
+    /// <pool address>#<owner>#<tick lower>#<tick upper>
+    #[prost(string, tag="1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pool: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub owner: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub token0: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub token1: ::prost::alloc::string::String,
+    #[prost(message, optional, tag="6")]
+    pub tick_lower: ::core::option::Option<BigInt>,
+    #[prost(message, optional, tag="7")]
+    pub tick_upper: ::core::option::Option<BigInt>,
+    #[prost(string, tag="8")]
+    pub transaction: ::prost::alloc::string::String,
+    /// liquidity minted or burned, zero for collects
+    #[prost(message, optional, tag="9")]
+    pub liquidity: ::core::option::Option<BigInt>,
+    #[prost(message, optional, tag="10")]
+    pub amount0: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="11")]
+    pub amount1: ::core::option::Option<BigDecimal>,
+    #[prost(enumeration="raw_position_event::Type", tag="12")]
+    pub r#type: i32,
+    #[prost(uint64, tag="13")]
+    pub timestamp: u64,
+    #[prost(uint64, tag="14")]
+    pub block_number: u64,
+    /// internals
+    #[prost(uint64, tag="30")]
+    pub log_ordinal: u64,
+}
+/// Nested message and enum types in `RawPositionEvent`.
+pub mod raw_position_event {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+    #[repr(i32)]
+    pub enum Type {
+        Unset = 0,
+        Mint = 1,
+        Burn = 2,
+        Collect = 3,
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Trades {
+    #[prost(message, repeated, tag="1")]
+    pub trades: ::prost::alloc::vec::Vec<Trade>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Trade {
+    /// <transaction id>#<log ordinal of the first hop>
+    #[prost(string, tag="1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub transaction_id: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub origin: ::prost::alloc::string::String,
+    /// sender of the first hop
+    #[prost(string, tag="4")]
+    pub sender: ::prost::alloc::string::String,
+    /// recipient of the last hop
+    #[prost(string, tag="5")]
+    pub recipient: ::prost::alloc::string::String,
+    /// pools swapped through, in order
+    #[prost(string, repeated, tag="6")]
+    pub pools: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// tokens swapped through, from the input token to the output token
+    #[prost(string, repeated, tag="7")]
+    pub path: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, tag="8")]
+    pub token_in: ::prost::alloc::string::String,
+    #[prost(string, tag="9")]
+    pub token_out: ::prost::alloc::string::String,
+    #[prost(message, optional, tag="10")]
+    pub amount_in: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="11")]
+    pub amount_out: ::core::option::Option<BigDecimal>,
+    /// amount out per amount in
+    #[prost(message, optional, tag="12")]
+    pub effective_price: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="13")]
+    pub amount_usd: ::core::option::Option<BigDecimal>,
+    #[prost(uint64, tag="14")]
+    pub timestamp: u64,
+    #[prost(uint64, tag="15")]
+    pub block_number: u64,
+    /// internals
+    #[prost(uint64, tag="30")]
+    pub log_ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SwapInterfaces {
+    #[prost(message, repeated, tag="1")]
+    pub swap_interfaces: ::prost::alloc::vec::Vec<SwapInterface>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SwapInterface {
+    #[prost(string, tag="1")]
+    pub transaction_id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pool_address: ::prost::alloc::string::String,
+    /// label of the router or aggregator the swap went through, "Other" when unknown
+    #[prost(string, tag="3")]
+    pub interface: ::prost::alloc::string::String,
+    /// address the transaction was sent to
+    #[prost(string, tag="4")]
+    pub to: ::prost::alloc::string::String,
+    #[prost(message, optional, tag="5")]
+    pub amount_usd: ::core::option::Option<BigDecimal>,
+    #[prost(uint64, tag="6")]
+    pub timestamp: u64,
+    /// internals
+    #[prost(uint64, tag="30")]
+    pub log_ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Sandwiches {
+    #[prost(message, repeated, tag="1")]
+    pub sandwiches: ::prost::alloc::vec::Vec<Sandwich>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Sandwich {
+    /// <front-run transaction id>#<back-run transaction id>
+    #[prost(string, tag="1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pool: ::prost::alloc::string::String,
+    /// origin of the front-run swap
+    #[prost(string, tag="3")]
+    pub attacker: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub front_run_transaction: ::prost::alloc::string::String,
+    #[prost(string, tag="5")]
+    pub back_run_transaction: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag="6")]
+    pub victim_transactions: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// token the attacker starts and ends with
+    #[prost(string, tag="7")]
+    pub token_in: ::prost::alloc::string::String,
+    /// token the attacker holds between the front-run and the back-run
+    #[prost(string, tag="8")]
+    pub token_out: ::prost::alloc::string::String,
+    /// back-run output minus front-run input, in token_in
+    #[prost(message, optional, tag="9")]
+    pub attacker_profit: ::core::option::Option<BigDecimal>,
+    /// includes the token_out left over from the front-run
+    #[prost(message, optional, tag="10")]
+    pub attacker_profit_usd: ::core::option::Option<BigDecimal>,
+    /// token_out the victims missed because of the price move of the front-run
+    #[prost(message, optional, tag="11")]
+    pub victim_loss: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="12")]
+    pub victim_loss_usd: ::core::option::Option<BigDecimal>,
+    #[prost(uint64, tag="13")]
+    pub block_number: u64,
+    #[prost(uint64, tag="14")]
+    pub timestamp: u64,
+    /// internals
+    #[prost(uint64, tag="30")]
+    pub log_ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct JitLiquidityEvents {
+    #[prost(message, repeated, tag="1")]
+    pub jit_liquidity_events: ::prost::alloc::vec::Vec<JitLiquidityEvent>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct JitLiquidityEvent {
+    /// <pool address>#<log ordinal of the mint>
+    #[prost(string, tag="1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pool: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub owner: ::prost::alloc::string::String,
+    /// NFT token id, empty when the liquidity was minted directly on the pool
+    #[prost(string, tag="4")]
+    pub position_id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag="5")]
+    pub tick_lower: ::core::option::Option<BigInt>,
+    #[prost(message, optional, tag="6")]
+    pub tick_upper: ::core::option::Option<BigInt>,
+    #[prost(message, optional, tag="7")]
+    pub liquidity: ::core::option::Option<BigInt>,
+    #[prost(string, tag="8")]
+    pub mint_transaction: ::prost::alloc::string::String,
+    #[prost(string, tag="9")]
+    pub burn_transaction: ::prost::alloc::string::String,
+    /// swaps in range between the mint and the burn
+    #[prost(uint64, tag="10")]
+    pub swap_count: u64,
+    #[prost(message, optional, tag="11")]
+    pub swap_volume_usd: ::core::option::Option<BigDecimal>,
+    /// share of the swap fees earned by the JIT liquidity
+    #[prost(message, optional, tag="12")]
+    pub fees_token0: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="13")]
+    pub fees_token1: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="14")]
+    pub fees_usd: ::core::option::Option<BigDecimal>,
+    /// swap fees left to the other liquidity providers
+    #[prost(message, optional, tag="15")]
+    pub passive_fees_usd: ::core::option::Option<BigDecimal>,
+    #[prost(uint64, tag="16")]
+    pub block_number: u64,
+    #[prost(uint64, tag="17")]
+    pub timestamp: u64,
+    /// internals
+    #[prost(uint64, tag="30")]
+    pub log_ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Arbitrages {
+    #[prost(message, repeated, tag="1")]
+    pub arbitrages: ::prost::alloc::vec::Vec<Arbitrage>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Arbitrage {
+    /// id of the cyclic trade
+    #[prost(string, tag="1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub transaction_id: ::prost::alloc::string::String,
+    /// origin of the transaction
+    #[prost(string, tag="3")]
+    pub arbitrageur: ::prost::alloc::string::String,
+    /// pools swapped through, in order
+    #[prost(string, repeated, tag="4")]
+    pub pools: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// tokens swapped through, starting and ending with the arbitraged token
+    #[prost(string, repeated, tag="5")]
+    pub path: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, tag="6")]
+    pub token: ::prost::alloc::string::String,
+    #[prost(message, optional, tag="7")]
+    pub amount_in: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="8")]
+    pub amount_out: ::core::option::Option<BigDecimal>,
+    /// amount out minus amount in
+    #[prost(message, optional, tag="9")]
+    pub profit: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="10")]
+    pub profit_usd: ::core::option::Option<BigDecimal>,
+    /// value of the first hop
+    #[prost(message, optional, tag="11")]
+    pub volume_usd: ::core::option::Option<BigDecimal>,
+    #[prost(uint64, tag="12")]
+    pub gas_used: u64,
+    #[prost(message, optional, tag="13")]
+    pub gas_price: ::core::option::Option<BigInt>,
+    /// transaction gas split evenly between the arbitrages of the transaction
+    #[prost(message, optional, tag="14")]
+    pub gas_cost_eth: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="15")]
+    pub gas_cost_usd: ::core::option::Option<BigDecimal>,
+    /// profit minus gas cost
+    #[prost(message, optional, tag="16")]
+    pub net_profit_usd: ::core::option::Option<BigDecimal>,
+    #[prost(uint64, tag="17")]
+    pub block_number: u64,
+    #[prost(uint64, tag="18")]
+    pub timestamp: u64,
+    /// internals
+    #[prost(uint64, tag="30")]
+    pub log_ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CandleSwaps {
+    #[prost(message, repeated, tag="1")]
+    pub candle_swaps: ::prost::alloc::vec::Vec<CandleSwap>,
+    /// key prefixes of the candles too old to be closed anymore
+    #[prost(string, repeated, tag="2")]
+    pub expired_prefixes: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CandleSwap {
+    #[prost(string, tag="1")]
+    pub interval: ::prost::alloc::string::String,
+    #[prost(uint64, tag="2")]
+    pub period_start: u64,
+    #[prost(string, tag="3")]
+    pub pool_address: ::prost::alloc::string::String,
+    /// token0 per token1 after the swap
+    #[prost(message, optional, tag="4")]
+    pub token0_price: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="5")]
+    pub amount0: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="6")]
+    pub amount1: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="7")]
+    pub amount_usd: ::core::option::Option<BigDecimal>,
+    /// internals
+    #[prost(uint64, tag="30")]
+    pub log_ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoolCandles {
+    #[prost(message, repeated, tag="1")]
+    pub pool_candles: ::prost::alloc::vec::Vec<PoolCandle>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoolCandle {
+    /// <pool address>-<interval>-<period start>
+    #[prost(string, tag="1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub pool: ::prost::alloc::string::String,
+    /// 1m, 5m, 15m, 1h, 4h or 1d
+    #[prost(string, tag="3")]
+    pub interval: ::prost::alloc::string::String,
+    #[prost(uint64, tag="4")]
+    pub period_start: u64,
+    #[prost(message, optional, tag="5")]
+    pub token0_price_open: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="6")]
+    pub token0_price_high: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="7")]
+    pub token0_price_low: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="8")]
+    pub token0_price_close: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="9")]
+    pub token1_price_open: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="10")]
+    pub token1_price_high: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="11")]
+    pub token1_price_low: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="12")]
+    pub token1_price_close: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="13")]
+    pub volume_token0: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="14")]
+    pub volume_token1: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="15")]
+    pub volume_usd: ::core::option::Option<BigDecimal>,
+    #[prost(uint64, tag="16")]
+    pub trade_count: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoolObservations {
+    #[prost(message, repeated, tag="1")]
+    pub pool_observations: ::prost::alloc::vec::Vec<PoolObservation>,
+}
+/// first tick update of a pool in a block, as written by the pool oracle
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoolObservation {
+    #[prost(string, tag="1")]
+    pub pool_address: ::prost::alloc::string::String,
+    #[prost(uint64, tag="2")]
+    pub timestamp: u64,
+    #[prost(message, optional, tag="3")]
+    pub tick_cumulative: ::core::option::Option<BigInt>,
+    #[prost(message, optional, tag="4")]
+    pub tick: ::core::option::Option<BigInt>,
+    /// timestamp and tick of the update before, 0 on the pool initialization
+    #[prost(uint64, tag="5")]
+    pub previous_timestamp: u64,
+    #[prost(message, optional, tag="6")]
+    pub previous_tick: ::core::option::Option<BigInt>,
+    #[prost(uint64, tag="7")]
+    pub ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoolTwaps {
+    #[prost(message, repeated, tag="1")]
+    pub pool_twaps: ::prost::alloc::vec::Vec<PoolTwap>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PoolTwap {
+    #[prost(string, tag="1")]
+    pub pool_address: ::prost::alloc::string::String,
+    /// 5m, 30m, 1h or 24h
+    #[prost(string, tag="2")]
+    pub window: ::prost::alloc::string::String,
+    /// arithmetic mean tick over the window, rounded to negative infinity
+    #[prost(int64, tag="3")]
+    pub mean_tick: i64,
+    /// token0 per token1 at the mean tick
+    #[prost(message, optional, tag="4")]
+    pub token0_price: ::core::option::Option<BigDecimal>,
+    /// token1 per token0 at the mean tick
+    #[prost(message, optional, tag="5")]
+    pub token1_price: ::core::option::Option<BigDecimal>,
+    #[prost(uint64, tag="6")]
+    pub timestamp: u64,
+    #[prost(uint64, tag="7")]
+    pub ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BundlePrices {
+    #[prost(message, repeated, tag="1")]
+    pub bundle_prices: ::prost::alloc::vec::Vec<BundlePrice>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BundlePrice {
+    #[prost(message, optional, tag="1")]
+    pub eth_price_usd: ::core::option::Option<BigDecimal>,
+    /// weighted or median across the liquid reference pools, or pool when falling back on the first
+    /// priced reference pool
+    #[prost(string, tag="2")]
+    pub source: ::prost::alloc::string::String,
+    /// reference pools the price was taken from
+    #[prost(string, repeated, tag="3")]
+    pub pools: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(uint64, tag="4")]
+    pub ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DerivedEthPrices {
+    #[prost(message, repeated, tag="1")]
+    pub derived_eth_prices: ::prost::alloc::vec::Vec<DerivedEthPrice>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DerivedEthPrice {
+    #[prost(string, tag="1")]
+    pub token_address: ::prost::alloc::string::String,
+    #[prost(message, optional, tag="2")]
+    pub eth_price: ::core::option::Option<BigDecimal>,
+    /// pools hopped through to WETH
+    #[prost(string, repeated, tag="3")]
+    pub pools: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// tokens hopped through, starting with the priced token and ending with WETH
+    #[prost(string, repeated, tag="4")]
+    pub path: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// 0 to 1, from the ETH locked in the thinnest hop and the number of hops
+    #[prost(message, optional, tag="5")]
+    pub confidence: ::core::option::Option<BigDecimal>,
+    #[prost(uint64, tag="6")]
+    pub ordinal: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PriceAnomalies {
+    #[prost(message, repeated, tag="1")]
+    pub price_anomalies: ::prost::alloc::vec::Vec<PriceAnomaly>,
+}
+/// derived ETH price update rejected by the price guards
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PriceAnomaly {
+    #[prost(string, tag="1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub token_address: ::prost::alloc::string::String,
+    #[prost(enumeration="price_anomaly::Reason", tag="3")]
+    pub reason: i32,
+    #[prost(message, optional, tag="4")]
+    pub rejected_eth_price: ::core::option::Option<BigDecimal>,
+    /// 0 when no TWAP covers the pricing path
+    #[prost(message, optional, tag="5")]
+    pub reference_eth_price: ::core::option::Option<BigDecimal>,
+    /// relative deviation from the reference price
+    #[prost(message, optional, tag="6")]
+    pub deviation: ::core::option::Option<BigDecimal>,
+    /// pools agreeing with the rejected price, besides the first hop of its path
+    #[prost(uint64, tag="7")]
+    pub confirmations: u64,
+    #[prost(string, repeated, tag="8")]
+    pub pools: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(uint64, tag="9")]
+    pub block_number: u64,
+    #[prost(uint64, tag="10")]
+    pub timestamp: u64,
+    #[prost(uint64, tag="11")]
+    pub ordinal: u64,
+}
+/// Nested message and enum types in `PriceAnomaly`.
+pub mod price_anomaly {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+    #[repr(i32)]
+    pub enum Reason {
+        /// a pool of the pricing path is below the minimum in-range liquidity
+        LowLiquidity = 0,
+        /// the price strays from the TWAP reference without enough pools confirming it
+        Deviation = 1,
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TokenPriceChanges {
+    #[prost(message, repeated, tag="1")]
+    pub token_price_changes: ::prost::alloc::vec::Vec<TokenPriceChange>,
+}
+/// USD price of a token, moved by its derived ETH price or by the bundle
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TokenPriceChange {
+    #[prost(string, tag="1")]
+    pub token_address: ::prost::alloc::string::String,
+    #[prost(enumeration="token_price_change::Cause", tag="2")]
+    pub cause: i32,
+    /// 0 when the token had no USD price yet
+    #[prost(message, optional, tag="3")]
+    pub previous_price_usd: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="4")]
+    pub price_usd: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="5")]
+    pub derived_eth: ::core::option::Option<BigDecimal>,
+    #[prost(message, optional, tag="6")]
+    pub eth_price_usd: ::core::option::Option<BigDecimal>,
+    #[prost(uint64, tag="7")]
+    pub block_number: u64,
+    #[prost(uint64, tag="8")]
+    pub timestamp: u64,
+    #[prost(uint64, tag="9")]
+    pub ordinal: u64,
+}
+/// Nested message and enum types in `TokenPriceChange`.
+pub mod token_price_change {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+    #[repr(i32)]
+    pub enum Cause {
+        DerivedEth = 0,
+        Bundle = 1,
+    }
+}
+/// which USD amounts are tracked, parsed once from the module params
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TrackingPolicy {
+    #[prost(enumeration="tracking_policy::Type", tag="1")]
+    pub r#type: i32,
+    /// ETH both sides of a pool must lock, for MIN_LIQUIDITY only
+    #[prost(message, optional, tag="2")]
+    pub min_eth_locked: ::core::option::Option<BigDecimal>,
+}
+/// Nested message and enum types in `TrackingPolicy`.
+pub mod tracking_policy {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+    #[repr(i32)]
+    pub enum Type {
+        Whitelist = 0,
+        StablecoinAnchored = 1,
+        MinLiquidity = 2,
+    }
+}
 /// Encoded file descriptor set for the `uniswap.types.v1` package
 pub const FILE_DESCRIPTOR_SET: &[u8] = &[
     0x0a, 0xa7, 0x83, 0x01, 0x0a, 0x18, 0x75, 0x6e, 0x69, 0x73, 0x77, 0x61, 0x70, 0x2f, 0x76, 0x31,
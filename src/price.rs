@@ -1,13 +1,17 @@
-use crate::{keyer, math, Erc20Token, Pool};
-use std::ops::{Div, Mul};
+use crate::uniswap::price_anomaly::Reason as PriceAnomalyReason;
+use crate::{
+    keyer, math, utils, DerivedEthPrice, Erc20Token, Pool, PoolSqrtPrice, PriceAnomaly,
+    TokenTransferBehavior,
+};
+use std::ops::{Add, Div, Mul, Sub};
 use std::str;
 use std::str::FromStr;
 use substreams::log;
 use substreams::scalar::{BigDecimal, BigInt};
-use substreams::store::{StoreGet, StoreGetBigDecimal, StoreGetBigInt, StoreGetProto, StoreGetRaw};
+use substreams::store::{
+    StoreGet, StoreGetArray, StoreGetBigDecimal, StoreGetBigInt, StoreGetProto,
+};
 
-const USDC_WETH_03_POOL: &str = "8ad599c3a0ff1de082011efddc58f1908eb6e6d8";
-const USDC_ADDRESS: &str = "a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
 const WETH_ADDRESS: &str = "c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
 
 pub const STABLE_COINS: [&str; 6] = [
@@ -73,251 +77,661 @@ pub fn sqrt_price_x96_to_token_prices(
     return (price0, price1);
 }
 
+// same prices as sqrt_price_x96_to_token_prices, from a tick instead of the pool sqrt price
+pub fn tick_to_token_prices(
+    tick: i64,
+    token_0: &Erc20Token,
+    token_1: &Erc20Token,
+) -> (BigDecimal, BigDecimal) {
+    let price1 = math::big_decimal_pow(&BigDecimal::from_str("1.0001").unwrap(), tick)
+        .mul(math::exponent_to_big_decimal(&BigInt::from(
+            token_0.decimals,
+        )))
+        .div(math::exponent_to_big_decimal(&BigInt::from(
+            token_1.decimals,
+        )));
+    let price0 = math::safe_div(&BigDecimal::one(), &price1);
+
+    return (price0, price1);
+}
+
+// hops searched from a token to WETH, and counter tokens expanded at each hop
+const MAX_PATH_DEPTH: usize = 3;
+const MAX_EXPANDED_PAIRS: usize = 5;
+const MINIMUM_ETH_LOCKED: &str = "60";
+// ETH locked in the thinnest hop above which a single hop price is fully trusted
+const CONFIDENT_ETH_LOCKED: &str = "1000";
+
+pub struct PricePath {
+    pub eth_price: BigDecimal,
+    // ETH locked on the WETH side of the thinnest hop, none for WETH itself
+    pub eth_locked: Option<BigDecimal>,
+    pub pools: Vec<String>,
+    pub tokens: Vec<String>,
+}
+
+impl PricePath {
+    fn direct(eth_price: BigDecimal, token_address: &String) -> Self {
+        PricePath {
+            eth_price,
+            eth_locked: None,
+            pools: vec![],
+            tokens: vec![token_address.clone()],
+        }
+    }
+
+    // the thinnest hop ETH locked over CONFIDENT_ETH_LOCKED, capped at 1, losing a tenth for
+    // every hop past the first, direct prices being fully trusted
+    pub fn confidence(&self) -> BigDecimal {
+        let eth_locked = match &self.eth_locked {
+            None => return BigDecimal::one(),
+            Some(eth_locked) => eth_locked,
+        };
+        let mut confidence = eth_locked
+            .clone()
+            .div(BigDecimal::from_str(CONFIDENT_ETH_LOCKED).unwrap());
+        if confidence.gt(&BigDecimal::one()) {
+            confidence = BigDecimal::one();
+        }
+        confidence.mul(math::big_decimal_pow(
+            &BigDecimal::from_str("0.9").unwrap(),
+            self.pools.len() as i64 - 1,
+        ))
+    }
+}
+
+// WETH is worth 1, stablecoins are priced off the bundle and any other token off the widest path
+// to WETH, the one whose thinnest hop locks the most ETH
 pub fn find_eth_per_token(
     log_ordinal: u64,
-    pool_address: &String,
     token_address: &String,
     pools_store: &StoreGetProto<Pool>,
     pool_liquidities_store: &StoreGetBigInt,
-    tokens_whitelist_pools_store: &StoreGetRaw,
+    token_pools_store: &StoreGetArray<String>,
     total_native_value_locked_store: &StoreGetBigDecimal,
     prices_store: &StoreGetBigDecimal,
-) -> BigDecimal {
-    log::debug!(
-        "finding ETH per token for {} in pool {}",
+    transfer_behaviors_store: &StoreGetProto<TokenTransferBehavior>,
+    eth_price_usd: &BigDecimal,
+) -> PricePath {
+    if token_address.eq(WETH_ADDRESS) {
+        return PricePath::direct(BigDecimal::one(), token_address);
+    }
+    if STABLE_COINS.contains(&token_address.as_str()) {
+        log::debug!("token addr: {} is a stable coin", token_address);
+        return PricePath::direct(
+            math::safe_div(&BigDecimal::one(), eth_price_usd),
+            token_address,
+        );
+    }
+
+    let path = find_widest_eth_path(
+        log_ordinal,
         token_address,
-        pool_address
+        MAX_PATH_DEPTH,
+        &mut vec![],
+        pools_store,
+        pool_liquidities_store,
+        token_pools_store,
+        total_native_value_locked_store,
+        prices_store,
+        transfer_behaviors_store,
     );
+    match path {
+        Some(path)
+            if path
+                .eth_locked
+                .as_ref()
+                .unwrap()
+                .gt(&BigDecimal::from_str(MINIMUM_ETH_LOCKED).unwrap()) =>
+        {
+            log::debug!(
+                "token {} priced through pools {:?}",
+                token_address,
+                path.pools
+            );
+            path
+        }
+        _ => {
+            log::debug!("no path to WETH for token {}", token_address);
+            PricePath {
+                eth_price: BigDecimal::zero(),
+                eth_locked: Some(BigDecimal::zero()),
+                pools: vec![],
+                tokens: vec![token_address.clone()],
+            }
+        }
+    }
+}
+
+fn find_widest_eth_path(
+    log_ordinal: u64,
+    token_address: &String,
+    depth: usize,
+    visited: &mut Vec<String>,
+    pools_store: &StoreGetProto<Pool>,
+    pool_liquidities_store: &StoreGetBigInt,
+    token_pools_store: &StoreGetArray<String>,
+    total_native_value_locked_store: &StoreGetBigDecimal,
+    prices_store: &StoreGetBigDecimal,
+    transfer_behaviors_store: &StoreGetProto<TokenTransferBehavior>,
+) -> Option<PricePath> {
     if token_address.eq(WETH_ADDRESS) {
-        log::debug!("is ETH return 1");
-        return BigDecimal::one();
+        return Some(PricePath::direct(BigDecimal::one(), token_address));
+    }
+    if depth == 0 {
+        return None;
     }
 
-    let mut price_so_far = BigDecimal::zero().with_prec(100);
+    let native_locked = |pool_address: &String, token_address: &String| -> BigDecimal {
+        total_native_value_locked_store
+            .get_at(
+                log_ordinal,
+                keyer::pool_native_total_value_locked_token(pool_address, token_address),
+            )
+            .unwrap_or(BigDecimal::zero())
+    };
 
-    if STABLE_COINS.contains(&token_address.as_str()) {
-        log::debug!("token addr: {} is a stable coin", token_address);
-        let eth_price_usd = get_eth_price_in_usd(prices_store, log_ordinal);
-        price_so_far = math::safe_div(&BigDecimal::one(), &eth_price_usd);
-    } else {
-        // TODO: @eduard change this once the changes for store of list has been merged
-        let wl = match tokens_whitelist_pools_store
-            .get_last(&keyer::token_pool_whitelist(token_address))
-        {
-            None => {
-                log::debug!(
-                    "failed to get whitelisted pools for token {}",
-                    token_address
-                );
-                return BigDecimal::zero();
+    let pairs: Vec<(String, String)> = token_pools_store
+        .get_at(log_ordinal, keyer::token_pairs(token_address))
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|pair| {
+            let (pool_address, other_token) = pair.split_once(":")?;
+            Some((pool_address.to_string(), other_token.to_string()))
+        })
+        // the native liquidity of a fee-on-transfer token overstates its pools, so no
+        // price is routed through it
+        .filter(|(_, other_token)| {
+            !utils::is_fee_on_transfer(log_ordinal, other_token, transfer_behaviors_store)
+        })
+        .collect();
+
+    // a token paired with WETH is priced off its WETH pools alone, which keeps the hub tokens
+    // from being expanded, other tokens expand their deepest pairs
+    let weth_pairs: Vec<(String, String)> = pairs
+        .iter()
+        .filter(|(_, other_token)| other_token.eq(WETH_ADDRESS))
+        .cloned()
+        .collect();
+
+    visited.push(token_address.clone());
+    let mut best_path: Option<PricePath> = None;
+    for expand in [false, true] {
+        let candidates = match expand {
+            false => weth_pairs.clone(),
+            true if best_path.is_none() && depth > 1 => {
+                let mut ranked: Vec<(BigDecimal, (String, String))> = pairs
+                    .iter()
+                    .filter(|(_, other_token)| {
+                        !other_token.eq(WETH_ADDRESS) && !visited.contains(other_token)
+                    })
+                    .map(|(pool_address, other_token)| {
+                        (
+                            native_locked(pool_address, token_address),
+                            (pool_address.clone(), other_token.clone()),
+                        )
+                    })
+                    .collect();
+                ranked.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+                ranked
+                    .into_iter()
+                    .take(MAX_EXPANDED_PAIRS)
+                    .map(|(_, pair)| pair)
+                    .collect()
             }
-            Some(bytes) => String::from_utf8(bytes.to_vec()).unwrap(),
+            true => break,
         };
 
-        let mut whitelisted_pools: Vec<&str> = vec![];
-        for p in wl.split(";") {
-            if !p.is_empty() {
-                whitelisted_pools.push(p);
+        for (pool_address, other_token) in candidates {
+            let liquidity = pool_liquidities_store
+                .get_last(keyer::pool_liquidity(&pool_address))
+                .unwrap_or(BigInt::zero());
+            if !liquidity.gt(&BigInt::zero()) {
+                continue;
             }
-        }
-        log::debug!("found whitelisted pools {}", whitelisted_pools.len());
+            let pool = match pools_store.get_last(keyer::pool_key(&pool_address)) {
+                None => continue,
+                Some(pool) => pool,
+            };
 
-        let mut largest_eth_locked = BigDecimal::zero().with_prec(100);
-        let minimum_eth_locked = BigDecimal::from_str("60").unwrap();
-        let mut eth_locked: BigDecimal;
+            let price = match pool_token_price(&pool, token_address, log_ordinal, prices_store) {
+                None => continue,
+                Some(price) => price,
+            };
 
-        for pool_address in whitelisted_pools.iter() {
-            log::debug!("checking pool: {}", pool_address);
-            let pool = match pools_store.get_last(keyer::pool_key(&pool_address.to_string())) {
+            let rest = match find_widest_eth_path(
+                log_ordinal,
+                &other_token,
+                depth - 1,
+                visited,
+                pools_store,
+                pool_liquidities_store,
+                token_pools_store,
+                total_native_value_locked_store,
+                prices_store,
+                transfer_behaviors_store,
+            ) {
                 None => continue,
-                Some(p) => p,
+                Some(rest) => rest,
             };
-            let token0 = pool.token0.as_ref().unwrap();
-            let token1 = pool.token1.as_ref().unwrap();
-            log::debug!(
-                "found pool: {} with token0 {} with token1 {}",
-                pool.address,
-                token0.address,
-                token1.address
-            );
 
-            let liquidity: BigInt =
-                match pool_liquidities_store.get_last(&keyer::pool_liquidity(&pool.address)) {
-                    None => {
-                        log::debug!("No liquidity for pool {}", pool.address);
-                        BigInt::zero()
-                    }
-                    Some(price) => price,
-                };
-
-            if liquidity.gt(&BigInt::zero()) {
-                if &token0.address == token_address {
-                    log::info!(
-                        "current pool token 0 matches desired token, complementary token is {} {}",
-                        token1.address,
-                        token1.symbol
-                    );
-                    let native_locked_value: BigDecimal = match total_native_value_locked_store
-                        .get_last(&keyer::pool_native_total_value_locked_token(
-                            &pool.address,
-                            &token1.address,
-                        )) {
-                        None => BigDecimal::zero().with_prec(100),
-                        Some(price) => price.with_prec(100),
-                    };
-                    log::debug!(
-                        "native locked value of token1 in pool {}",
-                        native_locked_value
-                    );
-
-                    // If the counter token is WETH we know the derived price is 1
-                    if token1.address.eq(WETH_ADDRESS) {
-                        log::debug!("token 1 is WETH");
-                        eth_locked = native_locked_value
-                    } else {
-                        log::debug!("token 1 is NOT WETH");
-                        let token_eth_price: BigDecimal = match prices_store.get_at(
-                            log_ordinal,
-                            &keyer::prices_token_pair(&token1.address, &WETH_ADDRESS.to_string()),
-                        ) {
-                            None => {
-                                log::debug!(
-                                    "unable to find token 1 price in eth {:?}",
-                                    token1.address
-                                );
-                                continue;
-                            }
-                            Some(price) => price,
-                        };
-                        log::debug!("token 1 is price in eth {}", token_eth_price);
-                        eth_locked = native_locked_value.mul(token_eth_price);
-                        log::debug!("computed eth locked {}", eth_locked);
-                    }
-                    log::debug!(
-                        "eth locked in pool {} {} (largest {})",
-                        pool.address,
-                        eth_locked,
-                        largest_eth_locked
-                    );
-                    if eth_locked.gt(&largest_eth_locked) && eth_locked.gt(&minimum_eth_locked) {
-                        log::debug!("eth locked passed test");
-                        let token1_price: BigDecimal = match prices_store.get_at(
-                            log_ordinal,
-                            &keyer::prices_pool_token_key(
-                                &pool.address,
-                                &token1.address,
-                                "token1".to_string(),
-                            ),
-                        ) {
-                            None => {
-                                log::debug!(
-                                    "unable to find pool {} for token {} price",
-                                    pool.address,
-                                    token1.address
-                                );
-                                continue;
-                            }
-                            Some(price) => price,
-                        };
-                        log::debug!("found token 1 price {}", token1_price);
-                        largest_eth_locked = eth_locked.clone();
-                        price_so_far = token1_price.mul(eth_locked.clone());
-                        log::debug!("price_so_far {}", price_so_far);
-                    }
+            let hop_eth_locked =
+                native_locked(&pool_address, &other_token).mul(rest.eth_price.clone());
+            let eth_locked = match rest.eth_locked {
+                Some(rest_eth_locked) if rest_eth_locked.lt(&hop_eth_locked) => rest_eth_locked,
+                _ => hop_eth_locked,
+            };
+            if let Some(best_path) = &best_path {
+                if !eth_locked.gt(best_path.eth_locked.as_ref().unwrap()) {
+                    continue;
                 }
-                if &token1.address == token_address {
-                    log::debug!(
-                        "current pool token 1 matches desired token, complementary token is {} {}",
-                        token0.address,
-                        token1.symbol
-                    );
-                    let native_locked_value: BigDecimal = match total_native_value_locked_store
-                        .get_last(&keyer::pool_native_total_value_locked_token(
-                            &pool.address,
-                            &token0.address,
-                        )) {
-                        None => BigDecimal::zero().with_prec(100),
-                        Some(price) => price.with_prec(100),
-                    };
-                    log::debug!(
-                        "native locked value of token0 in pool {}",
-                        native_locked_value
-                    );
-
-                    // If the counter token is WETH we know the derived price is 1
-                    if token0.address.eq(WETH_ADDRESS) {
-                        log::debug!("token 0 is WETH");
-                        eth_locked = native_locked_value
-                    } else {
-                        log::debug!("token 0 is NOT WETH");
-                        let token_eth_price: BigDecimal = match prices_store.get_at(
-                            log_ordinal,
-                            &keyer::prices_token_pair(&token0.address, &WETH_ADDRESS.to_string()),
-                        ) {
-                            None => {
-                                log::debug!(
-                                    "unable to find token 0 price in eth {:?}",
-                                    token0.address
-                                );
-                                continue;
-                            }
-                            Some(price) => price,
-                        };
-                        log::debug!("token 0 is price in eth {}", token_eth_price);
-                        eth_locked = native_locked_value.mul(token_eth_price);
-                        log::debug!("computed eth locked {}", eth_locked);
-                    }
-                    log::debug!(
-                        "eth locked in pool {} {} (largest {})",
-                        pool.address,
-                        eth_locked,
-                        largest_eth_locked
-                    );
-                    if eth_locked.gt(&largest_eth_locked) && eth_locked.gt(&minimum_eth_locked) {
-                        log::debug!("eth locked passed test");
-                        let token0_price: BigDecimal = match prices_store.get_at(
-                            log_ordinal,
-                            &keyer::prices_pool_token_key(
-                                &pool.address,
-                                &token0.address,
-                                "token0".to_string(),
-                            ),
-                        ) {
-                            None => {
-                                log::debug!(
-                                    "unable to find pool {} for token {} price",
-                                    pool.address,
-                                    token0.address
-                                );
-                                continue;
-                            }
-                            Some(price) => price,
-                        };
-                        log::debug!("found token 0 price {}", token0_price);
-                        largest_eth_locked = eth_locked.clone();
-                        price_so_far = token0_price.mul(eth_locked.clone());
-                        log::debug!("price_so_far {}", price_so_far);
-                    }
+            }
+
+            let mut pools = vec![pool_address];
+            pools.extend(rest.pools);
+            let mut tokens = vec![token_address.clone()];
+            tokens.extend(rest.tokens);
+            best_path = Some(PricePath {
+                eth_price: price.mul(rest.eth_price),
+                eth_locked: Some(eth_locked),
+                pools,
+                tokens,
+            });
+        }
+    }
+    visited.pop();
+
+    best_path
+}
+
+// the token priced in the counter token of the pool
+fn pool_token_price(
+    pool: &Pool,
+    token_address: &String,
+    log_ordinal: u64,
+    prices_store: &StoreGetBigDecimal,
+) -> Option<BigDecimal> {
+    let token0 = pool.token0.as_ref().unwrap();
+    let token1 = pool.token1.as_ref().unwrap();
+    let price_key = match token0.address.eq(token_address) {
+        true => keyer::prices_pool_token_key(&pool.address, &token1.address, "token1".to_string()),
+        false => keyer::prices_pool_token_key(&pool.address, &token0.address, "token0".to_string()),
+    };
+    let price = prices_store.get_at(log_ordinal, price_key)?;
+    if price.eq(&BigDecimal::zero()) {
+        return None;
+    }
+    Some(price)
+}
+
+pub struct PriceGuard {
+    // relative deviation from the TWAP reference
+    pub max_deviation: BigDecimal,
+    pub min_confirmations: u64,
+    pub min_liquidity: BigDecimal,
+    pub twap_window: String,
+}
+
+// the guards are given as `max_deviation=25;min_confirmations=2;min_liquidity=10;twap_window=30m`,
+// the deviation in percent and the liquidity in whole token units
+pub fn parse_price_guard_params(params: &String) -> PriceGuard {
+    let mut guard = PriceGuard {
+        max_deviation: BigDecimal::from_str("0.25").unwrap(),
+        min_confirmations: 2,
+        min_liquidity: BigDecimal::from(10 as i32),
+        twap_window: "30m".to_string(),
+    };
+    for entry in params.split(";") {
+        let (name, value) = match entry.trim().split_once("=") {
+            None => continue,
+            Some((name, value)) => (name.trim(), value.trim()),
+        };
+        match name {
+            "max_deviation" => {
+                if let Ok(percent) = BigDecimal::from_str(value) {
+                    guard.max_deviation = percent.div(BigDecimal::from(100 as i32));
+                }
+            }
+            "min_confirmations" => {
+                if let Ok(confirmations) = value.parse::<u64>() {
+                    guard.min_confirmations = confirmations;
                 }
             }
+            "min_liquidity" => {
+                if let Ok(liquidity) = BigDecimal::from_str(value) {
+                    guard.min_liquidity = liquidity;
+                }
+            }
+            "twap_window" => guard.twap_window = value.to_string(),
+            _ => continue,
         }
     }
-    return price_so_far;
+    guard
 }
 
-pub fn get_eth_price_in_usd(prices_store: &StoreGetBigDecimal, ordinal: u64) -> BigDecimal {
-    // USDC is the token0 in this pool kinda same point as
-    // mentioned earlier, token0 hard-coded is not clean
-    let key = keyer::prices_pool_token_key(
-        &USDC_WETH_03_POOL.to_string(),
-        &USDC_ADDRESS.to_string(),
-        "token0".to_string(),
+// the liquidity is the geometric mean of the virtual reserves, so its square over
+// 10^(decimals0 + decimals1) compares to the squared minimum in whole token units
+fn has_min_liquidity(
+    pool_address: &String,
+    guard: &PriceGuard,
+    pools_store: &StoreGetProto<Pool>,
+    pool_liquidities_store: &StoreGetBigInt,
+) -> bool {
+    let pool = match pools_store.get_last(keyer::pool_key(pool_address)) {
+        None => return false,
+        Some(pool) => pool,
+    };
+    let liquidity = pool_liquidities_store
+        .get_last(keyer::pool_liquidity(pool_address))
+        .unwrap_or(BigInt::zero())
+        .to_decimal(0);
+    let decimals = pool.token0.as_ref().unwrap().decimals + pool.token1.as_ref().unwrap().decimals;
+
+    !liquidity
+        .clone()
+        .mul(liquidity)
+        .div(math::exponent_to_big_decimal(&BigInt::from(decimals)))
+        .lt(&guard.min_liquidity.clone().mul(guard.min_liquidity.clone()))
+}
+
+// the derived price again, through the TWAPs of the pools of its path
+fn twap_eth_price(
+    derived_eth_price: &DerivedEthPrice,
+    guard: &PriceGuard,
+    pools_store: &StoreGetProto<Pool>,
+    pool_twaps_store: &StoreGetBigDecimal,
+) -> Option<BigDecimal> {
+    let mut eth_price = BigDecimal::one();
+    for (i, pool_address) in derived_eth_price.pools.iter().enumerate() {
+        let pool = pools_store.get_last(keyer::pool_key(pool_address))?;
+        let name = match pool.token0.as_ref().unwrap().address == derived_eth_price.path[i] {
+            true => "token1Price",
+            false => "token0Price",
+        };
+        let twap_price = pool_twaps_store.get_at(
+            derived_eth_price.ordinal,
+            keyer::pool_twap(pool_address, &guard.twap_window, name),
+        )?;
+        eth_price = eth_price.mul(twap_price);
+    }
+    Some(eth_price)
+}
+
+fn relative_deviation(price: &BigDecimal, reference: &BigDecimal) -> BigDecimal {
+    let deviation = math::safe_div(&price.clone().sub(reference.clone()), reference);
+    match deviation.lt(&BigDecimal::zero()) {
+        true => deviation.neg(),
+        false => deviation,
+    }
+}
+
+// pools of the token quoting it close enough to the derived price, against WETH or against the
+// counter token of the first hop valued through the rest of the path, the first hop itself
+// not counting as it quotes the derived price by construction
+fn count_confirmations(
+    derived_eth_price: &DerivedEthPrice,
+    eth_price: &BigDecimal,
+    guard: &PriceGuard,
+    pools_store: &StoreGetProto<Pool>,
+    prices_store: &StoreGetBigDecimal,
+    pool_liquidities_store: &StoreGetBigInt,
+    token_pools_store: &StoreGetArray<String>,
+) -> u64 {
+    let token_address = &derived_eth_price.token_address;
+    let ordinal = derived_eth_price.ordinal;
+    let counter_token = &derived_eth_price.path[1];
+    let counter_eth_price = match pools_store
+        .get_last(keyer::pool_key(&derived_eth_price.pools[0]))
+        .and_then(|pool| pool_token_price(&pool, token_address, ordinal, prices_store))
+    {
+        None => return 0,
+        Some(first_hop_price) => eth_price.clone().div(first_hop_price),
+    };
+
+    let mut confirmations = 0;
+    for pair in token_pools_store
+        .get_at(ordinal, keyer::token_pairs(token_address))
+        .unwrap_or_default()
+    {
+        let (pool_address, other_token) = match pair.split_once(":") {
+            None => continue,
+            Some(pair) => pair,
+        };
+        let other_eth_price = match other_token {
+            WETH_ADDRESS => BigDecimal::one(),
+            _ if other_token == counter_token.as_str() => counter_eth_price.clone(),
+            _ => continue,
+        };
+        let pool_address = pool_address.to_string();
+        if pool_address == derived_eth_price.pools[0] {
+            continue;
+        }
+        if !has_min_liquidity(&pool_address, guard, pools_store, pool_liquidities_store) {
+            continue;
+        }
+        let quoted_eth_price = match pools_store
+            .get_last(keyer::pool_key(&pool_address))
+            .and_then(|pool| pool_token_price(&pool, token_address, ordinal, prices_store))
+        {
+            None => continue,
+            Some(price) => price.mul(other_eth_price),
+        };
+
+        if !relative_deviation(&quoted_eth_price, eth_price).gt(&guard.max_deviation) {
+            confirmations += 1;
+        }
+    }
+    confirmations
+}
+
+// a derived price is rejected when a pool of its path is too thin, or when it strays from the
+// TWAP reference and too few pools of the token confirm it, WETH and stablecoin prices being
+// left to the bundle
+pub fn check_derived_eth_price(
+    derived_eth_price: &DerivedEthPrice,
+    guard: &PriceGuard,
+    pools_store: &StoreGetProto<Pool>,
+    prices_store: &StoreGetBigDecimal,
+    pool_liquidities_store: &StoreGetBigInt,
+    token_pools_store: &StoreGetArray<String>,
+    pool_twaps_store: &StoreGetBigDecimal,
+) -> Option<PriceAnomaly> {
+    if derived_eth_price.pools.is_empty() {
+        return None;
+    }
+    let eth_price = BigDecimal::from(derived_eth_price.eth_price.clone().unwrap());
+    let mut price_anomaly = PriceAnomaly {
+        token_address: derived_eth_price.token_address.clone(),
+        rejected_eth_price: Some(eth_price.clone().into()),
+        reference_eth_price: Some(BigDecimal::zero().into()),
+        deviation: Some(BigDecimal::zero().into()),
+        pools: derived_eth_price.pools.clone(),
+        ordinal: derived_eth_price.ordinal,
+        ..Default::default()
+    };
+
+    if derived_eth_price.pools.iter().any(|pool_address| {
+        !has_min_liquidity(pool_address, guard, pools_store, pool_liquidities_store)
+    }) {
+        price_anomaly.reason = PriceAnomalyReason::LowLiquidity as i32;
+        return Some(price_anomaly);
+    }
+
+    let reference_eth_price =
+        twap_eth_price(derived_eth_price, guard, pools_store, pool_twaps_store)?;
+    let deviation = relative_deviation(&eth_price, &reference_eth_price);
+    if !deviation.gt(&guard.max_deviation) {
+        return None;
+    }
+
+    let confirmations = count_confirmations(
+        derived_eth_price,
+        &eth_price,
+        guard,
+        pools_store,
+        prices_store,
+        pool_liquidities_store,
+        token_pools_store,
     );
-    return match prices_store.get_at(ordinal, &key) {
-        None => {
-            log::debug!("price not found");
-            BigDecimal::zero()
+    if confirmations >= guard.min_confirmations {
+        return None;
+    }
+
+    price_anomaly.reason = PriceAnomalyReason::Deviation as i32;
+    price_anomaly.reference_eth_price = Some(reference_eth_price.into());
+    price_anomaly.deviation = Some(deviation.into());
+    price_anomaly.confirmations = confirmations;
+    Some(price_anomaly)
+}
+
+pub const BUNDLE_SOURCE_WEIGHTED: &str = "weighted";
+pub const BUNDLE_SOURCE_MEDIAN: &str = "median";
+pub const BUNDLE_SOURCE_POOL: &str = "pool";
+
+// the reference pools are given as `method=weighted;pools=<address>,<address>`, the method being
+// weighted or median and the pools listed by fallback priority
+pub fn parse_bundle_price_params(params: &String) -> (String, Vec<String>) {
+    let mut method = BUNDLE_SOURCE_WEIGHTED.to_string();
+    let mut pools = vec![];
+    for entry in params.split(";") {
+        match entry.trim().split_once("=") {
+            Some(("method", value)) => method = value.trim().to_string(),
+            Some(("pools", value)) => {
+                for pool_address in value.split(",") {
+                    let pool_address = pool_address.trim().trim_start_matches("0x").to_lowercase();
+                    if !pool_address.is_empty() {
+                        pools.push(pool_address);
+                    }
+                }
+            }
+            _ => continue,
+        }
+    }
+    (method, pools)
+}
+
+// ETH priced in the stablecoin of a WETH/stablecoin reference pool, along with the virtual
+// stablecoin depth of its active liquidity, which unlike the raw liquidity compares across
+// pools whatever the decimals of their stablecoin
+fn reference_pool_eth_price(
+    pool_address: &String,
+    ordinal: u64,
+    pools_store: &StoreGetProto<Pool>,
+    prices_store: &StoreGetBigDecimal,
+    pool_sqrt_prices_store: &StoreGetProto<PoolSqrtPrice>,
+    pool_liquidities_store: &StoreGetBigInt,
+) -> Option<(BigDecimal, BigDecimal)> {
+    let pool = pools_store.get_last(keyer::pool_key(pool_address))?;
+    let token0 = pool.token0.as_ref().unwrap();
+    let token1 = pool.token1.as_ref().unwrap();
+    let weth_is_token0 = match (token0.address.as_str(), token1.address.as_str()) {
+        (WETH_ADDRESS, _) => true,
+        (_, WETH_ADDRESS) => false,
+        _ => return None,
+    };
+
+    let price_key = match weth_is_token0 {
+        true => keyer::prices_pool_token_key(&pool.address, &token1.address, "token1".to_string()),
+        false => keyer::prices_pool_token_key(&pool.address, &token0.address, "token0".to_string()),
+    };
+    let price = prices_store.get_at(ordinal, &price_key)?;
+    if price.eq(&BigDecimal::zero()) {
+        return None;
+    }
+
+    let liquidity = pool_liquidities_store
+        .get_last(keyer::pool_liquidity(&pool.address))
+        .unwrap_or(BigInt::zero())
+        .to_decimal(0);
+    let depth = match pool_sqrt_prices_store
+        .get_at(ordinal, keyer::pool_sqrt_price_key(&pool.address))
+    {
+        None => BigDecimal::zero(),
+        Some(pool_sqrt_price) => {
+            let sqrt_price = math::sqrt_price_from_x96(&pool_sqrt_price.sqrt_price.unwrap().into());
+            match weth_is_token0 {
+                true => {
+                    liquidity
+                        .mul(sqrt_price)
+                        .div(math::exponent_to_big_decimal(&BigInt::from(
+                            token1.decimals,
+                        )))
+                }
+                false => math::safe_div(&liquidity, &sqrt_price).div(
+                    math::exponent_to_big_decimal(&BigInt::from(token0.decimals)),
+                ),
+            }
         }
-        Some(price) => price,
     };
+
+    Some((price, depth))
+}
+
+// ETH price in USD across the reference pools, falling back on the first priced reference pool
+// when none has active liquidity, along with the source used and the pools it was taken from
+pub fn get_eth_price_in_usd(
+    method: &String,
+    reference_pools: &Vec<String>,
+    ordinal: u64,
+    pools_store: &StoreGetProto<Pool>,
+    prices_store: &StoreGetBigDecimal,
+    pool_sqrt_prices_store: &StoreGetProto<PoolSqrtPrice>,
+    pool_liquidities_store: &StoreGetBigInt,
+) -> Option<(BigDecimal, String, Vec<String>)> {
+    let mut priced_pools: Vec<(String, BigDecimal, BigDecimal)> = vec![];
+    for pool_address in reference_pools {
+        if let Some((price, depth)) = reference_pool_eth_price(
+            pool_address,
+            ordinal,
+            pools_store,
+            prices_store,
+            pool_sqrt_prices_store,
+            pool_liquidities_store,
+        ) {
+            priced_pools.push((pool_address.clone(), price, depth));
+        }
+    }
+
+    let liquid_pools: Vec<&(String, BigDecimal, BigDecimal)> = priced_pools
+        .iter()
+        .filter(|(_, _, depth)| depth.gt(&BigDecimal::zero()))
+        .collect();
+    if !liquid_pools.is_empty() {
+        let sources = liquid_pools
+            .iter()
+            .map(|(pool, _, _)| pool.clone())
+            .collect();
+        if method == BUNDLE_SOURCE_MEDIAN {
+            let mut prices: Vec<BigDecimal> = liquid_pools
+                .iter()
+                .map(|(_, price, _)| price.clone())
+                .collect();
+            prices.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let middle = prices.len() / 2;
+            let median = match prices.len() % 2 {
+                0 => prices[middle - 1]
+                    .clone()
+                    .add(prices[middle].clone())
+                    .div(BigDecimal::from(2 as i32)),
+                _ => prices[middle].clone(),
+            };
+            return Some((median, BUNDLE_SOURCE_MEDIAN.to_string(), sources));
+        }
+
+        let mut weighted_price = BigDecimal::zero();
+        let mut total_depth = BigDecimal::zero();
+        for (_, price, depth) in &liquid_pools {
+            weighted_price = weighted_price.add(price.clone().mul(depth.clone()));
+            total_depth = total_depth.add(depth.clone());
+        }
+        return Some((
+            weighted_price.div(total_depth),
+            BUNDLE_SOURCE_WEIGHTED.to_string(),
+            sources,
+        ));
+    }
+
+    let (pool_address, price, _) = priced_pools.into_iter().next()?;
+    log::debug!("no liquid reference pool, falling back on {}", pool_address);
+    Some((price, BUNDLE_SOURCE_POOL.to_string(), vec![pool_address]))
 }
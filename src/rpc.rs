@@ -53,28 +53,6 @@ pub fn fee_growth_outside_x128_call(pool_address: &String, tick: &BigInt) -> (Bi
     return (fee_0.try_into().unwrap(), fee_1.try_into().unwrap());
 }
 
-pub fn positions_call(
-    pool_address: &String,
-    token_id: BigInt,
-) -> Option<(Vec<u8>, Vec<u8>, BigInt, BigInt, BigInt, BigInt, BigInt)> {
-    let positions = abi::positionmanager::functions::Positions {
-        token_id: token_id.clone(),
-    };
-    if let Some(positions_result) = positions.call(hex::decode(pool_address).unwrap()) {
-        return Some((
-            positions_result.2,
-            positions_result.3,
-            positions_result.4,
-            positions_result.5,
-            positions_result.6,
-            positions_result.8,
-            positions_result.9,
-        ));
-    };
-
-    return None;
-}
-
 pub fn create_uniswap_token(token_address: &String) -> Option<Erc20Token> {
     let batch = RpcBatch::new();
     let responses = batch
@@ -90,6 +68,10 @@ pub fn create_uniswap_token(token_address: &String) -> Option<Erc20Token> {
             abi::erc20::functions::Symbol {},
             hex::decode(token_address).unwrap(),
         )
+        .add(
+            abi::erc20::functions::TotalSupply {},
+            hex::decode(token_address).unwrap(),
+        )
         .execute()
         .unwrap()
         .responses;
@@ -149,21 +131,19 @@ pub fn create_uniswap_token(token_address: &String) -> Option<Erc20Token> {
     };
     log::debug!("decoded_symbol ok");
 
+    let total_supply: BigInt =
+        match RpcBatch::decode::<_, abi::erc20::functions::TotalSupply>(&responses[3]) {
+            Some(decoded_total_supply) => decoded_total_supply,
+            None => panic!("failed to get token {} total supply", token_address),
+        };
+    log::debug!("decoded_total_supply ok");
+
     return Some(Erc20Token {
         address: token_address.clone(),
         name,
         symbol,
         decimals,
-        total_supply: "".to_string(),
+        total_supply: total_supply.to_string(),
         whitelist_pools: vec![],
     });
 }
-
-pub fn token_total_supply_call(token_address: &String) -> Option<BigInt> {
-    let token_supply = abi::erc20::functions::TotalSupply {};
-    if let Some(token_supply_result) = token_supply.call(hex::decode(token_address).unwrap()) {
-        return Some(token_supply_result);
-    };
-
-    return None;
-}
@@ -0,0 +1,321 @@
+use crate::keyer;
+use crate::price::{STABLE_COINS, WHITELIST_TOKENS};
+use crate::uniswap;
+use crate::uniswap::tracking_policy::Type as TrackingPolicyType;
+use std::ops::{Add, Mul};
+use std::str::FromStr;
+use substreams::scalar::BigDecimal;
+use substreams::store::{StoreGet, StoreGetBigDecimal};
+
+pub const TRACKING_POLICY_WHITELIST: &str = "whitelist";
+pub const TRACKING_POLICY_STABLECOIN: &str = "stablecoin";
+pub const TRACKING_POLICY_MIN_LIQUIDITY: &str = "min_liquidity";
+
+// decides which part of a USD amount is tracked, the rest only counting as untracked
+#[derive(Clone, Debug, PartialEq)]
+pub enum TrackingPolicy {
+    // whitelisted tokens are tracked, a single whitelisted side counting twice
+    Whitelist,
+    // the whitelist, with stablecoins valued at 1 USD instead of their derived ETH price
+    StablecoinAnchored,
+    // the whitelist, in pools where both tokens lock at least this much ETH
+    MinLiquidity(BigDecimal),
+}
+
+impl TrackingPolicy {
+    // amounts are absolute and in whole token units, pool_eth_locked being the ETH
+    // locked by the thinnest side of the pool
+    pub fn tracked_amount_usd(
+        &self,
+        token0_id: &String,
+        token1_id: &String,
+        amount0_abs: &BigDecimal,
+        amount1_abs: &BigDecimal,
+        token0_price_usd: &BigDecimal,
+        token1_price_usd: &BigDecimal,
+        pool_eth_locked: &BigDecimal,
+    ) -> BigDecimal {
+        let (tracked0_usd, tracked1_usd) = self.tracked_amounts_usd(
+            token0_id,
+            token1_id,
+            amount0_abs,
+            amount1_abs,
+            token0_price_usd,
+            token1_price_usd,
+            pool_eth_locked,
+        );
+        tracked0_usd.add(tracked1_usd)
+    }
+
+    // the tracked amount split by token, a non whitelisted side being valued as
+    // much as the whitelisted one
+    pub fn tracked_amounts_usd(
+        &self,
+        token0_id: &String,
+        token1_id: &String,
+        amount0_abs: &BigDecimal,
+        amount1_abs: &BigDecimal,
+        token0_price_usd: &BigDecimal,
+        token1_price_usd: &BigDecimal,
+        pool_eth_locked: &BigDecimal,
+    ) -> (BigDecimal, BigDecimal) {
+        if let TrackingPolicy::MinLiquidity(min_eth_locked) = self {
+            if pool_eth_locked.lt(min_eth_locked) {
+                return (BigDecimal::zero(), BigDecimal::zero());
+            }
+        }
+
+        let amount0_usd = amount0_abs
+            .clone()
+            .mul(self.price_usd(token0_id, token0_price_usd));
+        let amount1_usd = amount1_abs
+            .clone()
+            .mul(self.price_usd(token1_id, token1_price_usd));
+
+        match (
+            WHITELIST_TOKENS.contains(&token0_id.as_str()),
+            WHITELIST_TOKENS.contains(&token1_id.as_str()),
+        ) {
+            // both are whitelist tokens, return both amounts
+            (true, true) => (amount0_usd, amount1_usd),
+            // take double value of the whitelisted token amount
+            (true, false) => (amount0_usd.clone(), amount0_usd),
+            (false, true) => (amount1_usd.clone(), amount1_usd),
+            // neither token is on white list, tracked amount is 0
+            (false, false) => (BigDecimal::zero(), BigDecimal::zero()),
+        }
+    }
+
+    fn price_usd(&self, token_id: &String, price_usd: &BigDecimal) -> BigDecimal {
+        if *self == TrackingPolicy::StablecoinAnchored && STABLE_COINS.contains(&token_id.as_str())
+        {
+            return BigDecimal::one();
+        }
+        price_usd.clone()
+    }
+}
+
+// the policy travels between modules as the map_tracking_policy output
+impl From<TrackingPolicy> for uniswap::TrackingPolicy {
+    fn from(tracking_policy: TrackingPolicy) -> Self {
+        match tracking_policy {
+            TrackingPolicy::Whitelist => uniswap::TrackingPolicy {
+                r#type: TrackingPolicyType::Whitelist as i32,
+                min_eth_locked: None,
+            },
+            TrackingPolicy::StablecoinAnchored => uniswap::TrackingPolicy {
+                r#type: TrackingPolicyType::StablecoinAnchored as i32,
+                min_eth_locked: None,
+            },
+            TrackingPolicy::MinLiquidity(min_eth_locked) => uniswap::TrackingPolicy {
+                r#type: TrackingPolicyType::MinLiquidity as i32,
+                min_eth_locked: Some(min_eth_locked.into()),
+            },
+        }
+    }
+}
+
+impl From<uniswap::TrackingPolicy> for TrackingPolicy {
+    fn from(tracking_policy: uniswap::TrackingPolicy) -> Self {
+        match TrackingPolicyType::from_i32(tracking_policy.r#type) {
+            Some(TrackingPolicyType::StablecoinAnchored) => TrackingPolicy::StablecoinAnchored,
+            Some(TrackingPolicyType::MinLiquidity) => TrackingPolicy::MinLiquidity(
+                tracking_policy
+                    .min_eth_locked
+                    .map(BigDecimal::from)
+                    .unwrap_or(BigDecimal::zero()),
+            ),
+            _ => TrackingPolicy::Whitelist,
+        }
+    }
+}
+
+// the policy is given as `policy=whitelist`, `policy=stablecoin` or
+// `policy=min_liquidity;min_eth_locked=10`, defaulting to the whitelist
+pub fn parse_tracking_policy_params(params: &String) -> TrackingPolicy {
+    let mut policy = TRACKING_POLICY_WHITELIST;
+    let mut min_eth_locked = BigDecimal::from(10 as i32);
+    for entry in params.split(";") {
+        let (name, value) = match entry.trim().split_once("=") {
+            None => continue,
+            Some((name, value)) => (name.trim(), value.trim()),
+        };
+        match name {
+            "policy" => policy = value,
+            "min_eth_locked" => {
+                if let Ok(eth_locked) = BigDecimal::from_str(value) {
+                    min_eth_locked = eth_locked;
+                }
+            }
+            _ => continue,
+        }
+    }
+
+    match policy {
+        TRACKING_POLICY_STABLECOIN => TrackingPolicy::StablecoinAnchored,
+        TRACKING_POLICY_MIN_LIQUIDITY => TrackingPolicy::MinLiquidity(min_eth_locked),
+        _ => TrackingPolicy::Whitelist,
+    }
+}
+
+// ETH locked by the thinnest side of the pool at the ordinal, from its native balances
+pub fn pool_eth_locked(
+    ordinal: u64,
+    pool_address: &String,
+    token0_id: &String,
+    token1_id: &String,
+    native_total_value_locked_store: &StoreGetBigDecimal,
+    eth_prices_store: &StoreGetBigDecimal,
+) -> BigDecimal {
+    let side_eth_locked = |token_id: &String| -> BigDecimal {
+        let balance = native_total_value_locked_store
+            .get_at(
+                ordinal,
+                keyer::pool_native_total_value_locked_token(pool_address, token_id),
+            )
+            .unwrap_or(BigDecimal::zero());
+        let derived_eth_price = eth_prices_store
+            .get_at(ordinal, keyer::token_eth_price(token_id))
+            .unwrap_or(BigDecimal::zero());
+        balance.mul(derived_eth_price)
+    };
+
+    let eth_locked0 = side_eth_locked(token0_id);
+    let eth_locked1 = side_eth_locked(token1_id);
+    match eth_locked0.lt(&eth_locked1) {
+        true => eth_locked0,
+        false => eth_locked1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WETH: &str = "c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+    const USDC: &str = "a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+    const UNLISTED: &str = "1f9840a85d5af5bf1d1762f925bdaddc4201f984";
+
+    fn decimal(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    fn tracked(
+        policy: &TrackingPolicy,
+        token0: &str,
+        token1: &str,
+        eth_locked: &str,
+    ) -> BigDecimal {
+        // 2 units of token0 at 1500 USD, 3000 units of token1 at 0.99 USD
+        policy.tracked_amount_usd(
+            &token0.to_string(),
+            &token1.to_string(),
+            &decimal("2"),
+            &decimal("3000"),
+            &decimal("1500"),
+            &decimal("0.99"),
+            &decimal(eth_locked),
+        )
+    }
+
+    #[test]
+    fn test_whitelist_both_tokens() {
+        let amount = tracked(&TrackingPolicy::Whitelist, WETH, USDC, "0");
+        assert_eq!(decimal("5970"), amount);
+    }
+
+    #[test]
+    fn test_whitelist_token0_only() {
+        let amount = tracked(&TrackingPolicy::Whitelist, WETH, UNLISTED, "0");
+        assert_eq!(decimal("6000"), amount);
+    }
+
+    #[test]
+    fn test_whitelist_token1_only() {
+        let amount = tracked(&TrackingPolicy::Whitelist, UNLISTED, USDC, "0");
+        assert_eq!(decimal("5940"), amount);
+    }
+
+    #[test]
+    fn test_whitelist_no_token() {
+        let amount = tracked(&TrackingPolicy::Whitelist, UNLISTED, UNLISTED, "0");
+        assert_eq!(BigDecimal::zero(), amount);
+    }
+
+    #[test]
+    fn test_whitelist_tracked_amounts_by_token() {
+        let (tracked0, tracked1) = TrackingPolicy::Whitelist.tracked_amounts_usd(
+            &UNLISTED.to_string(),
+            &USDC.to_string(),
+            &decimal("2"),
+            &decimal("3000"),
+            &decimal("1500"),
+            &decimal("0.99"),
+            &BigDecimal::zero(),
+        );
+        assert_eq!(decimal("2970"), tracked0);
+        assert_eq!(decimal("2970"), tracked1);
+    }
+
+    #[test]
+    fn test_stablecoin_anchored_values_stablecoins_at_one_usd() {
+        let amount = tracked(&TrackingPolicy::StablecoinAnchored, WETH, USDC, "0");
+        assert_eq!(decimal("6000"), amount);
+
+        let amount = tracked(&TrackingPolicy::StablecoinAnchored, UNLISTED, USDC, "0");
+        assert_eq!(decimal("6000"), amount);
+    }
+
+    #[test]
+    fn test_stablecoin_anchored_keeps_derived_price_of_other_tokens() {
+        let amount = tracked(&TrackingPolicy::StablecoinAnchored, WETH, UNLISTED, "0");
+        assert_eq!(decimal("6000"), amount);
+
+        let amount = tracked(&TrackingPolicy::StablecoinAnchored, UNLISTED, UNLISTED, "0");
+        assert_eq!(BigDecimal::zero(), amount);
+    }
+
+    #[test]
+    fn test_min_liquidity_below_minimum() {
+        let policy = TrackingPolicy::MinLiquidity(decimal("10"));
+        assert_eq!(BigDecimal::zero(), tracked(&policy, WETH, USDC, "9.5"));
+    }
+
+    #[test]
+    fn test_min_liquidity_above_minimum() {
+        let policy = TrackingPolicy::MinLiquidity(decimal("10"));
+        assert_eq!(decimal("5970"), tracked(&policy, WETH, USDC, "10"));
+        assert_eq!(
+            BigDecimal::zero(),
+            tracked(&policy, UNLISTED, UNLISTED, "10")
+        );
+    }
+
+    #[test]
+    fn test_tracking_policy_proto_round_trip() {
+        for policy in [
+            TrackingPolicy::Whitelist,
+            TrackingPolicy::StablecoinAnchored,
+            TrackingPolicy::MinLiquidity(decimal("25")),
+        ] {
+            let proto: uniswap::TrackingPolicy = policy.clone().into();
+            assert_eq!(policy, TrackingPolicy::from(proto));
+        }
+    }
+
+    #[test]
+    fn test_parse_tracking_policy_params() {
+        assert_eq!(
+            TrackingPolicy::Whitelist,
+            parse_tracking_policy_params(&"".to_string())
+        );
+        assert_eq!(
+            TrackingPolicy::StablecoinAnchored,
+            parse_tracking_policy_params(&"policy=stablecoin".to_string())
+        );
+        assert_eq!(
+            TrackingPolicy::MinLiquidity(decimal("25")),
+            parse_tracking_policy_params(&"policy=min_liquidity;min_eth_locked=25".to_string())
+        );
+    }
+}
@@ -3,15 +3,20 @@ use crate::pb::PositionEvent;
 use crate::uniswap::position::PositionType;
 use crate::uniswap::Transaction;
 use crate::{
-    keyer, rpc, Erc20Token, Pool, PoolLiquidity, Position, StorageChange, WHITELIST_TOKENS,
+    keyer, math, Erc20Token, Pool, PoolLiquidity, PoolObservation, PoolSqrtPrice, Position,
+    PositionStatic, StorageChange, TokenTransferBehavior,
 };
 
+use crate::uniswap::token_transfer_behavior::Type as TransferBehaviorType;
 use crate::uniswap::BigInt as PbBigInt;
 use std::ops::{Add, Mul};
 use std::str;
 use substreams::scalar::{BigDecimal, BigInt};
-use substreams::store::{StoreGet, StoreGetProto};
+use substreams::store::{
+    DeltaBigInt, Deltas, Operation, StoreGet, StoreGetBigDecimal, StoreGetBigInt, StoreGetProto,
+};
 use substreams::{hex, log, Hex};
+use tiny_keccak::{Hasher, Keccak};
 
 pub const UNISWAP_V3_FACTORY: [u8; 20] = hex!("1f98431c8ad98523631ae4a59f267346ea31f984");
 pub const ZERO_ADDRESS: [u8; 20] = hex!("0000000000000000000000000000000000000000");
@@ -90,6 +95,19 @@ pub fn get_static_uniswap_tokens(token_address: &[u8]) -> Option<Erc20Token> {
     }
 }
 
+// a token is first seen in a block when `store_known_tokens` recorded that very
+// block for it, any other value means its metadata was fetched in an earlier block
+pub fn is_first_seen_token(
+    token_address: &String,
+    block_number: u64,
+    known_tokens_store: &StoreGetBigInt,
+) -> bool {
+    match known_tokens_store.get_last(keyer::token_key(token_address)) {
+        None => true,
+        Some(first_seen_block) => first_seen_block.to_u64() == block_number,
+    }
+}
+
 pub fn extract_pool_liquidity(
     log_ordinal: u64,
     pool_address: &Vec<u8>,
@@ -109,6 +127,49 @@ pub fn extract_pool_liquidity(
     None
 }
 
+// feeGrowthGlobal0X128 and feeGrowthGlobal1X128 live in slots 1 and 2 of the pool storage
+pub fn fee_growth_global_x128_token(storage_key: &Vec<u8>) -> Option<&'static str> {
+    if storage_key.len() != 32 || storage_key[..31].iter().any(|byte| *byte != 0) {
+        return None;
+    }
+
+    return match storage_key[31] {
+        1 => Some("token0"),
+        2 => Some("token1"),
+        _ => None,
+    };
+}
+
+// the ticks mapping is slot 5 of the pool storage, feeGrowthOutside0X128 and
+// feeGrowthOutside1X128 being the second and third slots of Tick.Info
+pub fn tick_fee_growth_outside_x128_slots(tick_idx: &BigInt) -> Vec<(String, &'static str)> {
+    let tick = tick_idx.to_string().parse::<i64>().unwrap();
+    let mut preimage = [0u8; 64];
+    if tick < 0 {
+        preimage[..24].fill(0xff);
+    }
+    preimage[24..32].copy_from_slice(&tick.to_be_bytes());
+    preimage[63] = 5;
+
+    let mut info_slot = [0u8; 32];
+    let mut hasher = Keccak::v256();
+    hasher.update(&preimage);
+    hasher.finalize(&mut info_slot);
+
+    let slot_at = |offset: u16| -> String {
+        let mut slot = info_slot.clone();
+        let mut carry = offset;
+        for byte in slot.iter_mut().rev() {
+            let sum = *byte as u16 + carry;
+            *byte = sum as u8;
+            carry = sum >> 8;
+        }
+        Hex(&slot).to_string()
+    };
+
+    return vec![(slot_at(1), "token0"), (slot_at(2), "token1")];
+}
+
 pub fn calculate_amount_usd(
     amount0: &BigDecimal,
     amount1: &BigDecimal,
@@ -132,54 +193,190 @@ pub fn calculate_amount_usd(
         );
 }
 
-pub fn get_tracked_amount_usd(
-    token0_id: &String,
-    token1_id: &String,
-    token0_derived_eth_price: &BigDecimal,
-    token1_derived_eth_price: &BigDecimal,
-    amount0_abs: &BigDecimal,
-    amount1_abs: &BigDecimal,
-    eth_price_in_usd: &BigDecimal,
+// values an amount of a single token at the prices known at the given ordinal
+pub fn token_amount_usd(
+    amount: &BigDecimal,
+    token_address: &String,
+    ordinal: u64,
+    eth_prices_store: &StoreGetBigDecimal,
 ) -> BigDecimal {
-    let price0_usd = token0_derived_eth_price
-        .clone()
-        .mul(eth_price_in_usd.clone());
-    let price1_usd = token1_derived_eth_price
-        .clone()
-        .mul(eth_price_in_usd.clone());
+    let derived_eth_price = eth_prices_store
+        .get_at(ordinal, keyer::token_eth_price(token_address))
+        .unwrap_or(BigDecimal::zero());
+    let bundle_eth_price = eth_prices_store
+        .get_at(ordinal, keyer::bundle_eth_price())
+        .unwrap_or(BigDecimal::zero());
 
-    // both are whitelist tokens, return sum of both amounts
-    if WHITELIST_TOKENS.contains(&token0_id.as_str())
-        && WHITELIST_TOKENS.contains(&token0_id.as_str())
-    {
-        return amount0_abs
-            .clone()
-            .mul(price0_usd)
-            .add(amount1_abs.clone().mul(price1_usd));
+    amount.clone().mul(derived_eth_price).mul(bundle_eth_price)
+}
+
+// compares what the Transfer logs moved in or out of a pool for a token with
+// what the pool events accounted for, in the same transaction. Only a fee taken
+// on transfer shows in the logs: a rebase emits no Transfer at all, and the pool
+// receiving more than it accounted for is a donation, not a token behavior
+pub fn classify_transfer_behavior(
+    transfer_amount: &BigInt,
+    event_amount: &BigInt,
+) -> TransferBehaviorType {
+    // the pool received less (or sent more) than what it accounted for,
+    // the token took a cut on the way
+    if transfer_amount.lt(event_amount) {
+        return TransferBehaviorType::FeeOnTransfer;
     }
 
-    // take double value of the whitelisted token amount
-    if WHITELIST_TOKENS.contains(&token0_id.as_str())
-        && !WHITELIST_TOKENS.contains(&token1_id.as_str())
-    {
-        return amount0_abs
-            .clone()
-            .mul(price0_usd)
-            .mul(BigDecimal::from(2 as i32));
+    TransferBehaviorType::Standard
+}
+
+// a fee-on-transfer token holds less in its pools than their events account for
+pub fn is_fee_on_transfer(
+    ordinal: u64,
+    token_address: &String,
+    transfer_behaviors_store: &StoreGetProto<TokenTransferBehavior>,
+) -> bool {
+    match transfer_behaviors_store.get_at(ordinal, keyer::token_transfer_behavior(token_address)) {
+        None => false,
+        Some(transfer_behavior) => {
+            transfer_behavior.r#type == TransferBehaviorType::FeeOnTransfer as i32
+        }
     }
+}
 
-    // take double value of the whitelisted token amount
-    if !WHITELIST_TOKENS.contains(&token0_id.as_str())
-        && WHITELIST_TOKENS.contains(&token1_id.as_str())
-    {
-        return amount1_abs
-            .clone()
-            .mul(price1_usd)
-            .mul(BigDecimal::from(2 as i32));
+pub const OTHER_INTERFACE: &str = "Other";
+
+// the registry is given as `label=address,address;label=address`
+pub fn parse_interface_registry(params: &String) -> Vec<(String, String)> {
+    let mut registry = vec![];
+    for entry in params.split(";") {
+        let (label, addresses) = match entry.trim().split_once("=") {
+            None => continue,
+            Some(entry) => entry,
+        };
+        for address in addresses.split(",") {
+            let address = address.trim().trim_start_matches("0x").to_lowercase();
+            if address.is_empty() {
+                continue;
+            }
+            registry.push((address, label.trim().to_string()));
+        }
+    }
+    registry
+}
+
+// the intervals are given as `1m,5m,1h`, with an s, m, h or d unit
+pub fn parse_candle_intervals(params: &String) -> Vec<(String, u64)> {
+    let mut intervals = vec![];
+    for interval in params.split(",") {
+        let interval = interval.trim();
+        if interval.len() < 2 {
+            continue;
+        }
+        let (count, unit) = interval.split_at(interval.len() - 1);
+        let unit_seconds: u64 = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            "d" => 86400,
+            _ => continue,
+        };
+        let count = match count.parse::<u64>() {
+            Ok(count) if count > 0 => count,
+            _ => continue,
+        };
+        intervals.push((interval.to_string(), count * unit_seconds));
+    }
+    intervals
+}
+
+// timestamps of the previous and of the current block, out of the store_candles_timestamp
+// deltas, the first block being its own previous block
+pub fn candle_timestamps(timestamp_deltas: &Deltas<DeltaBigInt>) -> Option<(u64, u64)> {
+    let delta = timestamp_deltas
+        .deltas
+        .iter()
+        .find(|delta| delta.key == keyer::candles_timestamp())?;
+    let current = delta.new_value.to_u64();
+    if delta.operation == Operation::Create {
+        return Some((current, current));
+    }
+    Some((delta.old_value.to_u64(), current))
+}
+
+pub const TWAP_WINDOWS: [(&str, u64); 4] =
+    [("5m", 300), ("30m", 1800), ("1h", 3600), ("24h", 86400)];
+
+// observationIndex, observationCardinality and observationCardinalityNext are packed in slot0
+// above sqrtPriceX96 and tick, at bits 184, 200 and 216
+pub fn slot0_observation_state(storage_key: &Vec<u8>, value: &Vec<u8>) -> Option<[u64; 3]> {
+    if storage_key.len() != 32 || storage_key.iter().any(|byte| *byte != 0) || value.len() > 32 {
+        return None;
+    }
+
+    let mut slot = [0u8; 32];
+    slot[32 - value.len()..].copy_from_slice(value);
+    let field = |start: usize| -> u64 { u16::from_be_bytes([slot[start], slot[start + 1]]) as u64 };
+    Some([field(7), field(5), field(3)])
+}
+
+// observations are appended as `timestamp:tickCumulative:previousTimestamp:previousTick`
+pub fn encode_observation(observation: &PoolObservation) -> String {
+    format!(
+        "{}:{}:{}:{}",
+        observation.timestamp,
+        BigInt::from(observation.tick_cumulative.as_ref().unwrap()),
+        observation.previous_timestamp,
+        BigInt::from(observation.previous_tick.as_ref().unwrap()),
+    )
+}
+
+pub fn decode_observation(observation: &String) -> Option<(u64, i64, u64, i64)> {
+    let mut fields = observation.split(":");
+    Some((
+        fields.next()?.parse().ok()?,
+        fields.next()?.parse().ok()?,
+        fields.next()?.parse().ok()?,
+        fields.next()?.parse().ok()?,
+    ))
+}
+
+// tickCumulative at `timestamp`, extrapolated back from the first observation written after it,
+// the tick having been the previous tick of that observation in between
+pub fn tick_cumulative_at(observations: &Vec<(u64, i64, u64, i64)>, timestamp: u64) -> Option<i64> {
+    let (observed_at, tick_cumulative, previous_timestamp, previous_tick) = observations
+        .iter()
+        .find(|(observed_at, _, _, _)| *observed_at > timestamp)?;
+    if *previous_timestamp == 0 || *previous_timestamp > timestamp {
+        return None;
     }
+    Some(tick_cumulative - previous_tick * (observed_at - timestamp) as i64)
+}
 
-    // neither token is on white list, tracked amount is 0
-    return BigDecimal::from(0 as i32);
+// a swap is attributed to the registered contract the transaction was sent to, or else to the
+// first registered contract called from it, which covers smart wallets and multisigs
+pub fn classify_interface(
+    transaction_trace: &TransactionTrace,
+    registry: &Vec<(String, String)>,
+) -> String {
+    let find_label = |address: &Vec<u8>| -> Option<String> {
+        let address = Hex(address).to_string();
+        registry
+            .iter()
+            .find(|(registered, _)| registered.eq(&address))
+            .map(|(_, label)| label.clone())
+    };
+
+    if let Some(label) = find_label(&transaction_trace.to) {
+        return label;
+    }
+    for call in transaction_trace.calls.iter() {
+        if call.depth != 1 {
+            continue;
+        }
+        if let Some(label) = find_label(&call.address) {
+            return label;
+        }
+    }
+
+    OTHER_INTERFACE.to_string()
 }
 
 pub fn load_transaction(
@@ -206,9 +403,76 @@ pub fn load_transaction(
     transaction
 }
 
+// the fee growth inside the range as of the given ordinal, None until the pool has a price
+pub fn get_fee_growth_inside_x128(
+    ordinal: u64,
+    pool_address: &String,
+    tick_lower: &BigInt,
+    tick_upper: &BigInt,
+    tick_fee_growth_outside_store: &StoreGetBigInt,
+    pool_sqrt_price_store: &StoreGetProto<PoolSqrtPrice>,
+    pool_fee_growth_global_x128_store: &StoreGetBigInt,
+) -> Option<(BigInt, BigInt)> {
+    let tick_current = pool_sqrt_price_store
+        .get_at(ordinal, keyer::pool_sqrt_price_key(pool_address))?
+        .tick();
+
+    // a tick never written in the pool storage has no fee growth outside
+    let fee_growth_outside_x128 = |tick_idx: &BigInt| -> (BigInt, BigInt) {
+        let tick_id = format!("{}#{}", pool_address, tick_idx);
+        let token_fee_growth_outside_x128 = |token: &str| -> BigInt {
+            tick_fee_growth_outside_store
+                .get_at(
+                    ordinal,
+                    keyer::tick_fee_growth_outside_x128(&tick_id, token),
+                )
+                .unwrap_or(BigInt::zero())
+        };
+        (
+            token_fee_growth_outside_x128("token0"),
+            token_fee_growth_outside_x128("token1"),
+        )
+    };
+    let (lower_fee_growth_outside_0_x128, lower_fee_growth_outside_1_x128) =
+        fee_growth_outside_x128(tick_lower);
+    let (upper_fee_growth_outside_0_x128, upper_fee_growth_outside_1_x128) =
+        fee_growth_outside_x128(tick_upper);
+
+    let fee_growth_global_x128 = |token: &str| -> BigInt {
+        pool_fee_growth_global_x128_store
+            .get_at(
+                ordinal,
+                keyer::pool_fee_growth_global_x128(pool_address, token.to_string()),
+            )
+            .unwrap_or(BigInt::zero())
+    };
+
+    return Some((
+        math::fee_growth_inside_x128(
+            &tick_current,
+            tick_lower,
+            tick_upper,
+            &fee_growth_global_x128("token0"),
+            &lower_fee_growth_outside_0_x128,
+            &upper_fee_growth_outside_0_x128,
+        ),
+        math::fee_growth_inside_x128(
+            &tick_current,
+            tick_lower,
+            tick_upper,
+            &fee_growth_global_x128("token1"),
+            &lower_fee_growth_outside_1_x128,
+            &upper_fee_growth_outside_1_x128,
+        ),
+    ));
+}
+
 pub fn get_position(
+    position_static_store: &StoreGetProto<PositionStatic>,
     store_pool: &StoreGetProto<Pool>,
-    log_address: &String,
+    tick_fee_growth_outside_store: &StoreGetBigInt,
+    pool_sqrt_price_store: &StoreGetProto<PoolSqrtPrice>,
+    pool_fee_growth_global_x128_store: &StoreGetBigInt,
     transaction_hash: &Vec<u8>,
     position_type: PositionType,
     log_ordinal: u64,
@@ -216,55 +480,252 @@ pub fn get_position(
     block_number: u64,
     event: PositionEvent,
 ) -> Option<Position> {
-    if let Some(positions_call_result) = rpc::positions_call(log_address, event.get_token_id()) {
-        let token_id_0_bytes = positions_call_result.0;
-        let token_id_1_bytes = positions_call_result.1;
-        let fee = positions_call_result.2;
-        let tick_lower: BigInt = positions_call_result.3.into();
-        let tick_upper: BigInt = positions_call_result.4.into();
-        let fee_growth_inside_0_last_x128: BigInt = positions_call_result.5.into();
-        let fee_growth_inside_1_last_x128: BigInt = positions_call_result.6.into();
-
-        let token0: String = Hex(&token_id_0_bytes.as_slice()).to_string();
-        let token1: String = Hex(&token_id_1_bytes.as_slice()).to_string();
-
-        let pool: Pool =
-            match store_pool.get_last(keyer::pool_token_index_key(&token0, &token1, fee.into())) {
-                None => {
-                    log::info!(
-                        "pool does not exist for token0 {} and token1 {}",
-                        token0,
-                        token1
-                    );
-                    return None;
-                }
-                Some(pool) => pool,
-            };
-
-        let amount0 = event.get_amount0().to_decimal(pool.token0_ref().decimals);
-        let amount1 = event.get_amount1().to_decimal(pool.token1_ref().decimals);
-
-        return Some(Position {
-            id: event.get_token_id().to_string(),
-            owner: Hex(ZERO_ADDRESS).to_string(),
-            pool: pool.address.clone(),
-            token0,
-            token1,
-            tick_lower: format!("{}#{}", pool.address, tick_lower.to_string()),
-            tick_upper: format!("{}#{}", pool.address, tick_upper.to_string()),
-            transaction: Hex(&transaction_hash).to_string(),
-            fee_growth_inside_0_last_x_128: Some(fee_growth_inside_0_last_x128.into()),
-            fee_growth_inside_1_last_x_128: Some(fee_growth_inside_1_last_x128.into()),
-            liquidity: Some(PbBigInt {
-                value: event.get_liquidity(),
-            }),
-            amount0: Some(amount0.into()),
-            amount1: Some(amount1.into()),
-            position_type: position_type as i32,
+    let position_static = match position_static_store
+        .get_last(keyer::position_static(&event.get_token_id().to_string()))
+    {
+        None => {
+            log::info!(
+                "no pool mint found for position {}",
+                event.get_token_id().to_string()
+            );
+            return None;
+        }
+        Some(position_static) => position_static,
+    };
+
+    let pool: Pool = match store_pool.get_last(keyer::pool_key(&position_static.pool)) {
+        None => {
+            log::info!("pool {} does not exist", position_static.pool);
+            return None;
+        }
+        Some(pool) => pool,
+    };
+
+    let tick_lower: BigInt = position_static.tick_lower.unwrap().into();
+    let tick_upper: BigInt = position_static.tick_upper.unwrap().into();
+    let (fee_growth_inside_0_last_x128, fee_growth_inside_1_last_x128) =
+        match get_fee_growth_inside_x128(
             log_ordinal,
-            timestamp,
-            block_number,
-        });
+            &pool.address,
+            &tick_lower,
+            &tick_upper,
+            tick_fee_growth_outside_store,
+            pool_sqrt_price_store,
+            pool_fee_growth_global_x128_store,
+        ) {
+            None => {
+                log::info!("pool {} has no price yet", pool.address);
+                return None;
+            }
+            Some(fee_growth_inside_x128) => fee_growth_inside_x128,
+        };
+
+    let amount0 = event.get_amount0().to_decimal(pool.token0_ref().decimals);
+    let amount1 = event.get_amount1().to_decimal(pool.token1_ref().decimals);
+
+    return Some(Position {
+        id: event.get_token_id().to_string(),
+        owner: Hex(ZERO_ADDRESS).to_string(),
+        pool: pool.address.clone(),
+        token0: position_static.token0,
+        token1: position_static.token1,
+        tick_lower: format!("{}#{}", pool.address, tick_lower.to_string()),
+        tick_upper: format!("{}#{}", pool.address, tick_upper.to_string()),
+        transaction: Hex(&transaction_hash).to_string(),
+        fee_growth_inside_0_last_x_128: Some(fee_growth_inside_0_last_x128.into()),
+        fee_growth_inside_1_last_x_128: Some(fee_growth_inside_1_last_x128.into()),
+        liquidity: Some(PbBigInt {
+            value: event.get_liquidity(),
+        }),
+        amount0: Some(amount0.into()),
+        amount1: Some(amount1.into()),
+        position_type: position_type as i32,
+        log_ordinal,
+        timestamp,
+        block_number,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_fee_growth_outside_x128_slots_tick_zero() {
+        assert_eq!(
+            vec![
+                (
+                    "05b8ccbb9d4d8fb16ea74ce3c29a41f1b461fbdaff4714a0d9a8eb05499746bd".to_string(),
+                    "token0"
+                ),
+                (
+                    "05b8ccbb9d4d8fb16ea74ce3c29a41f1b461fbdaff4714a0d9a8eb05499746be".to_string(),
+                    "token1"
+                ),
+            ],
+            tick_fee_growth_outside_x128_slots(&BigInt::from(0))
+        );
+    }
+
+    #[test]
+    fn test_tick_fee_growth_outside_x128_slots_positive_tick() {
+        assert_eq!(
+            vec![
+                (
+                    "0623d507dd36662ab3410d18108f709279bcf55cc514e5b24bb1718ebc54e517".to_string(),
+                    "token0"
+                ),
+                (
+                    "0623d507dd36662ab3410d18108f709279bcf55cc514e5b24bb1718ebc54e518".to_string(),
+                    "token1"
+                ),
+            ],
+            tick_fee_growth_outside_x128_slots(&BigInt::from(201180))
+        );
+    }
+
+    #[test]
+    fn test_tick_fee_growth_outside_x128_slots_negative_tick() {
+        // the int24 key is sign extended to 32 bytes
+        assert_eq!(
+            vec![
+                (
+                    "7f16e4ac80e3195175c78aa64fe305d939ea0f7e52dc9181b922712fa7117c1a".to_string(),
+                    "token0"
+                ),
+                (
+                    "7f16e4ac80e3195175c78aa64fe305d939ea0f7e52dc9181b922712fa7117c1b".to_string(),
+                    "token1"
+                ),
+            ],
+            tick_fee_growth_outside_x128_slots(&BigInt::from(-887220))
+        );
+    }
+
+    #[test]
+    fn test_slot0_observation_state() {
+        // sqrtPriceX96 1906627189879578071735963003040891, tick 201180, observationIndex 412,
+        // observationCardinality 723, observationCardinalityNext 723, unlocked
+        let value = hex::decode("00010002d302d3019c0311dc0000000000005e0104b6d5b88bc1c22e5c92b47b")
+            .unwrap();
+
+        assert_eq!(
+            Some([412, 723, 723]),
+            slot0_observation_state(&vec![0u8; 32], &value)
+        );
+    }
+
+    #[test]
+    fn test_slot0_observation_state_negative_tick() {
+        // sqrtPriceX96 2^96, tick -887220, observationIndex 0, observationCardinality 1,
+        // observationCardinalityNext 1, unlocked
+        let value = hex::decode("000100000100010000f2764c0000000000000001000000000000000000000000")
+            .unwrap();
+
+        assert_eq!(
+            Some([0, 1, 1]),
+            slot0_observation_state(&vec![0u8; 32], &value)
+        );
+    }
+
+    #[test]
+    fn test_slot0_observation_state_other_slot() {
+        let mut storage_key = vec![0u8; 32];
+        storage_key[31] = 1;
+
+        assert_eq!(None, slot0_observation_state(&storage_key, &vec![0u8; 32]));
+    }
+
+    #[test]
+    fn test_encode_decode_observation() {
+        let observation = PoolObservation {
+            pool_address: "88e6a0c2ddd26feeb64f039a2c41296fcb3f5640".to_string(),
+            timestamp: 1620158000,
+            tick_cumulative: Some(BigInt::from(-5634000).into()),
+            tick: Some(BigInt::from(-20).into()),
+            previous_timestamp: 1620157988,
+            previous_tick: Some(BigInt::from(-25).into()),
+            ordinal: 12,
+        };
+
+        assert_eq!(
+            Some((1620158000, -5634000, 1620157988, -25)),
+            decode_observation(&encode_observation(&observation))
+        );
+    }
+
+    #[test]
+    fn test_decode_observation_missing_field() {
+        assert_eq!(
+            None,
+            decode_observation(&"1620158000:-5634000:1620157988".to_string())
+        );
+    }
+
+    #[test]
+    fn test_tick_cumulative_at() {
+        let observations = vec![(1000, 5000, 900, 10), (1100, 6000, 1000, 10)];
+
+        // the tick was 10 from 900 until the observation at 1000
+        assert_eq!(Some(4500), tick_cumulative_at(&observations, 950));
+        assert_eq!(Some(5000), tick_cumulative_at(&observations, 1000));
+    }
+
+    #[test]
+    fn test_tick_cumulative_at_negative_tick() {
+        let observations = vec![(1000, -5000, 900, -10)];
+
+        assert_eq!(Some(-4500), tick_cumulative_at(&observations, 950));
+    }
+
+    #[test]
+    fn test_tick_cumulative_at_out_of_range() {
+        let observations = vec![(1000, 5000, 900, 10), (1100, 6000, 1000, 10)];
+
+        // before the first observation's previous update
+        assert_eq!(None, tick_cumulative_at(&observations, 850));
+        // after the last observation
+        assert_eq!(None, tick_cumulative_at(&observations, 1100));
+    }
+
+    #[test]
+    fn test_tick_cumulative_at_pool_initialization() {
+        let observations = vec![(1000, 0, 0, 0)];
+
+        assert_eq!(None, tick_cumulative_at(&observations, 950));
+    }
+
+    #[test]
+    fn test_classify_transfer_behavior_standard() {
+        assert_eq!(
+            TransferBehaviorType::Standard,
+            classify_transfer_behavior(&BigInt::from(100), &BigInt::from(100))
+        );
+        assert_eq!(
+            TransferBehaviorType::Standard,
+            classify_transfer_behavior(&BigInt::from(-100), &BigInt::from(-100))
+        );
+    }
+
+    #[test]
+    fn test_classify_transfer_behavior_fee_on_transfer() {
+        // the pool received less than it accounted for
+        assert_eq!(
+            TransferBehaviorType::FeeOnTransfer,
+            classify_transfer_behavior(&BigInt::from(98), &BigInt::from(100))
+        );
+        // the pool sent more than it accounted for
+        assert_eq!(
+            TransferBehaviorType::FeeOnTransfer,
+            classify_transfer_behavior(&BigInt::from(-102), &BigInt::from(-100))
+        );
+    }
+
+    #[test]
+    fn test_classify_transfer_behavior_donation() {
+        assert_eq!(
+            TransferBehaviorType::Standard,
+            classify_transfer_behavior(&BigInt::from(150), &BigInt::from(100))
+        );
     }
-    return None;
 }